@@ -280,24 +280,28 @@ fn render_esf_alg_status(frame: &mut Frame, area: Rect, app: &mut App) {
         EsfStatusInsInit::Off => "OFF",
         EsfStatusInsInit::Initialized => "INITIALIZED",
         EsfStatusInsInit::Initializing => "INITIALIZING",
+        _ => "UNKNOWN",
     };
 
     let imu_status = match app.esf_alg_state.imu_status {
         EsfStatusImuInit::Off => "OFF",
         EsfStatusImuInit::Initialized => "INITIALIZED",
         EsfStatusImuInit::Initializing => "INITIALIZING",
+        _ => "UNKNOWN",
     };
 
     let wt_status = match app.esf_alg_state.wheel_tick_sensor_status {
         EsfStatusWheelTickInit::Off => "OFF",
         EsfStatusWheelTickInit::Initialized => "INITIALIZED",
         EsfStatusWheelTickInit::Initializing => "INITIALIZING",
+        _ => "UNKNOWN",
     };
 
     let mount_angle_status = match app.esf_alg_state.imu_mount_alignment_status {
         EsfStatusMountAngle::Off => "OFF",
         EsfStatusMountAngle::Initialized => "INITIALIZED",
         EsfStatusMountAngle::Initializing => "INITIALIZING",
+        _ => "UNKNOWN",
     };
 
     let rows = [
@@ -0,0 +1,277 @@
+//! Offline AssistNow: capture of navigation data from one receiver and
+//! flow-controlled injection as MGA messages into another one, and loading
+//! of AssistNow Online and Offline files.
+//!
+//! The injector waits for a MGA-ACK after every message, the receiver only sends
+//! these with `ackAiding` enabled (CFG-NAVX5 or CFG-NAVSPG-ACKAIDING).
+
+use std::{collections::BTreeMap, path::Path, time::Duration};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use ublox::{
+    navmsg::{NavDecoder, NavMessage},
+    GnssId, MgaIniPosLlhBuilder, MgaIniTimeUtcBuilder, MsgAckInfoCode, PacketRef, Parser, Position,
+    UbxUnknownPacketRef,
+};
+
+use crate::Device;
+
+/// Time to wait for the MGA-ACK of a message
+const ACK_TIMEOUT: Duration = Duration::from_secs(1);
+
+const MGA_CLASS: u8 = 0x13;
+const MGA_ANO: u8 = 0x20;
+const MGA_INI: u8 = 0x40;
+
+/// Collects the latest ephemerides and ionosphere model from RXM-SFRBX frames
+#[derive(Debug, Default)]
+pub struct AssistCapture {
+    decoder: NavDecoder,
+    /// Latest MGA frame by (gnss_id, sv_id)
+    frames: BTreeMap<(u8, u8), Vec<u8>>,
+}
+
+impl AssistCapture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of captured MGA frames
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// The captured MGA frames, ionosphere model first
+    pub fn frames(&self) -> impl Iterator<Item = &[u8]> {
+        self.frames.values().map(Vec::as_slice)
+    }
+
+    fn add(&mut self, message: &NavMessage) {
+        let key = match message {
+            NavMessage::Ephemeris(eph) => (eph.gnss as u8, eph.sv_id),
+            NavMessage::GlonassEphemeris(eph) => (GnssId::GLONASS as u8, eph.sv_id),
+            // Sorts before all satellites
+            NavMessage::Klobuchar(gnss, _) => (*gnss as u8, 0),
+            _ => return,
+        };
+        if let Some(frame) = message.to_mga() {
+            self.frames.insert(key, frame);
+        }
+    }
+}
+
+impl crate::UbxPacketHandler for AssistCapture {
+    fn handle(&mut self, packet: PacketRef<'_>) {
+        if let PacketRef::RxmSfrbx(frame) = packet {
+            match self.decoder.decode(&frame) {
+                Ok(messages) => messages.iter().for_each(|message| self.add(message)),
+                Err(e) => eprintln!("Invalid navigation frame: {e:?}"),
+            }
+        }
+    }
+}
+
+/// MGA-INI-TIME_UTC and, if known, MGA-INI-POS_LLH frames to send ahead of the ephemerides
+pub fn initial_frames(
+    time: DateTime<Utc>,
+    time_accuracy_ns: u64,
+    position: Option<(Position, f64)>,
+) -> Vec<Vec<u8>> {
+    let mut frames = vec![MgaIniTimeUtcBuilder::default()
+        .set_time(time, time_accuracy_ns)
+        .into_packet_bytes()
+        .to_vec()];
+    if let Some((position, accuracy)) = position {
+        frames.push(
+            MgaIniPosLlhBuilder::default()
+                .set_position(position, accuracy)
+                .into_packet_bytes()
+                .to_vec(),
+        );
+    }
+    frames
+}
+
+/// Identifies a message of an AssistNow file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MgaKey {
+    pub msg_id: u8,
+    /// Message type, the first payload byte
+    pub msg_type: u8,
+    /// GNSS of MGA-ANO, all other messages imply it by `msg_id`
+    pub gnss_id: u8,
+    /// Satellite, zero for messages not related to a single one
+    pub sv_id: u8,
+}
+
+/// AssistNow Online or Offline data, indexed by message and validity date
+#[derive(Debug, Clone, Default)]
+pub struct AssistNowData {
+    /// Frames by key and validity date, `None` for messages without one
+    frames: BTreeMap<MgaKey, BTreeMap<Option<NaiveDate>, Vec<u8>>>,
+}
+
+impl AssistNowData {
+    /// Parses concatenated UBX-MGA frames, as downloaded from the AssistNow services
+    pub fn from_bytes(data: &[u8]) -> anyhow::Result<Self> {
+        let mut frames: BTreeMap<MgaKey, BTreeMap<_, _>> = BTreeMap::new();
+        let mut parser = Parser::default();
+        let mut it = parser.consume(data);
+        while let Some(packet) = it.next() {
+            let packet = packet.map_err(|e| anyhow::anyhow!("Malformed AssistNow data: {e}"))?;
+            let (class, msg_id) = packet.class_and_msg_id();
+            let payload = packet.payload();
+            if class != MGA_CLASS || payload.is_empty() {
+                anyhow::bail!("Unexpected message 0x{class:02x} 0x{msg_id:02x}");
+            }
+            let (gnss_id, valid) = match &packet {
+                PacketRef::MgaAno(ano) => (ano.gnss_id(), ano.date()),
+                _ => (0, None),
+            };
+            let key = MgaKey {
+                msg_id,
+                msg_type: payload[0],
+                gnss_id,
+                sv_id: if msg_id == MGA_INI {
+                    0
+                } else {
+                    payload.get(2).copied().unwrap_or(0)
+                },
+            };
+
+            let frame = UbxUnknownPacketRef {
+                payload,
+                class,
+                msg_id,
+            }
+            .into_packet_vec();
+            frames.entry(key).or_default().insert(valid, frame);
+        }
+        if frames.is_empty() {
+            anyhow::bail!("No AssistNow data found");
+        }
+        Ok(Self { frames })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// Number of indexed messages
+    pub fn len(&self) -> usize {
+        self.frames.values().map(BTreeMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Dates covered by the MGA-ANO messages
+    pub fn dates(&self) -> Vec<NaiveDate> {
+        let mut dates: Vec<_> = self
+            .frames
+            .values()
+            .flat_map(|frames| frames.keys().flatten().copied())
+            .collect();
+        dates.sort();
+        dates.dedup();
+        dates
+    }
+
+    /// The messages relevant on `date`: MGA-ANO of that day and all messages
+    /// without a validity date, except MGA-INI as the time has to be current
+    pub fn select(&self, date: NaiveDate) -> Vec<&[u8]> {
+        self.frames
+            .iter()
+            .filter(|(key, _)| key.msg_id != MGA_INI)
+            .filter_map(|(key, frames)| match key.msg_id {
+                MGA_ANO => frames.get(&Some(date)),
+                _ => frames.get(&None),
+            })
+            .map(Vec::as_slice)
+            .collect()
+    }
+}
+
+/// Outcome of an injection
+#[derive(Debug, Default)]
+pub struct InjectReport {
+    /// Number of messages accepted by the receiver
+    pub accepted: usize,
+    /// Rejected messages with the reason given by the receiver
+    pub rejected: Vec<(Vec<u8>, MsgAckInfoCode)>,
+    /// Messages that were not acknowledged after all retries
+    pub unacknowledged: Vec<Vec<u8>>,
+}
+
+impl Device {
+    /// Sends the MGA `frames` one at a time, each after the previous one was acknowledged.
+    ///
+    /// A message is sent again up to `retries` times if its MGA-ACK does not arrive
+    /// in time or the receiver was not ready to process it.
+    pub fn inject_mga<'a>(
+        &mut self,
+        frames: impl IntoIterator<Item = &'a [u8]>,
+        retries: usize,
+    ) -> std::io::Result<InjectReport> {
+        let mut report = InjectReport::default();
+        for frame in frames {
+            let mut outcome = None;
+            for _ in 0..=retries {
+                outcome = self.send_and_wait_for_mga_ack(frame)?;
+                if !matches!(outcome, None | Some(MsgAckInfoCode::RejectedNotReady)) {
+                    break;
+                }
+            }
+            match outcome {
+                Some(MsgAckInfoCode::Accepted) => report.accepted += 1,
+                Some(code) => report.rejected.push((frame.to_vec(), code)),
+                None => report.unacknowledged.push(frame.to_vec()),
+            }
+        }
+        Ok(report)
+    }
+
+    /// Sends the AssistNow messages relevant at `time`, preceded by MGA-INI-TIME_UTC
+    pub fn inject_assistnow(
+        &mut self,
+        data: &AssistNowData,
+        time: DateTime<Utc>,
+        time_accuracy_ns: u64,
+        retries: usize,
+    ) -> std::io::Result<InjectReport> {
+        let initial = initial_frames(time, time_accuracy_ns, None);
+        let frames = data.select(time.date_naive());
+        self.inject_mga(initial.iter().map(Vec::as_slice).chain(frames), retries)
+    }
+
+    /// Writes a MGA `frame` and waits for its MGA-ACK.
+    ///
+    /// Returns `None` if the receiver did not answer in time.
+    fn send_and_wait_for_mga_ack(
+        &mut self,
+        frame: &[u8],
+    ) -> std::io::Result<Option<MsgAckInfoCode>> {
+        self.write_all(frame)?;
+
+        let mut answer = None;
+        let start = std::time::SystemTime::now();
+        while answer.is_none() {
+            self.on_data_available(|packet| match packet {
+                PacketRef::MgaAck(ack) if ack.is_ack_for(frame) => {
+                    answer = Some(ack.info_code());
+                },
+                _ => {},
+            })?;
+
+            if start.elapsed().unwrap() > ACK_TIMEOUT {
+                break;
+            }
+        }
+        Ok(answer)
+    }
+}
@@ -0,0 +1,117 @@
+//! Survey-in and fixed mode of RTK and timing base stations.
+//!
+//! The survey-in is started with CFG-TMODE3, or with the CFG-TMODE keys on
+//! receivers configured through CFG-VALSET. Its progress is followed with NAV-SVIN,
+//! polled once per second, and with TIM-SVIN on timing receivers that output it.
+//! Once the mean position is valid, the receiver is switched to fixed mode at
+//! that position.
+
+use std::time::{Duration, Instant};
+
+use ublox::{
+    CfgLayer, CfgValSetBuilder, FixedBase, NavSvin, PacketRef, SurveyIn, SurveyInStatus,
+    UbxPacketRequest,
+};
+
+use crate::Device;
+
+/// Interval of the NAV-SVIN polls
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How the time mode is written to the receiver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TmodeConfig {
+    /// UBX-CFG-TMODE3, for receivers before generation 9
+    Tmode3,
+    /// CFG-TMODE keys written to the RAM layer with UBX-CFG-VALSET
+    CfgVal,
+}
+
+impl Device {
+    /// Starts a survey-in with the given minimum duration and accuracy
+    pub fn start_survey_in(
+        &mut self,
+        survey: &SurveyIn,
+        config: TmodeConfig,
+    ) -> anyhow::Result<()> {
+        let packet = match config {
+            TmodeConfig::Tmode3 => survey.tmode3().into_packet_bytes().to_vec(),
+            TmodeConfig::CfgVal => cfg_val_set(&survey.cfg_vals()),
+        };
+        if !self.send_and_wait_for_ack(&packet)? {
+            anyhow::bail!("Receiver rejected the survey-in configuration");
+        }
+        Ok(())
+    }
+
+    /// Switches the receiver to fixed mode
+    pub fn set_fixed_base(&mut self, base: &FixedBase, config: TmodeConfig) -> anyhow::Result<()> {
+        let packet = match config {
+            TmodeConfig::Tmode3 => base.tmode3().into_packet_bytes().to_vec(),
+            TmodeConfig::CfgVal => cfg_val_set(&base.cfg_vals()),
+        };
+        if !self.send_and_wait_for_ack(&packet)? {
+            anyhow::bail!("Receiver rejected the fixed position");
+        }
+        Ok(())
+    }
+
+    /// Reports the survey-in progress to `progress` until the mean position is valid
+    pub fn wait_for_survey_in(
+        &mut self,
+        timeout: Option<Duration>,
+        mut progress: impl FnMut(&SurveyInStatus),
+    ) -> anyhow::Result<SurveyInStatus> {
+        let poll = UbxPacketRequest::request_for::<NavSvin>().into_packet_bytes();
+        let start = Instant::now();
+        let mut next_poll = start;
+        loop {
+            if Instant::now() >= next_poll {
+                self.write_all(&poll)?;
+                next_poll = Instant::now() + POLL_INTERVAL;
+            }
+
+            let mut status = None;
+            self.on_data_available(|packet| match packet {
+                PacketRef::NavSvin(svin) => status = Some(SurveyInStatus::from(&svin)),
+                PacketRef::TimSvin(svin) => status = Some(SurveyInStatus::from(&svin)),
+                _ => {},
+            })?;
+            if let Some(status) = status {
+                progress(&status);
+                if status.is_complete() {
+                    return Ok(status);
+                }
+            }
+
+            if timeout.is_some_and(|timeout| start.elapsed() > timeout) {
+                anyhow::bail!("Survey-in did not complete in time");
+            }
+        }
+    }
+
+    /// Runs a survey-in and switches to fixed mode at its mean position,
+    /// which is returned to be reused on the next start
+    pub fn survey_in_base(
+        &mut self,
+        survey: &SurveyIn,
+        config: TmodeConfig,
+        timeout: Option<Duration>,
+        progress: impl FnMut(&SurveyInStatus),
+    ) -> anyhow::Result<FixedBase> {
+        self.start_survey_in(survey, config)?;
+        let base = self.wait_for_survey_in(timeout, progress)?.fixed_base();
+        self.set_fixed_base(&base, config)?;
+        Ok(base)
+    }
+}
+
+fn cfg_val_set(items: &[ublox::cfg_val::CfgVal]) -> Vec<u8> {
+    CfgValSetBuilder {
+        version: 0,
+        layers: CfgLayer::RAM,
+        reserved1: 0,
+        cfg_data: items,
+    }
+    .into_packet_vec()
+}
@@ -0,0 +1,520 @@
+//! Backup, restore and comparison of the complete receiver configuration.
+//!
+//! Gen9 (and later) receivers are read back with a CFG-VALGET walk over all groups
+//! known by [CfgVal], older receivers by polling every CFG-* message.
+//! A backup is stored as a sequence of UBX frames that can be sent back as-is:
+//! CFG-VALSET frames for Gen9 receivers, the polled CFG-* frames otherwise.
+//! Items of the polled groups unknown to [CfgVal] are kept as raw key/value pairs.
+
+use std::{collections::BTreeMap, fmt, path::Path};
+
+use ublox::{
+    cfg_val::{CfgVal, CfgValDiff, KeyId},
+    CfgLayer, CfgValGetLayer, CfgValGetRequestBuilder, CfgValIter, CfgValSet, CfgValSetBuilder,
+    Parser, UbxPacketMeta, UbxUnknownPacketRef,
+};
+
+use crate::Device;
+
+const CFG_CLASS: u8 = 0x06;
+
+/// CFG-* messages polled with an empty payload
+const LEGACY_CFG_MESSAGES: &[(u8, &str)] = &[
+    (0x08, "CFG-RATE"),
+    (0x11, "CFG-RXM"),
+    (0x13, "CFG-ANT"),
+    (0x16, "CFG-SBAS"),
+    (0x17, "CFG-NMEA"),
+    (0x1e, "CFG-ODO"),
+    (0x23, "CFG-NAVX5"),
+    (0x24, "CFG-NAV5"),
+    (0x39, "CFG-ITFM"),
+    (0x3b, "CFG-PM2"),
+    (0x3d, "CFG-TMODE2"),
+    (0x3e, "CFG-GNSS"),
+    (0x56, "CFG-ESFALG"),
+    (0x5c, "CFG-HNR"),
+    (0x62, "CFG-SMGR"),
+    (0x69, "CFG-GEOFENCE"),
+    (0x70, "CFG-DGNSS"),
+    (0x71, "CFG-TMODE3"),
+    (0x82, "CFG-ESFWT"),
+    (0x86, "CFG-PMS"),
+];
+
+/// I/O ports polled with CFG-PRT: DDC, UART1, UART2, USB, SPI
+const LEGACY_PORTS: &[u8] = &[0, 1, 2, 3, 4];
+
+/// Protocols polled with CFG-INF: UBX, NMEA
+const LEGACY_INF_PROTOCOLS: &[u8] = &[0, 1];
+
+/// Time pulses polled with CFG-TP5: TIMEPULSE, TIMEPULSE2
+const LEGACY_TIME_PULSES: &[u8] = &[0, 1];
+
+/// Output messages whose rates are polled with CFG-MSG
+const LEGACY_OUTPUT_MESSAGES: &[(u8, u8)] = &[
+    // NMEA: GGA, GLL, GSA, GSV, RMC, VTG, GRS, GST, ZDA, GBS, DTM, GNS
+    (0xf0, 0x00),
+    (0xf0, 0x01),
+    (0xf0, 0x02),
+    (0xf0, 0x03),
+    (0xf0, 0x04),
+    (0xf0, 0x05),
+    (0xf0, 0x06),
+    (0xf0, 0x07),
+    (0xf0, 0x08),
+    (0xf0, 0x09),
+    (0xf0, 0x0a),
+    (0xf0, 0x0d),
+    // NAV: POSECEF, POSLLH, STATUS, DOP, ATT, PVT, ODO, VELECEF, VELNED, HPPOSECEF,
+    // HPPOSLLH, TIMEGPS, TIMEUTC, CLOCK, TIMELS, SAT, SVIN, RELPOSNED, EOE
+    (0x01, 0x01),
+    (0x01, 0x02),
+    (0x01, 0x03),
+    (0x01, 0x04),
+    (0x01, 0x05),
+    (0x01, 0x07),
+    (0x01, 0x09),
+    (0x01, 0x11),
+    (0x01, 0x12),
+    (0x01, 0x13),
+    (0x01, 0x14),
+    (0x01, 0x20),
+    (0x01, 0x21),
+    (0x01, 0x22),
+    (0x01, 0x26),
+    (0x01, 0x35),
+    (0x01, 0x3b),
+    (0x01, 0x3c),
+    (0x01, 0x61),
+    // RXM: SFRBX, RAWX, RTCM
+    (0x02, 0x13),
+    (0x02, 0x15),
+    (0x02, 0x32),
+    // MON: HW, GNSS
+    (0x0a, 0x09),
+    (0x0a, 0x28),
+    // TIM: TP, TM2, SVIN
+    (0x0d, 0x01),
+    (0x0d, 0x03),
+    (0x0d, 0x04),
+    // ESF: MEAS, RAW, STATUS, ALG, INS
+    (0x10, 0x02),
+    (0x10, 0x03),
+    (0x10, 0x10),
+    (0x10, 0x14),
+    (0x10, 0x15),
+    // HNR: PVT, ATT, INS
+    (0x28, 0x00),
+    (0x28, 0x01),
+    (0x28, 0x02),
+];
+
+/// A raw CFG-* message as read back from the receiver
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CfgMessage {
+    pub class: u8,
+    pub msg_id: u8,
+    pub payload: Vec<u8>,
+}
+
+impl CfgMessage {
+    fn to_packet_vec(&self) -> Vec<u8> {
+        UbxUnknownPacketRef {
+            payload: &self.payload,
+            class: self.class,
+            msg_id: self.msg_id,
+        }
+        .into_packet_vec()
+    }
+
+    /// Identifies the message among all messages of a backup, as some CFG-* messages
+    /// exist once per port, protocol, time pulse or output message
+    fn key(&self) -> (u8, u8, Option<[u8; 2]>) {
+        let selector = match (self.class, self.msg_id) {
+            // CFG-MSG
+            (CFG_CLASS, 0x01) if self.payload.len() >= 2 => {
+                Some([self.payload[0], self.payload[1]])
+            },
+            // CFG-PRT, CFG-INF, CFG-TP5
+            (CFG_CLASS, 0x00 | 0x02 | 0x31) if !self.payload.is_empty() => {
+                Some([self.payload[0], 0])
+            },
+            _ => None,
+        };
+        (self.class, self.msg_id, selector)
+    }
+
+    fn name(&self) -> String {
+        let (_, _, selector) = self.key();
+        let name = match (self.class, self.msg_id) {
+            (CFG_CLASS, 0x00) => "CFG-PRT".to_string(),
+            (CFG_CLASS, 0x01) => "CFG-MSG".to_string(),
+            (CFG_CLASS, 0x02) => "CFG-INF".to_string(),
+            (CFG_CLASS, 0x31) => "CFG-TP5".to_string(),
+            (class, msg_id) => LEGACY_CFG_MESSAGES
+                .iter()
+                .find(|(id, _)| class == CFG_CLASS && *id == msg_id)
+                .map(|(_, name)| name.to_string())
+                .unwrap_or_else(|| format!("0x{class:02x} 0x{msg_id:02x}")),
+        };
+        match (self.msg_id, selector) {
+            (0x01, Some([class, msg_id])) => format!("{name} 0x{class:02x} 0x{msg_id:02x}"),
+            (_, Some([index, _])) => format!("{name} #{index}"),
+            (_, None) => name,
+        }
+    }
+}
+
+/// A complete receiver configuration
+#[derive(Debug, Clone)]
+pub enum ConfigBackup {
+    /// Configuration items of Gen9 (and later) receivers, as read with CFG-VALGET
+    Items {
+        items: Vec<CfgVal>,
+        /// Encoded key/value pairs of the items unknown to [CfgVal], restored as-is
+        unknown: Vec<Vec<u8>>,
+    },
+    /// CFG-* messages of M8 (and earlier) receivers
+    Messages(Vec<CfgMessage>),
+}
+
+impl ConfigBackup {
+    /// Serializes the backup as a sequence of UBX frames
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.packets(CfgLayer::default()).concat()
+    }
+
+    /// Parses a backup created by [ConfigBackup::to_bytes]
+    pub fn from_bytes(data: &[u8]) -> anyhow::Result<Self> {
+        let mut items = Vec::new();
+        let mut unknown = Vec::new();
+        let mut messages = Vec::new();
+
+        let mut parser = Parser::default();
+        let mut it = parser.consume(data);
+        while let Some(packet) = it.next() {
+            let packet = packet.map_err(|e| anyhow::anyhow!("Malformed backup: {e}"))?;
+            let (class, msg_id) = packet.class_and_msg_id();
+            let payload = packet.payload();
+            if (class, msg_id) == (CfgValSet::CLASS, CfgValSet::ID) && payload.len() >= 4 {
+                split_items(&payload[4..], &mut items, &mut unknown);
+            } else {
+                messages.push(CfgMessage {
+                    class,
+                    msg_id,
+                    payload: payload.to_vec(),
+                });
+            }
+        }
+
+        match (items.is_empty() && unknown.is_empty(), messages.is_empty()) {
+            (false, true) => Ok(Self::Items { items, unknown }),
+            (true, false) => Ok(Self::Messages(messages)),
+            (true, true) => anyhow::bail!("No configuration found"),
+            (false, false) => anyhow::bail!("Both CFG-VALSET and legacy CFG-* messages found"),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        std::fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// Compares this (saved) configuration against the `live` one
+    pub fn diff(&self, live: &ConfigBackup) -> anyhow::Result<ConfigDiff> {
+        match (self, live) {
+            (
+                Self::Items {
+                    items: saved,
+                    unknown: saved_unknown,
+                },
+                Self::Items {
+                    items: live,
+                    unknown: live_unknown,
+                },
+            ) => {
+                let diff = CfgValDiff::new(saved, live);
+                let mut config_diff = ConfigDiff {
+                    missing: diff.removed.iter().map(|val| format!("{val:?}")).collect(),
+                    added: diff.added.iter().map(|val| format!("{val:?}")).collect(),
+                    changed: diff
+                        .changed
+                        .iter()
+                        .map(|(saved, live)| (format!("{saved:?}"), format!("{live:?}")))
+                        .collect(),
+                };
+                let entries = |unknown: &[Vec<u8>]| -> BTreeMap<_, _> {
+                    unknown
+                        .iter()
+                        .map(|item| {
+                            let (key, value) = item.split_at(4);
+                            let description = format!("{key:02x?}: {value:02x?}");
+                            (key.to_vec(), (description, value.to_vec()))
+                        })
+                        .collect()
+                };
+                let unknown_diff = ConfigDiff::new(&entries(saved_unknown), &entries(live_unknown));
+                config_diff.missing.extend(unknown_diff.missing);
+                config_diff.added.extend(unknown_diff.added);
+                config_diff.changed.extend(unknown_diff.changed);
+                Ok(config_diff)
+            },
+            (Self::Messages(saved), Self::Messages(live)) => {
+                let entries = |messages: &[CfgMessage]| -> BTreeMap<_, _> {
+                    messages
+                        .iter()
+                        .map(|msg| {
+                            let description = format!("{}: {:02x?}", msg.name(), msg.payload);
+                            (msg.key(), (description, msg.payload.clone()))
+                        })
+                        .collect()
+                };
+                Ok(ConfigDiff::new(&entries(saved), &entries(live)))
+            },
+            _ => anyhow::bail!("Cannot compare configurations of different receiver generations"),
+        }
+    }
+
+    /// UBX frames restoring this configuration, CFG-PRT messages and the port
+    /// items last as they can change the settings of the port we are talking over
+    fn packets(&self, layers: CfgLayer) -> Vec<Vec<u8>> {
+        match self {
+            Self::Items { items, unknown } => {
+                let (port_items, items): (Vec<CfgVal>, Vec<CfgVal>) = items
+                    .iter()
+                    .cloned()
+                    .partition(|item| is_port_group(item.key().group_id()));
+                let (port_unknown, unknown): (Vec<_>, Vec<_>) = unknown.iter().partition(|raw| {
+                    is_port_group(
+                        KeyId::new(u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]])).group_id(),
+                    )
+                });
+                let known = |items: &[CfgVal]| {
+                    items
+                        .chunks(CfgValSetBuilder::MAX_ITEMS)
+                        .map(|chunk| {
+                            CfgValSetBuilder {
+                                version: 0,
+                                layers,
+                                reserved1: 0,
+                                cfg_data: chunk,
+                            }
+                            .into_packet_vec()
+                        })
+                        .collect::<Vec<_>>()
+                };
+                // Same layout as CfgValSetBuilder, with the raw key/value pairs
+                let raw = |unknown: Vec<&Vec<u8>>| {
+                    unknown
+                        .chunks(CfgValSetBuilder::MAX_ITEMS)
+                        .map(|chunk| {
+                            let mut payload = vec![0, layers.bits(), 0, 0];
+                            chunk.iter().for_each(|raw| payload.extend_from_slice(raw));
+                            UbxUnknownPacketRef {
+                                payload: &payload,
+                                class: CfgValSet::CLASS,
+                                msg_id: CfgValSet::ID,
+                            }
+                            .into_packet_vec()
+                        })
+                        .collect::<Vec<_>>()
+                };
+                known(&items)
+                    .into_iter()
+                    .chain(raw(unknown))
+                    .chain(known(&port_items))
+                    .chain(raw(port_unknown))
+                    .collect()
+            },
+            Self::Messages(messages) => {
+                let (ports, others): (Vec<_>, Vec<_>) = messages
+                    .iter()
+                    .partition(|msg| (msg.class, msg.msg_id) == (CFG_CLASS, 0x00));
+                others
+                    .into_iter()
+                    .chain(ports)
+                    .map(CfgMessage::to_packet_vec)
+                    .collect()
+            },
+        }
+    }
+}
+
+/// Differences between a saved and the live receiver configuration
+#[derive(Debug, Default)]
+pub struct ConfigDiff {
+    /// Present in the saved configuration only
+    pub missing: Vec<String>,
+    /// Present in the live configuration only
+    pub added: Vec<String>,
+    /// Different values, as (saved, live)
+    pub changed: Vec<(String, String)>,
+}
+
+impl ConfigDiff {
+    /// Compares entries given as key -> (description, encoded value)
+    fn new<K: Ord>(
+        saved: &BTreeMap<K, (String, Vec<u8>)>,
+        live: &BTreeMap<K, (String, Vec<u8>)>,
+    ) -> Self {
+        let mut diff = Self::default();
+        for (key, (saved_desc, saved_value)) in saved {
+            match live.get(key) {
+                None => diff.missing.push(saved_desc.clone()),
+                Some((live_desc, live_value)) if live_value != saved_value => {
+                    diff.changed.push((saved_desc.clone(), live_desc.clone()))
+                },
+                Some(_) => {},
+            }
+        }
+        for (key, (live_desc, _)) in live {
+            if !saved.contains_key(key) {
+                diff.added.push(live_desc.clone());
+            }
+        }
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.added.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for ConfigDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "Configurations are identical");
+        }
+        for entry in &self.missing {
+            writeln!(f, "- {entry}")?;
+        }
+        for entry in &self.added {
+            writeln!(f, "+ {entry}")?;
+        }
+        for (saved, live) in &self.changed {
+            writeln!(f, "~ {saved} -> {live}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Device {
+    /// Reads all configuration items of the groups known by [CfgVal] from the given layer
+    /// of a Gen9 receiver, including the items of these groups unknown to [CfgVal]
+    pub fn backup_config_items(&mut self, layer: CfgValGetLayer) -> anyhow::Result<ConfigBackup> {
+        let mut items = Vec::new();
+        let mut unknown = Vec::new();
+        for group in CfgVal::known_groups() {
+            let keys = [KeyId::group_wildcard(group)];
+            let mut position = 0;
+            loop {
+                let request = CfgValGetRequestBuilder {
+                    version: 0,
+                    layer,
+                    position,
+                    keys: &keys,
+                }
+                .into_packet_vec();
+                // Groups not supported by the receiver are rejected
+                let Some(payload) = self.poll(&request)? else {
+                    break;
+                };
+                let data = payload.get(4..).unwrap_or_default();
+                let num_items = split_items(data, &mut items, &mut unknown);
+                if num_items < CfgValSetBuilder::MAX_ITEMS {
+                    break;
+                }
+                position += num_items as u16;
+            }
+        }
+        Ok(ConfigBackup::Items { items, unknown })
+    }
+
+    /// Polls every known CFG-* message of an M8 (or earlier) receiver
+    pub fn backup_config_messages(&mut self) -> anyhow::Result<ConfigBackup> {
+        let mut polls: Vec<Vec<u8>> = LEGACY_CFG_MESSAGES
+            .iter()
+            .map(|(msg_id, _)| cfg_poll(*msg_id, &[]))
+            .collect();
+        polls.extend(LEGACY_PORTS.iter().map(|port| cfg_poll(0x00, &[*port])));
+        polls.extend(
+            LEGACY_INF_PROTOCOLS
+                .iter()
+                .map(|proto| cfg_poll(0x02, &[*proto])),
+        );
+        polls.extend(LEGACY_TIME_PULSES.iter().map(|tp| cfg_poll(0x31, &[*tp])));
+        polls.extend(
+            LEGACY_OUTPUT_MESSAGES
+                .iter()
+                .map(|(class, msg_id)| cfg_poll(0x01, &[*class, *msg_id])),
+        );
+
+        let mut messages = Vec::new();
+        for poll in polls {
+            // Messages not supported by the receiver are rejected
+            if let Some(payload) = self.poll(&poll)? {
+                messages.push(CfgMessage {
+                    class: poll[2],
+                    msg_id: poll[3],
+                    payload,
+                });
+            }
+        }
+        Ok(ConfigBackup::Messages(messages))
+    }
+
+    /// Sends the configuration back to the receiver, waiting for each message to be acknowledged.
+    ///
+    /// The `layers` are only used for Gen9 receivers; on older receivers
+    /// use [Device::send_cfg_cfg] afterwards to make the configuration permanent.
+    /// Returns the number of messages rejected by the receiver.
+    pub fn restore_config(
+        &mut self,
+        backup: &ConfigBackup,
+        layers: CfgLayer,
+    ) -> anyhow::Result<usize> {
+        let mut rejected = 0;
+        for packet in backup.packets(layers) {
+            if !self.send_and_wait_for_ack(&packet)? {
+                eprintln!("Configuration message rejected: {:02x?}", &packet[2..4]);
+                rejected += 1;
+            }
+        }
+        Ok(rejected)
+    }
+}
+
+fn cfg_poll(msg_id: u8, payload: &[u8]) -> Vec<u8> {
+    UbxUnknownPacketRef {
+        payload,
+        class: CFG_CLASS,
+        msg_id,
+    }
+    .into_packet_vec()
+}
+
+/// Key groups of the port settings: CFG-UART1, CFG-UART2, CFG-USB, CFG-SPI, CFG-I2C
+/// and their INPROT/OUTPROT groups
+fn is_port_group(group_id: u8) -> bool {
+    matches!(group_id, 0x51..=0x53 | 0x64 | 0x65 | 0x71..=0x7a)
+}
+
+/// Splits the key/value pairs of a CFG-VALGET or CFG-VALSET payload into the items
+/// known by [CfgVal] and the raw unknown ones, returns the number of pairs
+fn split_items(data: &[u8], items: &mut Vec<CfgVal>, unknown: &mut Vec<Vec<u8>>) -> usize {
+    let iter = CfgValIter::from_raw(data);
+    let mut count = 0;
+    for raw in iter.raw_items() {
+        if CfgVal::try_parse(raw).is_none() {
+            unknown.push(raw.to_vec());
+        }
+        count += 1;
+    }
+    items.extend(iter);
+    count
+}
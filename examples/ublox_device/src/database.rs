@@ -0,0 +1,114 @@
+//! Dump and restore of the receiver's navigation database with UBX-MGA-DBD,
+//! for fast restarts without network access.
+//!
+//! A dump is stored as the sequence of MGA-DBD frames read from the receiver
+//! and sent back as-is. The transfer is checked against the MGA-ACK counts:
+//! the number of entries reported at the end of the dump, and one accepted
+//! acknowledgment per entry when restoring (which needs `ackAiding` enabled).
+
+use std::{path::Path, time::Duration};
+
+use ublox::{MgaDbdPollBuilder, PacketRef, Parser};
+
+use crate::Device;
+
+/// Time to wait for the next entry of a dump
+const DUMP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Contents of the navigation database
+#[derive(Debug, Clone, Default)]
+pub struct NavDatabase {
+    /// MGA-DBD frames in the order they were received
+    pub frames: Vec<Vec<u8>>,
+}
+
+impl NavDatabase {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.frames.concat()
+    }
+
+    /// Parses a dump created by [NavDatabase::to_bytes]
+    pub fn from_bytes(data: &[u8]) -> anyhow::Result<Self> {
+        let mut frames = Vec::new();
+        let mut parser = Parser::default();
+        let mut it = parser.consume(data);
+        while let Some(packet) = it.next() {
+            match packet.map_err(|e| anyhow::anyhow!("Malformed database dump: {e}"))? {
+                PacketRef::MgaDbd(entry) => frames.push(entry.into_packet_vec()),
+                packet => {
+                    let (class, msg_id) = packet.class_and_msg_id();
+                    anyhow::bail!("Unexpected message 0x{class:02x} 0x{msg_id:02x}");
+                },
+            }
+        }
+        Ok(Self { frames })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        std::fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+}
+
+impl Device {
+    /// Polls the navigation database and collects the MGA-DBD entries until
+    /// the receiver reports the end of the dump
+    pub fn dump_navigation_database(&mut self) -> anyhow::Result<NavDatabase> {
+        self.write_all(&MgaDbdPollBuilder {}.into_packet_bytes())?;
+
+        let mut database = NavDatabase::default();
+        let mut count = None;
+        let mut last_entry = std::time::SystemTime::now();
+        while count.is_none() {
+            let received = database.frames.len();
+            self.on_data_available(|packet| match packet {
+                PacketRef::MgaDbd(entry) => database.frames.push(entry.into_packet_vec()),
+                PacketRef::MgaAck(ack) if ack.dbd_count().is_some() => count = ack.dbd_count(),
+                _ => {},
+            })?;
+
+            if database.frames.len() > received {
+                last_entry = std::time::SystemTime::now();
+            } else if last_entry.elapsed().unwrap() > DUMP_TIMEOUT {
+                anyhow::bail!(
+                    "Database dump timed out after {} entries",
+                    database.frames.len()
+                );
+            }
+        }
+
+        let count = count.unwrap_or_default() as usize;
+        if count != database.frames.len() {
+            anyhow::bail!(
+                "Receiver reported {count} database entries, received {}",
+                database.frames.len()
+            );
+        }
+        Ok(database)
+    }
+
+    /// Sends a navigation database back to the receiver, failing unless
+    /// every entry was accepted
+    pub fn restore_navigation_database(
+        &mut self,
+        database: &NavDatabase,
+        retries: usize,
+    ) -> anyhow::Result<()> {
+        let frames = database.frames.iter().map(Vec::as_slice);
+        let report = self.inject_mga(frames, retries)?;
+        if report.accepted != database.frames.len() {
+            anyhow::bail!(
+                "Receiver accepted {} of {} database entries ({} rejected, {} unacknowledged)",
+                report.accepted,
+                database.frames.len(),
+                report.rejected.len(),
+                report.unacknowledged.len()
+            );
+        }
+        Ok(())
+    }
+}
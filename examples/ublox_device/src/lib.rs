@@ -1,8 +1,15 @@
 use cli::UbxPortConfiguration;
 use std::time::Duration;
-use ublox::{CfgPrtUart, CfgPrtUartBuilder, PacketRef, Parser, UartMode, UbxPacketMeta};
+use ublox::{
+    CfgCfgBuilder, CfgPrtUart, CfgPrtUartBuilder, PacketRef, Parser, UartMode, UbxPacketMeta,
+};
 
+pub mod assist;
+pub mod base;
 pub mod cli;
+pub mod config;
+pub mod database;
+pub mod ntrip;
 
 pub trait UbxPacketHandler {
     fn handle(&mut self, _packet: PacketRef<'_>) {}
@@ -52,6 +59,17 @@ impl Device {
         Ok(())
     }
 
+    /// Clears, saves and/or loads the (legacy) receiver configuration
+    /// with UBX-CFG-CFG and waits for the receiver to acknowledge it.
+    ///
+    /// Fails if the receiver rejected the request or did not answer in time.
+    pub fn send_cfg_cfg(&mut self, cfg: CfgCfgBuilder) -> std::io::Result<()> {
+        if !self.send_and_wait_for_ack(&cfg.into_packet_bytes())? {
+            return Err(std::io::Error::other("UBX-CFG-CFG was not acknowledged"));
+        }
+        Ok(())
+    }
+
     pub fn write_all(&mut self, data: &[u8]) -> std::io::Result<()> {
         self.port.write_all(data)
     }
@@ -114,6 +132,65 @@ impl Device {
         Ok(())
     }
 
+    /// Writes the `packet` and waits for the receiver to acknowledge it.
+    ///
+    /// Returns `false` if the receiver rejected the packet or did not answer in time.
+    pub fn send_and_wait_for_ack(&mut self, packet: &[u8]) -> std::io::Result<bool> {
+        let (class, msg_id) = (packet[2], packet[3]);
+        self.write_all(packet)?;
+
+        let mut answer = None;
+        let start = std::time::SystemTime::now();
+        let timeout = Duration::from_secs(3);
+        while answer.is_none() {
+            self.on_data_available(|packet| match packet {
+                PacketRef::AckAck(ack) if ack.class() == class && ack.msg_id() == msg_id => {
+                    answer = Some(true);
+                },
+                PacketRef::AckNak(nak) if nak.class() == class && nak.msg_id() == msg_id => {
+                    answer = Some(false);
+                },
+                _ => {},
+            })?;
+
+            if start.elapsed().unwrap() > timeout {
+                eprintln!("Did not receive ACK message for request");
+                break;
+            }
+        }
+        Ok(answer.unwrap_or(false))
+    }
+
+    /// Writes the poll `request` and waits for the answer with the same class and message id.
+    ///
+    /// Returns a copy of the answer payload, or `None` if the receiver rejected the poll
+    /// or did not answer in time.
+    pub fn poll(&mut self, request: &[u8]) -> std::io::Result<Option<Vec<u8>>> {
+        let (class, msg_id) = (request[2], request[3]);
+        self.write_all(request)?;
+
+        let mut payload = None;
+        let mut rejected = false;
+        let start = std::time::SystemTime::now();
+        let timeout = Duration::from_secs(3);
+        while payload.is_none() && !rejected {
+            self.on_data_available(|packet| match packet {
+                PacketRef::AckNak(nak) if nak.class() == class && nak.msg_id() == msg_id => {
+                    rejected = true;
+                },
+                packet if payload.is_none() && packet.class_and_msg_id() == (class, msg_id) => {
+                    payload = Some(packet.payload().to_vec());
+                },
+                _ => {},
+            })?;
+
+            if start.elapsed().unwrap() > timeout {
+                break;
+            }
+        }
+        Ok(payload)
+    }
+
     /// Reads the serial port, converting timeouts into "no data received"
     fn read_port(&mut self, output: &mut [u8]) -> std::io::Result<usize> {
         match self.port.read(output) {
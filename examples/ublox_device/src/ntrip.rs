@@ -0,0 +1,554 @@
+//! NTRIP v1 and v2 client that forwards RTCM corrections from a caster to the receiver.
+//!
+//! The receiver confirms every RTCM message it gets with UBX-RXM-RTCM if that
+//! message is enabled; the confirmations are counted by a [CorrectionMonitor].
+
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    time::{Duration, Instant},
+};
+
+use ublox::{
+    nmea::NmeaGenerator,
+    rtcm::{CorrectionMonitor, RtcmParser},
+    PacketRef,
+};
+
+use crate::Device;
+
+/// Time to wait for the caster to answer a request
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Read timeout while forwarding, bounds the latency of the receiver side
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// A constellation is reported lost after this time without MSM
+const CONSTELLATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+const USER_AGENT: &str = "NTRIP ublox-rs/0.1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NtripVersion {
+    V1,
+    V2,
+}
+
+/// Caster, mountpoint and credentials
+#[derive(Debug, Clone)]
+pub struct NtripConfig {
+    pub host: String,
+    pub port: u16,
+    pub mountpoint: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub version: NtripVersion,
+    /// Interval of the GGA upload, `None` for casters that do not need a position
+    pub gga_interval: Option<Duration>,
+}
+
+impl NtripConfig {
+    pub fn new(host: &str, port: u16, mountpoint: &str) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            mountpoint: mountpoint.into(),
+            username: None,
+            password: None,
+            version: NtripVersion::V2,
+            gga_interval: Some(Duration::from_secs(10)),
+        }
+    }
+
+    fn request(&self, mountpoint: &str) -> String {
+        let mut request = match self.version {
+            NtripVersion::V1 => format!("GET /{mountpoint} HTTP/1.0\r\n"),
+            NtripVersion::V2 => format!(
+                "GET /{mountpoint} HTTP/1.1\r\nHost: {}:{}\r\nNtrip-Version: Ntrip/2.0\r\nConnection: close\r\n",
+                self.host, self.port
+            ),
+        };
+        request += &format!("User-Agent: {USER_AGENT}\r\n");
+        if let Some(username) = &self.username {
+            let credentials = format!("{username}:{}", self.password.as_deref().unwrap_or(""));
+            request += &format!(
+                "Authorization: Basic {}\r\n",
+                base64(credentials.as_bytes())
+            );
+        }
+        request + "\r\n"
+    }
+}
+
+/// Data stream entry (STR) of a caster's source table
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceStream {
+    pub mountpoint: String,
+    pub identifier: String,
+    pub format: String,
+    pub format_details: String,
+    /// 0 no carrier phase, 1 L1, 2 L1 and L2
+    pub carrier: u8,
+    pub nav_system: String,
+    pub network: String,
+    pub country: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// The caster needs GGA sentences from the client
+    pub nmea: bool,
+    /// Network solution (e.g. VRS) instead of a single base station
+    pub solution: bool,
+    pub generator: String,
+    pub compression: String,
+    /// N none, B basic, D digest
+    pub authentication: String,
+    pub fee: bool,
+    pub bitrate: u32,
+}
+
+/// Parses the STR entries of a source table, other records are ignored
+pub fn parse_sourcetable(table: &str) -> Vec<SourceStream> {
+    table
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.trim_end().split(';').collect();
+            if fields.first() != Some(&"STR") || fields.len() < 18 {
+                return None;
+            }
+            Some(SourceStream {
+                mountpoint: fields[1].into(),
+                identifier: fields[2].into(),
+                format: fields[3].into(),
+                format_details: fields[4].into(),
+                carrier: fields[5].parse().unwrap_or(0),
+                nav_system: fields[6].into(),
+                network: fields[7].into(),
+                country: fields[8].into(),
+                latitude: fields[9].parse().unwrap_or(0.0),
+                longitude: fields[10].parse().unwrap_or(0.0),
+                nmea: fields[11] == "1",
+                solution: fields[12] == "1",
+                generator: fields[13].into(),
+                compression: fields[14].into(),
+                authentication: fields[15].into(),
+                fee: fields[16] == "Y",
+                bitrate: fields[17].parse().unwrap_or(0),
+            })
+        })
+        .collect()
+}
+
+/// Downloads and parses the source table of the caster in `config`
+pub fn fetch_sourcetable(config: &NtripConfig) -> anyhow::Result<Vec<SourceStream>> {
+    let mut connection = Connection::open(config, "")?;
+    if !connection.sourcetable {
+        anyhow::bail!("Caster did not answer with a source table");
+    }
+    let mut table = Vec::new();
+    let mut buf = [0; 4096];
+    loop {
+        match connection.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => table.extend_from_slice(&buf[..n]),
+            Err(e) if is_timeout(&e) => break,
+            Err(e) => return Err(e.into()),
+        }
+        if table.windows(14).any(|w| w == b"ENDSOURCETABLE") {
+            break;
+        }
+    }
+    Ok(parse_sourcetable(&String::from_utf8_lossy(&table)))
+}
+
+/// Connection to a mountpoint
+#[derive(Debug)]
+pub struct NtripClient {
+    connection: Connection,
+    gga_interval: Option<Duration>,
+    last_gga: Option<Instant>,
+}
+
+impl NtripClient {
+    /// Requests the mountpoint of `config`, failing unless the caster starts the data stream
+    pub fn connect(config: &NtripConfig) -> anyhow::Result<Self> {
+        let connection = Connection::open(config, &config.mountpoint)?;
+        if connection.sourcetable {
+            anyhow::bail!("Mountpoint {} not found on the caster", config.mountpoint);
+        }
+        connection.stream.set_read_timeout(Some(POLL_INTERVAL))?;
+        Ok(Self {
+            connection,
+            gga_interval: config.gga_interval,
+            last_gga: None,
+        })
+    }
+
+    /// Reads corrections, returns 0 if none arrived within the poll interval
+    /// and a [ConnectionAborted](std::io::ErrorKind::ConnectionAborted) error
+    /// once the caster closed the connection
+    pub fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.connection.read(buf) {
+            Ok(0) => Err(std::io::ErrorKind::ConnectionAborted.into()),
+            Err(e) if is_timeout(&e) => Ok(0),
+            result => result,
+        }
+    }
+
+    /// Sends a NMEA sentence to the caster
+    pub fn send_gga(&mut self, sentence: &str) -> std::io::Result<()> {
+        self.last_gga = Some(Instant::now());
+        self.connection.stream.write_all(sentence.as_bytes())
+    }
+
+    /// The GGA upload is due
+    pub fn gga_due(&self) -> bool {
+        match (self.gga_interval, self.last_gga) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(interval), Some(last)) => last.elapsed() >= interval,
+        }
+    }
+}
+
+impl Device {
+    /// Forwards the corrections of `client` to the receiver for `duration`, or
+    /// until the caster closes the connection.
+    ///
+    /// GGA sentences from NAV-PVT are uploaded at the configured interval and
+    /// RXM-RTCM confirmations are recorded, both have to be enabled on the receiver.
+    pub fn forward_corrections(
+        &mut self,
+        client: &mut NtripClient,
+        duration: Option<Duration>,
+    ) -> anyhow::Result<CorrectionMonitor> {
+        let mut monitor = CorrectionMonitor::new(CONSTELLATION_TIMEOUT);
+        let mut parser = RtcmParser::new();
+        let start = Instant::now();
+        let mut nmea = NmeaGenerator::new();
+        let mut buf = [0; 4096];
+        loop {
+            if duration.is_some_and(|duration| start.elapsed() >= duration) {
+                break;
+            }
+            let n = match client.read(&mut buf) {
+                Err(e) if e.kind() == std::io::ErrorKind::ConnectionAborted => break,
+                result => result?,
+            };
+            for frame in parser.consume(&buf[..n]) {
+                let Ok(frame) = frame else {
+                    eprintln!("Dropped RTCM frame with invalid CRC");
+                    continue;
+                };
+                self.write_all(frame.as_bytes())?;
+                match frame.decode() {
+                    Ok(message) => {
+                        for event in monitor.on_message(&message, start.elapsed()) {
+                            println!("Corrections: {event:?}");
+                        }
+                    },
+                    Err(e) => eprintln!("Undecodable RTCM {}: {e}", frame.message_type()),
+                }
+            }
+            for event in monitor.check(start.elapsed()) {
+                println!("Corrections: {event:?}");
+            }
+
+            self.on_data_available(|packet| match packet {
+                PacketRef::RxmRtcm(status) => monitor.on_receiver_status(&status),
+                packet => nmea.handle(&packet),
+            })?;
+            if let (true, Some(sentence)) = (client.gga_due(), nmea.gga()) {
+                client.send_gga(&sentence)?;
+            }
+        }
+        Ok(monitor)
+    }
+}
+
+/// HTTP or ICY connection with the response header read
+#[derive(Debug)]
+struct Connection {
+    stream: TcpStream,
+    /// The caster answered with its source table
+    sourcetable: bool,
+    chunks: Option<ChunkDecoder>,
+    /// Received body data not yet read
+    body: Vec<u8>,
+}
+
+impl Connection {
+    fn open(config: &NtripConfig, mountpoint: &str) -> anyhow::Result<Self> {
+        let mut stream = TcpStream::connect((config.host.as_str(), config.port))?;
+        stream.set_read_timeout(Some(RESPONSE_TIMEOUT))?;
+        stream.write_all(config.request(mountpoint).as_bytes())?;
+
+        let mut response = Vec::new();
+        let mut buf = [0; 1024];
+        let header_len = loop {
+            let n = stream.read(&mut buf)?;
+            if n == 0 {
+                anyhow::bail!("Caster closed the connection without answering");
+            }
+            response.extend_from_slice(&buf[..n]);
+            // NTRIP v1 casters answer the data stream request with a status line only
+            if response.starts_with(b"ICY 200 OK\r\n") {
+                break 12;
+            }
+            if let Some(end) = response.windows(4).position(|w| w == b"\r\n\r\n") {
+                break end + 4;
+            }
+        };
+
+        let header = String::from_utf8_lossy(&response[..header_len]).to_ascii_lowercase();
+        let status = header.lines().next().unwrap_or_default().to_string();
+        let sourcetable = status.starts_with("sourcetable 200")
+            || header.contains("content-type: gnss/sourcetable");
+        if !(status.starts_with("icy 200") || status.contains(" 200") || sourcetable) {
+            anyhow::bail!("Caster refused the request: {}", status.to_uppercase());
+        }
+        let chunked = header.contains("transfer-encoding: chunked");
+        let mut connection = Self {
+            stream,
+            sourcetable,
+            chunks: chunked.then(ChunkDecoder::default),
+            body: Vec::new(),
+        };
+        connection.push(&response[header_len..])?;
+        Ok(connection)
+    }
+
+    /// Reads body data with the chunked transfer encoding removed,
+    /// returns 0 at the end of the body
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.body.is_empty() {
+            if self.chunks.as_ref().is_some_and(ChunkDecoder::finished) {
+                return Ok(0);
+            }
+            let mut raw = [0; 4096];
+            let n = self.stream.read(&mut raw)?;
+            if n == 0 {
+                return Ok(0);
+            }
+            self.push(&raw[..n])?;
+        }
+        let n = self.body.len().min(buf.len());
+        buf[..n].copy_from_slice(&self.body[..n]);
+        self.body.drain(..n);
+        Ok(n)
+    }
+
+    fn push(&mut self, data: &[u8]) -> std::io::Result<()> {
+        match &mut self.chunks {
+            Some(chunks) => chunks.decode(data, &mut self.body),
+            None => {
+                self.body.extend_from_slice(data);
+                Ok(())
+            },
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+enum ChunkState {
+    /// Reading the hexadecimal chunk size line
+    #[default]
+    Size,
+    Data(usize),
+    /// Skipping the line break after a chunk
+    DataEnd,
+    /// The last chunk was received
+    Done,
+}
+
+/// Removes the HTTP chunked transfer encoding from a stream
+#[derive(Debug, Default)]
+struct ChunkDecoder {
+    state: ChunkState,
+    line: String,
+}
+
+impl ChunkDecoder {
+    /// Appends the chunk data contained in `data` to `out`
+    fn decode(&mut self, data: &[u8], out: &mut Vec<u8>) -> std::io::Result<()> {
+        let mut i = 0;
+        while i < data.len() {
+            match self.state {
+                ChunkState::Size | ChunkState::DataEnd => {
+                    let byte = data[i];
+                    i += 1;
+                    if byte != b'\n' {
+                        self.line.push(byte as char);
+                        continue;
+                    }
+                    let line = std::mem::take(&mut self.line);
+                    if matches!(self.state, ChunkState::DataEnd) {
+                        self.state = ChunkState::Size;
+                        continue;
+                    }
+                    // Chunk extensions follow a semicolon
+                    let size = line.trim().split(';').next().unwrap_or_default();
+                    let size = usize::from_str_radix(size, 16).map_err(|_| {
+                        std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid chunk size")
+                    })?;
+                    self.state = match size {
+                        0 => ChunkState::Done,
+                        size => ChunkState::Data(size),
+                    };
+                },
+                ChunkState::Data(remaining) => {
+                    let n = remaining.min(data.len() - i);
+                    out.extend_from_slice(&data[i..i + n]);
+                    i += n;
+                    self.state = match remaining - n {
+                        0 => ChunkState::DataEnd,
+                        remaining => ChunkState::Data(remaining),
+                    };
+                },
+                ChunkState::Done => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn finished(&self) -> bool {
+        matches!(self.state, ChunkState::Done)
+    }
+}
+
+fn is_timeout(e: &std::io::Error) -> bool {
+    matches!(
+        e.kind(),
+        std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+    )
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{io::BufRead, net::TcpListener, thread};
+    use ublox::rtcm::{station_arp, RtcmMessage};
+    use ublox::PositionECEF;
+
+    /// Serves one connection: checks the request line and authorization, writes
+    /// `response` and returns the request and what the client sent afterwards
+    fn caster(response: Vec<u8>) -> (u16, thread::JoinHandle<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            while !request.ends_with("\r\n\r\n") {
+                reader.read_line(&mut request).unwrap();
+            }
+            (&stream).write_all(&response).unwrap();
+            let mut upload = String::new();
+            reader.read_line(&mut upload).unwrap();
+            (request, upload)
+        });
+        (port, handle)
+    }
+
+    #[test]
+    fn base64_encoding() {
+        assert_eq!(base64(b"user:pass"), "dXNlcjpwYXNz");
+        assert_eq!(base64(b"ab"), "YWI=");
+        assert_eq!(base64(b"a"), "YQ==");
+    }
+
+    #[test]
+    fn streams_chunked_corrections() {
+        let frame = station_arp(
+            17,
+            &PositionECEF {
+                x: 4027881.3388,
+                y: 307045.6,
+                z: 4919474.9108,
+            },
+        );
+        let mut response = b"HTTP/1.1 200 OK\r\nNtrip-Version: Ntrip/2.0\r\n\
+            Transfer-Encoding: chunked\r\n\r\n"
+            .to_vec();
+        for chunk in frame.chunks(10) {
+            response.extend(format!("{:x}\r\n", chunk.len()).as_bytes());
+            response.extend(chunk);
+            response.extend(b"\r\n");
+        }
+        let (port, caster) = caster(response);
+
+        let mut config = NtripConfig::new("127.0.0.1", port, "BASE");
+        config.username = Some("user".into());
+        config.password = Some("pass".into());
+        let mut client = NtripClient::connect(&config).unwrap();
+
+        let mut parser = RtcmParser::new();
+        let mut frames = Vec::new();
+        let mut buf = [0; 64];
+        while frames.is_empty() {
+            let n = client.read(&mut buf).unwrap();
+            frames.extend(parser.consume(&buf[..n]));
+        }
+        let received = frames.remove(0).unwrap();
+        assert_eq!(received.as_bytes(), frame.as_slice());
+        assert!(matches!(
+            received.decode(),
+            Ok(RtcmMessage::StationArp(arp)) if arp.station_id == 17
+        ));
+
+        assert!(client.gga_due());
+        let gga = "$GPGGA,120000.00,4807.03800,N,01131.00000,E,1,08,0.9,545.4,M,46.9,M,,*4A\r\n";
+        client.send_gga(gga).unwrap();
+        assert!(!client.gga_due());
+
+        let (request, upload) = caster.join().unwrap();
+        assert!(request.starts_with("GET /BASE HTTP/1.1\r\n"));
+        assert!(request.contains("Ntrip-Version: Ntrip/2.0\r\n"));
+        assert!(request.contains("Authorization: Basic dXNlcjpwYXNz\r\n"));
+        assert_eq!(upload, gga);
+    }
+
+    #[test]
+    fn reads_v1_sourcetable() {
+        let response = b"SOURCETABLE 200 OK\r\nContent-Type: text/plain\r\n\r\n\
+            CAS;caster.example;2101;EXAMPLE;Operator;0;DEU;48.1;11.6;0.0.0.0;0;http://example\r\n\
+            STR;BASE;Munich;RTCM 3.3;1005(10),1077(1),1087(1);2;GPS+GLO;NET;DEU;48.14;11.58;1;0;u-blox;none;B;N;5000;\r\n\
+            ENDSOURCETABLE\r\n"
+            .to_vec();
+        let (port, caster) = caster(response);
+        let mut config = NtripConfig::new("127.0.0.1", port, "BASE");
+        config.version = NtripVersion::V1;
+        let streams = fetch_sourcetable(&config).unwrap();
+        let (request, _) = caster.join().unwrap();
+        assert!(request.starts_with("GET / HTTP/1.0\r\n"));
+
+        assert_eq!(streams.len(), 1);
+        let stream = &streams[0];
+        assert_eq!(stream.mountpoint, "BASE");
+        assert_eq!(stream.format, "RTCM 3.3");
+        assert_eq!(stream.carrier, 2);
+        assert!(stream.nmea);
+        assert!(!stream.solution);
+        assert_eq!(stream.authentication, "B");
+        assert_eq!(stream.bitrate, 5000);
+        assert!((stream.latitude - 48.14).abs() < 1e-9);
+    }
+}
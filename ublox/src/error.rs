@@ -77,6 +77,10 @@ pub enum DateTimeError {
     InvalidDate,
     InvalidTime,
     InvalidNanoseconds,
+    /// Time is not (yet) valid according to the packet flags
+    NotValid,
+    /// Time base or GNSS is not supported
+    UnsupportedTimeBase,
 }
 
 impl fmt::Display for DateTimeError {
@@ -85,9 +89,94 @@ impl fmt::Display for DateTimeError {
             DateTimeError::InvalidDate => f.write_str("invalid date"),
             DateTimeError::InvalidTime => f.write_str("invalid time"),
             DateTimeError::InvalidNanoseconds => f.write_str("invalid nanoseconds"),
+            DateTimeError::NotValid => f.write_str("time not valid"),
+            DateTimeError::UnsupportedTimeBase => f.write_str("unsupported time base"),
         }
     }
 }
 
 #[cfg(feature = "std")]
 impl std::error::Error for DateTimeError {}
+
+/// Errors while decoding broadcast navigation data from RXM-SFRBX
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavDecodeError {
+    /// Unexpected number of data words for the signal
+    InvalidLength,
+    /// Parity (GPS, QZSS, BeiDou), CRC (Galileo) or Hamming code (GLONASS) check failed
+    Parity,
+    /// Wrong preamble or subframe, page or word identifier
+    InvalidFrame,
+}
+
+impl fmt::Display for NavDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NavDecodeError::InvalidLength => f.write_str("invalid number of navigation words"),
+            NavDecodeError::Parity => f.write_str("navigation data parity check failed"),
+            NavDecodeError::InvalidFrame => f.write_str("invalid navigation frame"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NavDecodeError {}
+
+/// Errors while parsing and decoding RTCM 3 messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RtcmError {
+    /// The frame failed the CRC-24Q check
+    InvalidCrc,
+    /// The message is shorter than its content requires
+    InvalidLength,
+}
+
+impl fmt::Display for RtcmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RtcmError::InvalidCrc => f.write_str("RTCM frame CRC check failed"),
+            RtcmError::InvalidLength => f.write_str("RTCM message too short"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RtcmError {}
+
+/// Errors while parsing NMEA sentences
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NmeaError {
+    /// The line does not start with `$` or has no `*` and two digit checksum
+    InvalidFraming,
+    InvalidChecksum {
+        expect: u8,
+        got: u8,
+    },
+    /// A field is missing or has an invalid value
+    InvalidField {
+        sentence: &'static str,
+        field: &'static str,
+    },
+    /// The sentence reports no position fix
+    NoFix,
+}
+
+impl fmt::Display for NmeaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NmeaError::InvalidFraming => f.write_str("invalid NMEA sentence framing"),
+            NmeaError::InvalidChecksum { expect, got } => write!(
+                f,
+                "Not valid NMEA checksum, expect {:02X}, got {:02X}",
+                expect, got
+            ),
+            NmeaError::InvalidField { sentence, field } => {
+                write!(f, "Invalid field {} of sentence {}", field, sentence)
+            },
+            NmeaError::NoFix => f.write_str("no position fix"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NmeaError {}
@@ -74,11 +74,19 @@ extern crate core;
 extern crate serde;
 
 pub use crate::{
-    error::{DateTimeError, MemWriterError, ParserError},
+    error::{DateTimeError, MemWriterError, NavDecodeError, NmeaError, ParserError, RtcmError},
     parser::{FixedLinearBuffer, Parser, ParserIter, UnderlyingBuffer},
     ubx_packets::*,
 };
 
 mod error;
+#[cfg(feature = "alloc")]
+pub mod navmsg;
+#[cfg(feature = "alloc")]
+pub mod nmea;
 mod parser;
+#[cfg(feature = "alloc")]
+pub mod rinex;
+#[cfg(feature = "alloc")]
+pub mod rtcm;
 mod ubx_packets;
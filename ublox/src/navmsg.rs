@@ -0,0 +1,461 @@
+//! Decoding of broadcast navigation messages from RXM-SFRBX.
+//!
+//! [NavDecoder] collects the subframes, pages and strings of every satellite
+//! and reports ephemerides, almanacs, ionosphere and UTC parameters once
+//! they are complete. Supported are GPS and QZSS LNAV, Galileo I/NAV,
+//! GLONASS L1/L2 OF strings and BeiDou D1/D2.
+
+use alloc::{collections::BTreeMap, vec::Vec};
+
+use crate::{
+    error::NavDecodeError,
+    ubx_packets::{GnssId, MgaGloEphRef, MgaGpsEphRef, RxmSfrbxRef},
+};
+
+mod beidou;
+mod galileo;
+mod glonass;
+mod gps;
+mod mga;
+mod orbit;
+
+pub use orbit::SatelliteState;
+
+const SC2RAD: f64 = core::f64::consts::PI;
+const P2_5: f64 = 1.0 / (1u64 << 5) as f64;
+const P2_6: f64 = 1.0 / (1u64 << 6) as f64;
+const P2_8: f64 = 1.0 / (1u64 << 8) as f64;
+const P2_11: f64 = 1.0 / (1u64 << 11) as f64;
+const P2_15: f64 = 1.0 / (1u64 << 15) as f64;
+const P2_19: f64 = 1.0 / (1u64 << 19) as f64;
+const P2_20: f64 = 1.0 / (1u64 << 20) as f64;
+const P2_21: f64 = 1.0 / (1u64 << 21) as f64;
+const P2_23: f64 = 1.0 / (1u64 << 23) as f64;
+const P2_24: f64 = 1.0 / (1u64 << 24) as f64;
+const P2_27: f64 = 1.0 / (1u64 << 27) as f64;
+const P2_29: f64 = 1.0 / (1u64 << 29) as f64;
+const P2_30: f64 = 1.0 / (1u64 << 30) as f64;
+const P2_31: f64 = 1.0 / (1u64 << 31) as f64;
+const P2_32: f64 = 1.0 / (1u64 << 32) as f64;
+const P2_33: f64 = 1.0 / (1u64 << 33) as f64;
+const P2_34: f64 = 1.0 / (1u64 << 34) as f64;
+const P2_38: f64 = 1.0 / (1u64 << 38) as f64;
+const P2_40: f64 = 1.0 / (1u64 << 40) as f64;
+const P2_43: f64 = 1.0 / (1u64 << 43) as f64;
+const P2_46: f64 = 1.0 / (1u64 << 46) as f64;
+const P2_50: f64 = 1.0 / (1u64 << 50) as f64;
+const P2_55: f64 = 1.0 / (1u64 << 55) as f64;
+const P2_59: f64 = 1.0 / (1u64 << 59) as f64;
+const P2_66: f64 = P2_33 * P2_33;
+
+/// Broadcast Keplerian ephemeris of a GPS, QZSS, Galileo or BeiDou satellite.
+///
+/// Angles are in radians, times in seconds of the week given by `week`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ephemeris {
+    pub gnss: GnssId,
+    pub sv_id: u8,
+    /// Week of `toe`: GPS and QZSS week modulo 1024, Galileo GST week or BeiDou BDT week
+    pub week: u16,
+    /// Time of ephemeris
+    pub toe: f64,
+    /// Time of clock
+    pub toc: f64,
+    /// IODE, IODnav or AODE
+    pub iode: u16,
+    /// IODC, IODnav or AODC
+    pub iodc: u16,
+    /// Accuracy index: URA, SISA or URAI
+    pub accuracy: u8,
+    /// Health as used in RINEX: GPS and QZSS 6 bit health, Galileo E1B/E5b
+    /// status bits or BeiDou SatH1
+    pub health: u16,
+    pub sqrt_a: f64,
+    pub e: f64,
+    pub i0: f64,
+    pub idot: f64,
+    pub omega0: f64,
+    pub omega_dot: f64,
+    pub omega: f64,
+    pub m0: f64,
+    pub delta_n: f64,
+    pub cuc: f64,
+    pub cus: f64,
+    pub crc: f64,
+    pub crs: f64,
+    pub cic: f64,
+    pub cis: f64,
+    pub af0: f64,
+    pub af1: f64,
+    pub af2: f64,
+    /// Group delays in seconds: GPS and QZSS TGD, Galileo BGD E5a/E1 and
+    /// E5b/E1, BeiDou TGD1 and TGD2
+    pub tgd: [f64; 2],
+    /// Codes on L2 (GPS)
+    pub l2_codes: u8,
+    /// L2 P data flag (GPS)
+    pub l2p_flag: bool,
+    /// Curve fit interval flag, `true` for more than 4 hours (GPS and QZSS)
+    pub fit_interval: bool,
+}
+
+/// Broadcast ephemeris of a GLONASS satellite, in PZ-90 coordinates
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlonassEphemeris {
+    /// Slot number
+    pub sv_id: u8,
+    /// Frequency channel number (-7..6)
+    pub frequency: i8,
+    /// Time of frame start, seconds of the day in Moscow time
+    pub tk: u32,
+    /// Reference time of the ephemeris, seconds of the day in Moscow time
+    pub tb: u32,
+    /// Day number within the four year interval
+    pub nt: u16,
+    /// Position in meters
+    pub position: [f64; 3],
+    /// Velocity in m/s
+    pub velocity: [f64; 3],
+    /// Luni-solar acceleration in m/s²
+    pub acceleration: [f64; 3],
+    /// Clock correction τn in seconds, RINEX records -τn
+    pub tau_n: f64,
+    /// Relative frequency bias γn
+    pub gamma_n: f64,
+    /// L1/L2 delay difference Δτn in seconds
+    pub delta_tau_n: f64,
+    /// Health flag, MSB of Bn
+    pub health: u8,
+    /// Age of data En in days
+    pub age: u8,
+    /// Accuracy index FT
+    pub accuracy: u8,
+    /// Satellite type M (0: GLONASS, 1: GLONASS-M)
+    pub m: u8,
+}
+
+/// Almanac of a GPS satellite. Angles are in radians.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Almanac {
+    pub gnss: GnssId,
+    pub sv_id: u8,
+    /// Reference time of the almanac, seconds of the almanac week
+    pub toa: u32,
+    pub e: f64,
+    pub i0: f64,
+    pub omega_dot: f64,
+    pub sqrt_a: f64,
+    pub omega0: f64,
+    pub omega: f64,
+    pub m0: f64,
+    pub af0: f64,
+    pub af1: f64,
+    pub health: u8,
+}
+
+/// Klobuchar ionosphere model of GPS, QZSS and BeiDou
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KlobucharModel {
+    pub alpha: [f64; 4],
+    pub beta: [f64; 4],
+}
+
+/// NeQuick G ionosphere model of Galileo
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NeQuickModel {
+    /// Effective ionisation level coefficients ai0 (sfu), ai1 (sfu/deg), ai2 (sfu/deg²)
+    pub ai: [f64; 3],
+    /// Ionospheric disturbance flags for regions 1 to 5
+    pub storm_flags: u8,
+}
+
+/// GNSS to UTC conversion and leap second parameters
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UtcParameters {
+    /// Bias in seconds
+    pub a0: f64,
+    /// Drift in s/s
+    pub a1: f64,
+    /// Reference time, seconds of week `wnt`
+    pub tot: u32,
+    /// Reference week, modulo 256 for GPS, QZSS and Galileo
+    pub wnt: u16,
+    /// Current leap seconds
+    pub delta_t_ls: i8,
+    /// Week of the leap second event, modulo 256 for GPS, QZSS and Galileo
+    pub wn_lsf: u16,
+    /// Day of the leap second event
+    pub dn: u8,
+    /// Leap seconds after the event
+    pub delta_t_lsf: i8,
+}
+
+/// GLONASS time scale parameters of string 5
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlonassTimeParameters {
+    /// Calendar day number of the almanac within the four year interval
+    pub na: u16,
+    /// Four year interval number since 1996
+    pub n4: u8,
+    /// GLONASS to UTC(SU) correction τc in seconds
+    pub tau_c: f64,
+    /// GLONASS to GPS time correction τGPS in seconds
+    pub tau_gps: f64,
+}
+
+/// A decoded navigation message
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum NavMessage {
+    Ephemeris(Ephemeris),
+    GlonassEphemeris(GlonassEphemeris),
+    Almanac(Almanac),
+    Klobuchar(GnssId, KlobucharModel),
+    NeQuick(NeQuickModel),
+    Utc(GnssId, UtcParameters),
+    GlonassTime(GlonassTimeParameters),
+}
+
+/// Kinds of messages, to report each distinct message only once
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Kind {
+    Ephemeris,
+    Almanac,
+    Iono,
+    Utc,
+}
+
+/// Assembles RXM-SFRBX frames into navigation messages.
+///
+/// Each distinct message is reported once, repeated broadcasts of the
+/// same data are suppressed.
+#[derive(Debug, Default)]
+pub struct NavDecoder {
+    /// Raw subframes, pages or strings by (gnss_id, sv_id, page)
+    pages: BTreeMap<(u8, u8, u8), Vec<u8>>,
+    /// Signature of the last reported message by (gnss_id, sv_id, kind)
+    reported: BTreeMap<(u8, u8, Kind), u64>,
+}
+
+impl NavDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decodes one frame, returning the messages it completed.
+    /// Frames of unsupported signals are ignored.
+    pub fn decode(&mut self, frame: &RxmSfrbxRef<'_>) -> Result<Vec<NavMessage>, NavDecodeError> {
+        let words: Vec<u32> = frame.dwrd().collect();
+        let sv_id = frame.sv_id();
+        let mut messages = Vec::new();
+        match GnssId::try_from(frame.gnss_id()) {
+            Ok(gnss @ (GnssId::GPS | GnssId::QZSS)) => {
+                gps::decode(self, gnss, sv_id, &words, &mut messages)?
+            },
+            Ok(GnssId::GALILEO) => galileo::decode(self, sv_id, &words, &mut messages)?,
+            Ok(GnssId::BEIDOU) => beidou::decode(self, sv_id, &words, &mut messages)?,
+            Ok(GnssId::GLONASS) => {
+                glonass::decode(self, sv_id, frame.freq_id(), &words, &mut messages)?
+            },
+            _ => {},
+        }
+        Ok(messages)
+    }
+
+    fn store(&mut self, gnss: GnssId, sv_id: u8, page: u8, data: &[u8]) {
+        self.pages.insert((gnss as u8, sv_id, page), data.to_vec());
+    }
+
+    fn page(&self, gnss: GnssId, sv_id: u8, page: u8) -> Option<&[u8]> {
+        self.pages
+            .get(&(gnss as u8, sv_id, page))
+            .map(Vec::as_slice)
+    }
+
+    /// Records a message as reported and returns `true` if its signature
+    /// differs from the last one of its kind
+    fn report(&mut self, gnss: GnssId, sv_id: u8, kind: Kind, signature: u64) -> bool {
+        self.reported.insert((gnss as u8, sv_id, kind), signature) != Some(signature)
+    }
+}
+
+/// GPS ephemeris from assistance data.
+/// MGA-GPS-EPH carries no week number, `week` is left zero.
+impl From<&MgaGpsEphRef<'_>> for Ephemeris {
+    fn from(eph: &MgaGpsEphRef<'_>) -> Self {
+        Self {
+            gnss: GnssId::GPS,
+            sv_id: eph.sv_id(),
+            week: 0,
+            toe: eph.toe(),
+            toc: eph.toc(),
+            iode: eph.iodc() & 0xff,
+            iodc: eph.iodc(),
+            accuracy: eph.ura_index(),
+            health: u16::from(eph.sv_health()),
+            sqrt_a: eph.sqrt_a(),
+            e: eph.e(),
+            i0: eph.i0() * SC2RAD,
+            idot: eph.idot() * SC2RAD,
+            omega0: eph.omega0() * SC2RAD,
+            omega_dot: eph.omega_dot() * SC2RAD,
+            omega: eph.omega() * SC2RAD,
+            m0: eph.m0() * SC2RAD,
+            delta_n: eph.delta_n() * SC2RAD,
+            cuc: eph.cuc(),
+            cus: eph.cus(),
+            crc: eph.crc(),
+            crs: eph.crs(),
+            cic: eph.cic(),
+            cis: eph.cis(),
+            af0: eph.af0(),
+            af1: eph.afl(),
+            af2: eph.af2(),
+            tgd: [eph.tgd(), 0.0],
+            l2_codes: 0,
+            l2p_flag: false,
+            fit_interval: eph.fit_interval() != 0,
+        }
+    }
+}
+
+/// GLONASS ephemeris from assistance data.
+/// MGA-GLO-EPH carries no frame time and day number, `tk` and `nt` are left zero.
+impl From<&MgaGloEphRef<'_>> for GlonassEphemeris {
+    fn from(eph: &MgaGloEphRef<'_>) -> Self {
+        let km = |value: i32, scale: f64| f64::from(value) * scale * 1e3;
+        Self {
+            sv_id: eph.sv_id(),
+            frequency: eph.h(),
+            tk: 0,
+            tb: u32::from(eph.tb()) * 900,
+            nt: 0,
+            position: [km(eph.x(), P2_11), km(eph.y(), P2_11), km(eph.z(), P2_11)],
+            velocity: [
+                km(eph.dx(), P2_20),
+                km(eph.dy(), P2_20),
+                km(eph.dz(), P2_20),
+            ],
+            acceleration: [
+                km(eph.ddx().into(), P2_30),
+                km(eph.ddy().into(), P2_30),
+                km(eph.ddz().into(), P2_30),
+            ],
+            tau_n: f64::from(eph.tau()) * P2_30,
+            gamma_n: f64::from(eph.gamma() as i16) * P2_40,
+            delta_tau_n: f64::from(eph.delta_tau() as i8) * P2_30,
+            // The MSB of Bn flags an unhealthy satellite
+            health: (eph.b() >> 2) & 1,
+            age: eph.e(),
+            accuracy: eph.ft(),
+            m: eph.m(),
+        }
+    }
+}
+
+/// FNV-1a hash of the raw bits of a message
+fn signature(raw: &[&[u8]]) -> u64 {
+    raw.iter()
+        .flat_map(|bytes| bytes.iter())
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+/// Reads `len` (up to 32) bits starting at bit `pos`, MSB first
+fn getbitu(buf: &[u8], pos: usize, len: usize) -> u32 {
+    (pos..pos + len).fold(0, |bits, i| {
+        (bits << 1) | u32::from((buf[i / 8] >> (7 - i % 8)) & 1)
+    })
+}
+
+/// Reads a two's complement number of `len` bits
+fn getbits(buf: &[u8], pos: usize, len: usize) -> i32 {
+    sign_extend(getbitu(buf, pos, len), len)
+}
+
+/// Reads a sign-magnitude number of `len` bits (GLONASS)
+fn getbitg(buf: &[u8], pos: usize, len: usize) -> f64 {
+    let magnitude = f64::from(getbitu(buf, pos + 1, len - 1));
+    if getbitu(buf, pos, 1) == 1 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// Reads a number split over several parts, MSB part first
+fn getbitu_parts(buf: &[u8], parts: &[(usize, usize)]) -> u32 {
+    parts.iter().fold(0, |bits, &(pos, len)| {
+        ((u64::from(bits) << len) | u64::from(getbitu(buf, pos, len))) as u32
+    })
+}
+
+/// Reads a two's complement number split over several parts
+fn getbits_parts(buf: &[u8], parts: &[(usize, usize)]) -> i32 {
+    let len = parts.iter().map(|(_, len)| len).sum();
+    sign_extend(getbitu_parts(buf, parts), len)
+}
+
+fn sign_extend(bits: u32, len: usize) -> i32 {
+    if len >= 32 {
+        bits as i32
+    } else {
+        ((bits << (32 - len)) as i32) >> (32 - len)
+    }
+}
+
+/// Writes the `len` lowest bits of `bits` starting at bit `pos`, MSB first
+fn setbitu(buf: &mut [u8], pos: usize, len: usize, bits: u32) {
+    for i in 0..len {
+        let mask = 0x80 >> ((pos + i) % 8);
+        if (bits >> (len - 1 - i)) & 1 == 1 {
+            buf[(pos + i) / 8] |= mask;
+        } else {
+            buf[(pos + i) / 8] &= !mask;
+        }
+    }
+}
+
+/// CRC-24Q as used by Galileo I/NAV and RTCM 3
+pub(crate) fn crc24q(data: &[u8]) -> u32 {
+    const POLY: u32 = 0x0186_4cfb;
+    data.iter().fold(0u32, |crc, byte| {
+        let mut crc = crc ^ (u32::from(*byte) << 16);
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x0100_0000 != 0 {
+                crc ^= POLY;
+            }
+        }
+        crc & 0x00ff_ffff
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bit_access() {
+        let mut buf = [0u8; 8];
+        setbitu(&mut buf, 3, 12, 0xabc);
+        assert_eq!(getbitu(&buf, 3, 12), 0xabc);
+        assert_eq!(getbits(&buf, 3, 12), 0xabc - 0x1000);
+        assert_eq!(getbitu_parts(&buf, &[(3, 4), (7, 8)]), 0xabc);
+        assert_eq!(getbits_parts(&buf, &[(3, 4), (7, 8)]), 0xabc - 0x1000);
+        assert_eq!(getbitg(&buf, 3, 12), -f64::from(0x2bc));
+    }
+
+    #[test]
+    fn crc24q_check_value() {
+        assert_eq!(crc24q(b"123456789"), 0x00cd_e703);
+    }
+}
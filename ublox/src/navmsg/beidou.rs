@@ -0,0 +1,344 @@
+//! BeiDou D1 and D2 navigation messages (BDS-SIS-ICD-B1I-3.0)
+
+use super::*;
+
+const PREAMBLE: u32 = 0x712;
+
+/// Parity bits of a BCH(15,11,1) code word, generator x^4 + x + 1
+fn bch_parity(info: u32) -> u32 {
+    (4..15).rev().fold(info << 4, |reg, i| {
+        if reg & (1 << i) != 0 {
+            reg ^ (0b10011 << (i - 4))
+        } else {
+            reg
+        }
+    })
+}
+
+/// Checks the BCH codes of the ten words of a subframe.
+///
+/// Words 2 to 10 hold two code words, reported as 22 information bits
+/// followed by the 8 parity bits, either grouped or still interleaved.
+fn parity_valid(buff: &[u8]) -> bool {
+    if bch_parity(getbitu(buff, 15, 11)) != getbitu(buff, 26, 4) {
+        return false;
+    }
+    (1..10).all(|word| {
+        let pos = word * 30;
+        let p1 = bch_parity(getbitu(buff, pos, 11));
+        let p2 = bch_parity(getbitu(buff, pos + 11, 11));
+        let parity = getbitu(buff, pos + 22, 8);
+        let interleaved = (0..4).rev().fold(0, |bits, i| {
+            (bits << 2) | (((p1 >> i) & 1) << 1) | ((p2 >> i) & 1)
+        });
+        parity == (p1 << 4) | p2 || parity == interleaved
+    })
+}
+
+pub(super) fn decode(
+    decoder: &mut NavDecoder,
+    sv_id: u8,
+    words: &[u32],
+    messages: &mut Vec<NavMessage>,
+) -> Result<(), NavDecodeError> {
+    if words.len() != 10 {
+        return Err(NavDecodeError::InvalidLength);
+    }
+    let mut buff = [0u8; 38];
+    for (i, word) in words.iter().enumerate() {
+        setbitu(&mut buff, i * 30, 30, word & 0x3fff_ffff);
+    }
+    if getbitu(&buff, 0, 11) != PREAMBLE {
+        return Err(NavDecodeError::InvalidFrame);
+    }
+    if !parity_valid(&buff) {
+        return Err(NavDecodeError::Parity);
+    }
+
+    let subframe = getbitu(&buff, 15, 3) as u8;
+    // GEO satellites broadcast D2, the others D1
+    if sv_id <= 5 || sv_id >= 59 {
+        if subframe == 1 {
+            decode_d2(decoder, sv_id, &buff, messages);
+        }
+    } else {
+        decode_d1(decoder, sv_id, subframe, &buff, messages);
+    }
+    Ok(())
+}
+
+const BDS: GnssId = GnssId::BEIDOU;
+
+fn decode_d1(
+    decoder: &mut NavDecoder,
+    sv_id: u8,
+    subframe: u8,
+    buff: &[u8],
+    messages: &mut Vec<NavMessage>,
+) {
+    match subframe {
+        1..=3 => {
+            decoder.store(BDS, sv_id, subframe, buff);
+            if subframe == 1 && decoder.report(BDS, 0, Kind::Iono, signature(&[&buff[15..27]])) {
+                messages.push(NavMessage::Klobuchar(BDS, klobuchar(buff)));
+            }
+            let subframes: Option<Vec<&[u8]>> =
+                (1..=3).map(|i| decoder.page(BDS, sv_id, i)).collect();
+            let complete = complete_ephemeris(sv_id, subframes, ephemeris_d1, 6);
+            report_ephemeris(decoder, complete, messages);
+        },
+        // Page 10 of subframe 5 carries the UTC parameters
+        5 if getbitu(buff, 43, 7) == 10
+            && decoder.report(BDS, 0, Kind::Utc, signature(&[&buff[6..22]])) =>
+        {
+            messages.push(NavMessage::Utc(BDS, utc(buff)));
+        },
+        _ => {},
+    }
+}
+
+fn decode_d2(decoder: &mut NavDecoder, sv_id: u8, buff: &[u8], messages: &mut Vec<NavMessage>) {
+    let page = getbitu(buff, 42, 4) as u8;
+    if !(1..=10).contains(&page) {
+        return;
+    }
+    decoder.store(BDS, sv_id, page, buff);
+    let pages: Option<Vec<&[u8]>> = (1..=10).map(|i| decoder.page(BDS, sv_id, i)).collect();
+    let complete = complete_ephemeris(sv_id, pages, ephemeris_d2, 3);
+    report_ephemeris(decoder, complete, messages);
+}
+
+/// Decodes a complete set of subframes or pages received `interval` seconds
+/// apart, along with the signature of their data
+fn complete_ephemeris(
+    sv_id: u8,
+    frames: Option<Vec<&[u8]>>,
+    ephemeris: fn(u8, &[&[u8]]) -> Ephemeris,
+    interval: u32,
+) -> Option<(Ephemeris, u64)> {
+    let frames = frames?;
+    let sow = |frame: &[u8]| getbitu_parts(frame, &[(18, 8), (30, 12)]);
+    if frames
+        .windows(2)
+        .any(|pair| sow(pair[1]) != sow(pair[0]) + interval)
+    {
+        return None;
+    }
+    // The data follows the seconds of week
+    let mut data = Vec::new();
+    for frame in &frames {
+        data.push(getbitu(frame, 42, 6) as u8);
+        data.extend_from_slice(&frame[6..]);
+    }
+    Some((ephemeris(sv_id, &frames), signature(&[&data])))
+}
+
+fn report_ephemeris(
+    decoder: &mut NavDecoder,
+    complete: Option<(Ephemeris, u64)>,
+    messages: &mut Vec<NavMessage>,
+) {
+    if let Some((eph, signature)) = complete {
+        if decoder.report(BDS, eph.sv_id, Kind::Ephemeris, signature) {
+            messages.push(NavMessage::Ephemeris(eph));
+        }
+    }
+}
+
+fn ephemeris_d1(sv_id: u8, subframes: &[&[u8]]) -> Ephemeris {
+    let (sf1, sf2, sf3) = (subframes[0], subframes[1], subframes[2]);
+    let toe = (getbitu(sf2, 290, 2) << 15) | getbitu_parts(sf3, &[(42, 10), (60, 5)]);
+    Ephemeris {
+        gnss: BDS,
+        sv_id,
+        week: getbitu(sf1, 60, 13) as u16,
+        toe: f64::from(toe) * 8.0,
+        toc: f64::from(getbitu_parts(sf1, &[(73, 9), (90, 8)])) * 8.0,
+        iode: getbitu(sf1, 287, 5) as u16,
+        iodc: getbitu(sf1, 43, 5) as u16,
+        accuracy: getbitu(sf1, 48, 4) as u8,
+        health: getbitu(sf1, 42, 1) as u16,
+        sqrt_a: f64::from(getbitu_parts(sf2, &[(250, 12), (270, 20)])) * P2_19,
+        e: f64::from(getbitu_parts(sf2, &[(132, 10), (150, 22)])) * P2_33,
+        i0: f64::from(getbits_parts(sf3, &[(65, 17), (90, 15)])) * P2_31 * SC2RAD,
+        idot: f64::from(getbits_parts(sf3, &[(189, 13), (210, 1)])) * P2_43 * SC2RAD,
+        omega0: f64::from(getbits_parts(sf3, &[(211, 21), (240, 11)])) * P2_31 * SC2RAD,
+        omega_dot: f64::from(getbits_parts(sf3, &[(131, 11), (150, 13)])) * P2_43 * SC2RAD,
+        omega: f64::from(getbits_parts(sf3, &[(251, 11), (270, 21)])) * P2_31 * SC2RAD,
+        m0: f64::from(getbits_parts(sf2, &[(92, 20), (120, 12)])) * P2_31 * SC2RAD,
+        delta_n: f64::from(getbits_parts(sf2, &[(42, 10), (60, 6)])) * P2_43 * SC2RAD,
+        cuc: f64::from(getbits_parts(sf2, &[(66, 16), (90, 2)])) * P2_31,
+        cus: f64::from(getbits(sf2, 180, 18)) * P2_31,
+        crc: f64::from(getbits_parts(sf2, &[(198, 4), (210, 14)])) * P2_6,
+        crs: f64::from(getbits_parts(sf2, &[(224, 8), (240, 10)])) * P2_6,
+        cic: f64::from(getbits_parts(sf3, &[(105, 7), (120, 11)])) * P2_31,
+        cis: f64::from(getbits_parts(sf3, &[(163, 9), (180, 9)])) * P2_31,
+        af0: f64::from(getbits_parts(sf1, &[(225, 7), (240, 17)])) * P2_33,
+        af1: f64::from(getbits_parts(sf1, &[(257, 5), (270, 17)])) * P2_50,
+        af2: f64::from(getbits(sf1, 214, 11)) * P2_66,
+        tgd: [
+            f64::from(getbits(sf1, 98, 10)) * 0.1e-9,
+            f64::from(getbits_parts(sf1, &[(108, 4), (120, 6)])) * 0.1e-9,
+        ],
+        l2_codes: 0,
+        l2p_flag: false,
+        fit_interval: false,
+    }
+}
+
+/// Joins a parameter split over two pages, `lsb_len` bits in the second one
+fn join(msb: u32, lsb: u32, lsb_len: u32, len: usize) -> i32 {
+    sign_extend((msb << lsb_len) | lsb, len)
+}
+
+fn ephemeris_d2(sv_id: u8, pages: &[&[u8]]) -> Ephemeris {
+    let [p1, _, p3, p4, p5, p6, p7, p8, p9, p10] = pages else {
+        unreachable!("D2 ephemeris spans ten pages");
+    };
+    let af1 = join(
+        getbitu(p3, 132, 4),
+        getbitu_parts(p4, &[(46, 6), (60, 12)]),
+        18,
+        22,
+    );
+    let cuc = join(getbitu(p4, 120, 14), getbitu(p5, 46, 4), 4, 18);
+    let e = (getbitu(p5, 124, 10) << 22) | getbitu_parts(p6, &[(46, 6), (60, 16)]);
+    let cic = join(
+        getbitu(p6, 124, 10),
+        getbitu_parts(p7, &[(46, 6), (60, 2)]),
+        8,
+        18,
+    );
+    let i0 = join(
+        getbitu_parts(p7, &[(105, 7), (120, 14)]),
+        getbitu_parts(p8, &[(46, 6), (60, 5)]),
+        11,
+        32,
+    );
+    let omega_dot = join(
+        getbitu_parts(p8, &[(109, 3), (120, 16)]),
+        getbitu(p9, 46, 5),
+        5,
+        24,
+    );
+    let omega = join(
+        getbitu_parts(p9, &[(99, 13), (120, 14)]),
+        getbitu(p10, 46, 5),
+        5,
+        32,
+    );
+    Ephemeris {
+        gnss: BDS,
+        sv_id,
+        week: getbitu(p1, 64, 13) as u16,
+        toe: f64::from(getbitu_parts(p7, &[(80, 2), (90, 15)])) * 8.0,
+        toc: f64::from(getbitu_parts(p1, &[(77, 5), (90, 12)])) * 8.0,
+        iode: getbitu(p4, 91, 5) as u16,
+        iodc: getbitu(p1, 47, 5) as u16,
+        accuracy: getbitu(p1, 60, 4) as u8,
+        health: getbitu(p1, 46, 1) as u16,
+        sqrt_a: f64::from(getbitu_parts(p6, &[(76, 6), (90, 22), (120, 4)])) * P2_19,
+        e: f64::from(e) * P2_33,
+        i0: f64::from(i0) * P2_31 * SC2RAD,
+        idot: f64::from(getbits_parts(p10, &[(51, 1), (60, 13)])) * P2_43 * SC2RAD,
+        omega0: f64::from(getbits_parts(p9, &[(51, 1), (60, 22), (90, 9)])) * P2_31 * SC2RAD,
+        omega_dot: f64::from(omega_dot) * P2_43 * SC2RAD,
+        omega: f64::from(omega) * P2_31 * SC2RAD,
+        m0: f64::from(getbits_parts(p5, &[(50, 2), (60, 22), (90, 8)])) * P2_31 * SC2RAD,
+        delta_n: f64::from(getbits(p4, 96, 16)) * P2_43 * SC2RAD,
+        cuc: f64::from(cuc) * P2_31,
+        cus: f64::from(getbits_parts(p5, &[(98, 14), (120, 4)])) * P2_31,
+        crc: f64::from(getbits_parts(p8, &[(65, 17), (90, 1)])) * P2_6,
+        crs: f64::from(getbits(p8, 91, 18)) * P2_6,
+        cic: f64::from(cic) * P2_31,
+        cis: f64::from(getbits(p7, 62, 18)) * P2_31,
+        af0: f64::from(getbits_parts(p3, &[(100, 12), (120, 12)])) * P2_33,
+        af1: f64::from(af1) * P2_50,
+        af2: f64::from(getbits_parts(p4, &[(72, 10), (90, 1)])) * P2_66,
+        tgd: [
+            f64::from(getbits(p1, 102, 10)) * 0.1e-9,
+            f64::from(getbits(p1, 120, 10)) * 0.1e-9,
+        ],
+        l2_codes: 0,
+        l2p_flag: false,
+        fit_interval: false,
+    }
+}
+
+fn klobuchar(sf1: &[u8]) -> KlobucharModel {
+    KlobucharModel {
+        alpha: [
+            f64::from(getbits(sf1, 126, 8)) * P2_30,
+            f64::from(getbits(sf1, 134, 8)) * P2_27,
+            f64::from(getbits(sf1, 150, 8)) * P2_24,
+            f64::from(getbits(sf1, 158, 8)) * P2_24,
+        ],
+        beta: [
+            f64::from(getbits_parts(sf1, &[(166, 6), (180, 2)])) * f64::from(1u32 << 11),
+            f64::from(getbits(sf1, 182, 8)) * f64::from(1u32 << 14),
+            f64::from(getbits(sf1, 190, 8)) * f64::from(1u32 << 16),
+            f64::from(getbits_parts(sf1, &[(198, 4), (210, 4)])) * f64::from(1u32 << 16),
+        ],
+    }
+}
+
+fn utc(sf5: &[u8]) -> UtcParameters {
+    UtcParameters {
+        a0: f64::from(getbits_parts(sf5, &[(90, 22), (120, 10)])) * P2_30,
+        a1: f64::from(getbits_parts(sf5, &[(130, 12), (150, 12)])) * P2_50,
+        // The BDT to UTC polynomial has no reference time, it is evaluated at the BDT seconds of week
+        tot: 0,
+        wnt: 0,
+        delta_t_ls: getbits_parts(sf5, &[(50, 2), (60, 6)]) as i8,
+        wn_lsf: getbitu(sf5, 74, 8) as u16,
+        dn: getbitu(sf5, 162, 8) as u8,
+        delta_t_lsf: getbits(sf5, 66, 8) as i8,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn encode(buff: &mut [u8; 38]) -> Vec<u32> {
+        setbitu(buff, 0, 11, PREAMBLE);
+        let parity = bch_parity(getbitu(buff, 15, 11));
+        setbitu(buff, 26, 4, parity);
+        for word in 1..10 {
+            let pos = word * 30;
+            let p1 = bch_parity(getbitu(buff, pos, 11));
+            let p2 = bch_parity(getbitu(buff, pos + 11, 11));
+            setbitu(buff, pos + 22, 8, (p1 << 4) | p2);
+        }
+        (0..10).map(|word| getbitu(buff, word * 30, 30)).collect()
+    }
+
+    #[test]
+    fn utc_page() {
+        let mut buff = [0u8; 38];
+        setbitu(&mut buff, 15, 3, 5);
+        setbitu(&mut buff, 43, 7, 10);
+        setbitu(&mut buff, 50, 2, 0);
+        setbitu(&mut buff, 60, 6, 4);
+        setbitu(&mut buff, 66, 8, 5);
+        setbitu(&mut buff, 120, 10, 0x3ff);
+        setbitu(&mut buff, 90, 22, 0x3f_ffff);
+        let words = encode(&mut buff);
+
+        let mut decoder = NavDecoder::new();
+        let mut messages = Vec::new();
+        decode(&mut decoder, 20, &words, &mut messages).unwrap();
+        let [NavMessage::Utc(GnssId::BEIDOU, utc)] = messages.as_slice() else {
+            panic!("expected UTC parameters, got {:?}", messages);
+        };
+        assert_eq!((utc.delta_t_ls, utc.delta_t_lsf), (4, 5));
+        assert_eq!(utc.a0, -P2_30);
+
+        let mut corrupted = words.clone();
+        corrupted[3] ^= 1 << 12;
+        assert_eq!(
+            decode(&mut decoder, 20, &corrupted, &mut messages),
+            Err(NavDecodeError::Parity)
+        );
+    }
+}
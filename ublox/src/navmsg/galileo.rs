@@ -0,0 +1,217 @@
+//! Galileo I/NAV nominal pages (Galileo OS SIS ICD)
+
+use super::*;
+
+/// Checks and assembles the 128 bit word of an even/odd I/NAV page pair.
+///
+/// u-blox reports each 120 bit half page in four words, even part first.
+/// Returns `None` for alert pages.
+fn page_word(words: &[u32]) -> Result<Option<[u8; 16]>, NavDecodeError> {
+    let mut page = [0u8; 32];
+    for (i, word) in words.iter().enumerate() {
+        page[i * 4..i * 4 + 4].copy_from_slice(&word.to_be_bytes());
+    }
+    let (even, odd) = page.split_at(16);
+    if getbitu(even, 0, 1) != 0 || getbitu(odd, 0, 1) != 1 {
+        return Err(NavDecodeError::InvalidFrame);
+    }
+    if getbitu(even, 1, 1) == 1 || getbitu(odd, 1, 1) == 1 {
+        return Ok(None);
+    }
+
+    // The CRC covers the 114 bits of the even and the first 82 bits of the odd part
+    let mut crc_data = [0u8; 25];
+    for i in 0..114 {
+        setbitu(&mut crc_data, 4 + i, 1, getbitu(even, i, 1));
+    }
+    for i in 0..82 {
+        setbitu(&mut crc_data, 118 + i, 1, getbitu(odd, i, 1));
+    }
+    if crc24q(&crc_data) != getbitu(odd, 82, 24) {
+        return Err(NavDecodeError::Parity);
+    }
+
+    let mut word = [0u8; 16];
+    for i in 0..112 {
+        setbitu(&mut word, i, 1, getbitu(even, 2 + i, 1));
+    }
+    for i in 0..16 {
+        setbitu(&mut word, 112 + i, 1, getbitu(odd, 2 + i, 1));
+    }
+    Ok(Some(word))
+}
+
+pub(super) fn decode(
+    decoder: &mut NavDecoder,
+    sv_id: u8,
+    words: &[u32],
+    messages: &mut Vec<NavMessage>,
+) -> Result<(), NavDecodeError> {
+    const GAL: GnssId = GnssId::GALILEO;
+    if words.len() != 8 {
+        return Err(NavDecodeError::InvalidLength);
+    }
+    let Some(word) = page_word(words)? else {
+        return Ok(());
+    };
+
+    let word_type = getbitu(&word, 0, 6) as u8;
+    match word_type {
+        1..=5 => {
+            decoder.store(GAL, sv_id, word_type, &word);
+            if word_type == 5 && decoder.report(GAL, 0, Kind::Iono, signature(&[&word[..6]])) {
+                messages.push(NavMessage::NeQuick(nequick(&word)));
+            }
+            let pages: Option<Vec<&[u8]>> = (1..=5).map(|i| decoder.page(GAL, sv_id, i)).collect();
+            let complete = pages.and_then(|pages| {
+                // Word 5 also carries the time, only its group delays and health are compared
+                let raw = [pages[0], pages[1], pages[2], pages[3], &pages[4][5..10]];
+                ephemeris(sv_id, &pages).map(|eph| (eph, signature(&raw)))
+            });
+            if let Some((eph, signature)) = complete {
+                if decoder.report(GAL, sv_id, Kind::Ephemeris, signature) {
+                    messages.push(NavMessage::Ephemeris(eph));
+                }
+            }
+        },
+        6 if decoder.report(GAL, 0, Kind::Utc, signature(&[&word[..13]])) => {
+            messages.push(NavMessage::Utc(GAL, utc(&word)));
+        },
+        _ => {},
+    }
+    Ok(())
+}
+
+fn ephemeris(sv_id: u8, words: &[&[u8]]) -> Option<Ephemeris> {
+    let [w1, w2, w3, w4, w5] = words else {
+        return None;
+    };
+    let iod_nav = getbitu(w1, 6, 10);
+    if [w2, w3, w4].iter().any(|w| getbitu(w, 6, 10) != iod_nav) {
+        return None;
+    }
+    // Word 5: ionosphere (6 + 41 bits), BGDs, health, GST
+    let mut i = 47;
+    let bgd_e5a = f64::from(getbits(w5, i, 10)) * P2_32;
+    i += 10;
+    let bgd_e5b = f64::from(getbits(w5, i, 10)) * P2_32;
+    i += 10;
+    let e5b_hs = getbitu(w5, i, 2);
+    let e1b_hs = getbitu(w5, i + 2, 2);
+    let e5b_dvs = getbitu(w5, i + 4, 1);
+    let e1b_dvs = getbitu(w5, i + 5, 1);
+    let week = getbitu(w5, i + 6, 12);
+
+    Some(Ephemeris {
+        gnss: GnssId::GALILEO,
+        sv_id,
+        week: week as u16,
+        toe: f64::from(getbitu(w1, 16, 14)) * 60.0,
+        toc: f64::from(getbitu(w4, 54, 14)) * 60.0,
+        iode: iod_nav as u16,
+        iodc: iod_nav as u16,
+        accuracy: getbitu(w3, 120, 8) as u8,
+        // RINEX bit layout: E1B DVS, E1B HS, E5a DVS, E5a HS, E5b DVS, E5b HS
+        health: (e1b_dvs | (e1b_hs << 1) | (e5b_dvs << 6) | (e5b_hs << 7)) as u16,
+        sqrt_a: f64::from(getbitu(w1, 94, 32)) * P2_19,
+        e: f64::from(getbitu(w1, 62, 32)) * P2_33,
+        i0: f64::from(getbits(w2, 48, 32)) * P2_31 * SC2RAD,
+        idot: f64::from(getbits(w2, 112, 14)) * P2_43 * SC2RAD,
+        omega0: f64::from(getbits(w2, 16, 32)) * P2_31 * SC2RAD,
+        omega_dot: f64::from(getbits(w3, 16, 24)) * P2_43 * SC2RAD,
+        omega: f64::from(getbits(w2, 80, 32)) * P2_31 * SC2RAD,
+        m0: f64::from(getbits(w1, 30, 32)) * P2_31 * SC2RAD,
+        delta_n: f64::from(getbits(w3, 40, 16)) * P2_43 * SC2RAD,
+        cuc: f64::from(getbits(w3, 56, 16)) * P2_29,
+        cus: f64::from(getbits(w3, 72, 16)) * P2_29,
+        crc: f64::from(getbits(w3, 88, 16)) * P2_5,
+        crs: f64::from(getbits(w3, 104, 16)) * P2_5,
+        cic: f64::from(getbits(w4, 22, 16)) * P2_29,
+        cis: f64::from(getbits(w4, 38, 16)) * P2_29,
+        af0: f64::from(getbits(w4, 68, 31)) * P2_34,
+        af1: f64::from(getbits(w4, 99, 21)) * P2_46,
+        af2: f64::from(getbits(w4, 120, 6)) * P2_59,
+        tgd: [bgd_e5a, bgd_e5b],
+        l2_codes: 0,
+        l2p_flag: false,
+        fit_interval: false,
+    })
+}
+
+fn nequick(word: &[u8]) -> NeQuickModel {
+    NeQuickModel {
+        ai: [
+            f64::from(getbitu(word, 6, 11)) * 0.25,
+            f64::from(getbits(word, 17, 11)) * P2_8,
+            f64::from(getbits(word, 28, 14)) * P2_15,
+        ],
+        storm_flags: getbitu(word, 42, 5) as u8,
+    }
+}
+
+fn utc(word: &[u8]) -> UtcParameters {
+    UtcParameters {
+        a0: f64::from(getbits(word, 6, 32)) * P2_30,
+        a1: f64::from(getbits(word, 38, 24)) * P2_50,
+        delta_t_ls: getbits(word, 62, 8) as i8,
+        tot: getbitu(word, 70, 8) * 3600,
+        wnt: getbitu(word, 78, 8) as u16,
+        wn_lsf: getbitu(word, 86, 8) as u16,
+        dn: getbitu(word, 94, 3) as u8,
+        delta_t_lsf: getbits(word, 97, 8) as i8,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn encode(word: &[u8; 16]) -> Vec<u32> {
+        let mut page = [0u8; 32];
+        setbitu(&mut page, 128, 1, 1);
+        for i in 0..112 {
+            setbitu(&mut page, 2 + i, 1, getbitu(word, i, 1));
+        }
+        for i in 0..16 {
+            setbitu(&mut page, 130 + i, 1, getbitu(word, 112 + i, 1));
+        }
+        let mut crc_data = [0u8; 25];
+        for i in 0..114 {
+            setbitu(&mut crc_data, 4 + i, 1, getbitu(&page, i, 1));
+        }
+        for i in 0..82 {
+            setbitu(&mut crc_data, 118 + i, 1, getbitu(&page, 128 + i, 1));
+        }
+        setbitu(&mut page, 210, 24, crc24q(&crc_data));
+        page.chunks(4)
+            .map(|bytes| u32::from_be_bytes(bytes.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn utc_word() {
+        let mut word = [0u8; 16];
+        setbitu(&mut word, 0, 6, 6);
+        setbitu(&mut word, 6, 32, (-3i32) as u32);
+        setbitu(&mut word, 62, 8, 18);
+        setbitu(&mut word, 70, 8, 24);
+        setbitu(&mut word, 97, 8, 18);
+        let words = encode(&word);
+
+        let mut decoder = NavDecoder::new();
+        let mut messages = Vec::new();
+        decode(&mut decoder, 11, &words, &mut messages).unwrap();
+        let [NavMessage::Utc(GnssId::GALILEO, utc)] = messages.as_slice() else {
+            panic!("expected UTC parameters, got {:?}", messages);
+        };
+        assert_eq!(utc.a0, -3.0 * P2_30);
+        assert_eq!((utc.delta_t_ls, utc.delta_t_lsf, utc.tot), (18, 18, 86400));
+
+        let mut corrupted = words.clone();
+        corrupted[1] ^= 1;
+        assert_eq!(
+            decode(&mut decoder, 11, &corrupted, &mut messages),
+            Err(NavDecodeError::Parity)
+        );
+    }
+}
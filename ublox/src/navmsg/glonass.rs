@@ -0,0 +1,181 @@
+//! GLONASS L1/L2 OF navigation strings (GLONASS ICD 5.1)
+
+use super::*;
+
+/// Bit `n` of a string, b85 is the idle bit and b1..b8 the check bits β1..β8
+fn bit(buff: &[u8], n: usize) -> u32 {
+    getbitu(buff, 85 - n, 1)
+}
+
+/// Sums of the data bits b9..b85 entering the checks C1..C7 and CΣ
+fn data_checks(buff: &[u8]) -> ([u32; 7], u32) {
+    let mut checks = [0u32; 7];
+    let mut sum = 0;
+    // Data bits take the Hamming positions that are not a power of two
+    let mut position = 2u32;
+    for n in 9..=85 {
+        position += 1;
+        while position.is_power_of_two() {
+            position += 1;
+        }
+        for (k, check) in checks.iter_mut().enumerate() {
+            if position & (1 << k) != 0 {
+                *check ^= bit(buff, n);
+            }
+        }
+        sum ^= bit(buff, n);
+    }
+    (checks, sum)
+}
+
+/// Checks the Hamming code of a string, `buff` holds bit `n` at index `85 - n`
+fn hamming_valid(buff: &[u8]) -> bool {
+    let (checks, sum) = data_checks(buff);
+    checks
+        .iter()
+        .enumerate()
+        .all(|(k, check)| check ^ bit(buff, k + 1) == 0)
+        && (1..=8).fold(sum, |sum, n| sum ^ bit(buff, n)) == 0
+}
+
+pub(super) fn decode(
+    decoder: &mut NavDecoder,
+    sv_id: u8,
+    freq_id: u8,
+    words: &[u32],
+    messages: &mut Vec<NavMessage>,
+) -> Result<(), NavDecodeError> {
+    const GLO: GnssId = GnssId::GLONASS;
+    if words.len() != 4 {
+        return Err(NavDecodeError::InvalidLength);
+    }
+    let mut buff = [0u8; 16];
+    for (i, word) in words.iter().enumerate() {
+        buff[i * 4..i * 4 + 4].copy_from_slice(&word.to_be_bytes());
+    }
+    if !hamming_valid(&buff) {
+        return Err(NavDecodeError::Parity);
+    }
+
+    match getbitu(&buff, 1, 4) as u8 {
+        string @ 1..=4 => {
+            decoder.store(GLO, sv_id, string, &buff);
+            let strings: Option<Vec<&[u8]>> =
+                (1..=4).map(|i| decoder.page(GLO, sv_id, i)).collect();
+            let complete = strings.and_then(|strings| {
+                // String 1 starts with the frame time, the rest identifies the data
+                let raw = [
+                    &strings[0][3..11],
+                    &strings[1][..11],
+                    &strings[2][..11],
+                    &strings[3][..11],
+                ];
+                ephemeris(sv_id, freq_id, &strings).map(|eph| (eph, signature(&raw)))
+            });
+            if let Some((eph, signature)) = complete {
+                if decoder.report(GLO, sv_id, Kind::Ephemeris, signature) {
+                    messages.push(NavMessage::GlonassEphemeris(eph));
+                }
+            }
+        },
+        5 if decoder.report(GLO, 0, Kind::Utc, signature(&[&buff[..10]])) => {
+            messages.push(NavMessage::GlonassTime(time_parameters(&buff)));
+        },
+        _ => {},
+    }
+    Ok(())
+}
+
+fn ephemeris(sv_id: u8, freq_id: u8, strings: &[&[u8]]) -> Option<GlonassEphemeris> {
+    let [s1, s2, s3, s4] = strings else {
+        return None;
+    };
+    // u-blox appends the frame number the string was received in
+    let frame = getbitu(s1, 112, 16);
+    if [s2, s3, s4].iter().any(|s| getbitu(s, 112, 16) != frame) {
+        return None;
+    }
+
+    // Position, velocity and acceleration, broadcast in km, km/s and km/s²
+    let coordinate = |s: &[u8], i: usize| {
+        (
+            getbitg(s, i + 29, 27) * P2_11 * 1e3,
+            getbitg(s, i, 24) * P2_20 * 1e3,
+            getbitg(s, i + 24, 5) * P2_30 * 1e3,
+        )
+    };
+    let (x, vx, ax) = coordinate(s1, 21);
+    let (y, vy, ay) = coordinate(s2, 21);
+    let (z, vz, az) = coordinate(s3, 21);
+
+    Some(GlonassEphemeris {
+        sv_id,
+        frequency: freq_id as i8 - 7,
+        tk: getbitu(s1, 9, 5) * 3600 + getbitu(s1, 14, 6) * 60 + getbitu(s1, 20, 1) * 30,
+        tb: getbitu(s2, 9, 7) * 900,
+        nt: getbitu(s4, 59, 11) as u16,
+        position: [x, y, z],
+        velocity: [vx, vy, vz],
+        acceleration: [ax, ay, az],
+        tau_n: getbitg(s4, 5, 22) * P2_30,
+        gamma_n: getbitg(s3, 6, 11) * P2_40,
+        delta_tau_n: getbitg(s4, 27, 5) * P2_30,
+        health: getbitu(s2, 5, 1) as u8,
+        age: getbitu(s4, 32, 5) as u8,
+        accuracy: getbitu(s4, 52, 4) as u8,
+        m: getbitu(s4, 75, 2) as u8,
+    })
+}
+
+fn time_parameters(buff: &[u8]) -> GlonassTimeParameters {
+    GlonassTimeParameters {
+        na: getbitu(buff, 5, 11) as u16,
+        tau_c: getbitg(buff, 16, 32) * P2_31,
+        n4: getbitu(buff, 49, 5) as u8,
+        tau_gps: getbitg(buff, 54, 22) * P2_30,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn encode(buff: &mut [u8; 16]) -> Vec<u32> {
+        let (checks, sum) = data_checks(buff);
+        for (k, check) in checks.iter().enumerate() {
+            setbitu(buff, 84 - k, 1, *check);
+        }
+        let beta8 = checks.iter().fold(sum, |sum, check| sum ^ check);
+        setbitu(buff, 77, 1, beta8);
+        buff.chunks(4)
+            .map(|bytes| u32::from_be_bytes(bytes.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn time_string() {
+        let mut buff = [0u8; 16];
+        setbitu(&mut buff, 1, 4, 5);
+        setbitu(&mut buff, 5, 11, 1021);
+        // τc is sign-magnitude
+        setbitu(&mut buff, 16, 32, 0x8000_0010);
+        setbitu(&mut buff, 49, 5, 7);
+        let words = encode(&mut buff);
+
+        let mut decoder = NavDecoder::new();
+        let mut messages = Vec::new();
+        decode(&mut decoder, 3, 8, &words, &mut messages).unwrap();
+        let [NavMessage::GlonassTime(time)] = messages.as_slice() else {
+            panic!("expected time parameters, got {:?}", messages);
+        };
+        assert_eq!((time.na, time.n4), (1021, 7));
+        assert_eq!(time.tau_c, -16.0 * P2_31);
+
+        let mut corrupted = words.clone();
+        corrupted[1] ^= 1 << 7;
+        assert_eq!(
+            decode(&mut decoder, 3, 8, &corrupted, &mut messages),
+            Err(NavDecodeError::Parity)
+        );
+    }
+}
@@ -0,0 +1,264 @@
+//! GPS and QZSS LNAV subframes (IS-GPS-200, IS-QZSS-PNT)
+
+use super::*;
+
+const PREAMBLE: u32 = 0x8b;
+
+/// Parity equations of D25..D30 over the data bits d1..d24 (d1 is the MSB)
+const PARITY_MASKS: [u32; 6] = [0xec7cd2, 0x763e69, 0xbb1f34, 0x5d8f9a, 0xaec7cd, 0x2dea27];
+
+/// Parity bits D25..D30 of the data bits `d` of a word, following the word `previous`
+fn word_parity(d: u32, previous: u32) -> u32 {
+    let (d29, d30) = ((previous >> 1) & 1, previous & 1);
+    PARITY_MASKS
+        .iter()
+        .enumerate()
+        .fold(0, |parity, (j, mask)| {
+            let star = if matches!(j, 0 | 2 | 5) { d29 } else { d30 };
+            (parity << 1) | (star ^ ((d & mask).count_ones() & 1))
+        })
+}
+
+/// Checks the parity of the ten 30 bit words and packs their data bits.
+///
+/// u-blox reports the data bits with the polarity already corrected, the
+/// parity bits D29* and D30* of the previous word are taken from its two LSBs.
+/// They are zero before the first word, as the last word of every subframe ends with 00.
+fn subframe_data(words: &[u32]) -> Result<[u8; 30], NavDecodeError> {
+    let mut data = [0u8; 30];
+    let mut previous = 0u32;
+    for (i, word) in words.iter().enumerate() {
+        let d = (word >> 6) & 0x00ff_ffff;
+        if word_parity(d, previous) != word & 0x3f {
+            return Err(NavDecodeError::Parity);
+        }
+        setbitu(&mut data, i * 24, 24, d);
+        previous = *word;
+    }
+    Ok(data)
+}
+
+pub(super) fn decode(
+    decoder: &mut NavDecoder,
+    gnss: GnssId,
+    sv_id: u8,
+    words: &[u32],
+    messages: &mut Vec<NavMessage>,
+) -> Result<(), NavDecodeError> {
+    if words.len() != 10 {
+        // Other signals (CNAV on L2C and L5) are not supported
+        return Ok(());
+    }
+    let data = subframe_data(words)?;
+    if getbitu(&data, 0, 8) != PREAMBLE {
+        return Err(NavDecodeError::InvalidFrame);
+    }
+
+    match getbitu(&data, 43, 3) {
+        id @ 1..=3 => {
+            decoder.store(gnss, sv_id, id as u8, &data);
+            let subframes = (
+                decoder.page(gnss, sv_id, 1),
+                decoder.page(gnss, sv_id, 2),
+                decoder.page(gnss, sv_id, 3),
+            );
+            let complete = match subframes {
+                (Some(sf1), Some(sf2), Some(sf3)) => ephemeris(gnss, sv_id, sf1, sf2, sf3)
+                    // The words after the HOW carry the ephemeris
+                    .map(|eph| (eph, signature(&[&sf1[6..], &sf2[6..], &sf3[6..]]))),
+                _ => None,
+            };
+            if let Some((eph, signature)) = complete {
+                if decoder.report(gnss, sv_id, Kind::Ephemeris, signature) {
+                    messages.push(NavMessage::Ephemeris(eph));
+                }
+            }
+        },
+        4 | 5 => {
+            let page_sv_id = getbitu(&data, 50, 6) as u8;
+            match page_sv_id {
+                1..=32
+                    if gnss == GnssId::GPS
+                        && decoder.report(
+                            gnss,
+                            page_sv_id,
+                            Kind::Almanac,
+                            signature(&[&data[6..]]),
+                        ) =>
+                {
+                    messages.push(NavMessage::Almanac(almanac(gnss, page_sv_id, &data)));
+                },
+                56 => {
+                    if decoder.report(gnss, 0, Kind::Iono, signature(&[&data[7..15]])) {
+                        messages.push(NavMessage::Klobuchar(gnss, klobuchar(&data)));
+                    }
+                    if decoder.report(gnss, 0, Kind::Utc, signature(&[&data[15..]])) {
+                        messages.push(NavMessage::Utc(gnss, utc(&data)));
+                    }
+                },
+                _ => {},
+            }
+        },
+        _ => return Err(NavDecodeError::InvalidFrame),
+    }
+    Ok(())
+}
+
+fn ephemeris(gnss: GnssId, sv_id: u8, sf1: &[u8], sf2: &[u8], sf3: &[u8]) -> Option<Ephemeris> {
+    let iodc = (getbitu(sf1, 70, 2) << 8) | getbitu(sf1, 168, 8);
+    let iode = getbitu(sf2, 48, 8);
+    if iode != getbitu(sf3, 216, 8) || iode != iodc & 0xff {
+        return None;
+    }
+    let tgd = getbits(sf1, 160, 8);
+    let sqrt_a = f64::from(getbitu(sf2, 184, 32)) * P2_19;
+    Some(Ephemeris {
+        gnss,
+        sv_id,
+        week: getbitu(sf1, 48, 10) as u16,
+        toe: f64::from(getbitu(sf2, 216, 16)) * 16.0,
+        toc: f64::from(getbitu(sf1, 176, 16)) * 16.0,
+        iode: iode as u16,
+        iodc: iodc as u16,
+        accuracy: getbitu(sf1, 60, 4) as u8,
+        health: getbitu(sf1, 64, 6) as u16,
+        sqrt_a,
+        e: f64::from(getbitu(sf2, 136, 32)) * P2_33,
+        i0: f64::from(getbits(sf3, 112, 32)) * P2_31 * SC2RAD,
+        idot: f64::from(getbits(sf3, 224, 14)) * P2_43 * SC2RAD,
+        omega0: f64::from(getbits(sf3, 64, 32)) * P2_31 * SC2RAD,
+        omega_dot: f64::from(getbits(sf3, 192, 24)) * P2_43 * SC2RAD,
+        omega: f64::from(getbits(sf3, 160, 32)) * P2_31 * SC2RAD,
+        m0: f64::from(getbits(sf2, 88, 32)) * P2_31 * SC2RAD,
+        delta_n: f64::from(getbits(sf2, 72, 16)) * P2_43 * SC2RAD,
+        cuc: f64::from(getbits(sf2, 120, 16)) * P2_29,
+        cus: f64::from(getbits(sf2, 168, 16)) * P2_29,
+        crc: f64::from(getbits(sf3, 144, 16)) * P2_5,
+        crs: f64::from(getbits(sf2, 56, 16)) * P2_5,
+        cic: f64::from(getbits(sf3, 48, 16)) * P2_29,
+        cis: f64::from(getbits(sf3, 96, 16)) * P2_29,
+        af0: f64::from(getbits(sf1, 216, 22)) * P2_31,
+        af1: f64::from(getbits(sf1, 200, 16)) * P2_43,
+        af2: f64::from(getbits(sf1, 192, 8)) * P2_55,
+        // -128 means the group delay is not available
+        tgd: [
+            if tgd == -128 {
+                0.0
+            } else {
+                f64::from(tgd) * P2_31
+            },
+            0.0,
+        ],
+        l2_codes: getbitu(sf1, 58, 2) as u8,
+        l2p_flag: getbitu(sf1, 72, 1) == 1,
+        fit_interval: getbitu(sf2, 232, 1) == 1,
+    })
+}
+
+fn almanac(gnss: GnssId, sv_id: u8, data: &[u8]) -> Almanac {
+    let af0 = (getbitu(data, 216, 8) << 3) | getbitu(data, 235, 3);
+    Almanac {
+        gnss,
+        sv_id,
+        toa: getbitu(data, 72, 8) * 4096,
+        e: f64::from(getbitu(data, 56, 16)) * P2_21,
+        // Inclination is relative to 0.3 semicircles
+        i0: (0.3 + f64::from(getbits(data, 80, 16)) * P2_19) * SC2RAD,
+        omega_dot: f64::from(getbits(data, 96, 16)) * P2_38 * SC2RAD,
+        health: getbitu(data, 112, 8) as u8,
+        sqrt_a: f64::from(getbitu(data, 120, 24)) * P2_11,
+        omega0: f64::from(getbits(data, 144, 24)) * P2_23 * SC2RAD,
+        omega: f64::from(getbits(data, 168, 24)) * P2_23 * SC2RAD,
+        m0: f64::from(getbits(data, 192, 24)) * P2_23 * SC2RAD,
+        af0: f64::from(sign_extend(af0, 11)) * P2_20,
+        af1: f64::from(getbits(data, 224, 11)) * P2_38,
+    }
+}
+
+fn klobuchar(data: &[u8]) -> KlobucharModel {
+    KlobucharModel {
+        alpha: [
+            f64::from(getbits(data, 56, 8)) * P2_30,
+            f64::from(getbits(data, 64, 8)) * P2_27,
+            f64::from(getbits(data, 72, 8)) * P2_24,
+            f64::from(getbits(data, 80, 8)) * P2_24,
+        ],
+        beta: [
+            f64::from(getbits(data, 88, 8)) * f64::from(1u32 << 11),
+            f64::from(getbits(data, 96, 8)) * f64::from(1u32 << 14),
+            f64::from(getbits(data, 104, 8)) * f64::from(1u32 << 16),
+            f64::from(getbits(data, 112, 8)) * f64::from(1u32 << 16),
+        ],
+    }
+}
+
+fn utc(data: &[u8]) -> UtcParameters {
+    UtcParameters {
+        a1: f64::from(getbits(data, 120, 24)) * P2_50,
+        a0: f64::from(getbits(data, 144, 32)) * P2_30,
+        tot: getbitu(data, 176, 8) << 12,
+        wnt: getbitu(data, 184, 8) as u16,
+        delta_t_ls: getbits(data, 192, 8) as i8,
+        wn_lsf: getbitu(data, 200, 8) as u16,
+        dn: getbitu(data, 208, 8) as u8,
+        delta_t_lsf: getbits(data, 216, 8) as i8,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn encode(data: &[u8; 30]) -> Vec<u32> {
+        let mut previous = 0;
+        (0..10)
+            .map(|i| {
+                let d = getbitu(data, i * 24, 24);
+                previous = (d << 6) | word_parity(d, previous);
+                previous
+            })
+            .collect()
+    }
+
+    fn subframe(id: u32, fields: &[(usize, usize, u32)]) -> Vec<u32> {
+        let mut data = [0u8; 30];
+        setbitu(&mut data, 0, 8, PREAMBLE);
+        setbitu(&mut data, 43, 3, id);
+        for &(pos, len, value) in fields {
+            setbitu(&mut data, pos, len, value);
+        }
+        encode(&data)
+    }
+
+    #[test]
+    fn ephemeris_from_subframes() {
+        let sf1 = subframe(
+            1,
+            &[(48, 10, 210), (70, 2, 0), (168, 8, 77), (176, 16, 225)],
+        );
+        let sf2 = subframe(2, &[(48, 8, 77), (184, 32, 2_702_000_000), (216, 16, 225)]);
+        let sf3 = subframe(3, &[(216, 8, 77), (224, 14, 0x3fff)]);
+
+        let mut decoder = NavDecoder::new();
+        let mut messages = Vec::new();
+        for words in [&sf1, &sf2, &sf3, &sf1] {
+            decode(&mut decoder, GnssId::GPS, 5, words, &mut messages).unwrap();
+        }
+        // Repeated subframes don't report the ephemeris again
+        assert_eq!(messages.len(), 1);
+        let NavMessage::Ephemeris(eph) = &messages[0] else {
+            panic!("expected an ephemeris, got {:?}", messages[0]);
+        };
+        assert_eq!((eph.sv_id, eph.week, eph.iode, eph.iodc), (5, 210, 77, 77));
+        assert_eq!((eph.toe, eph.toc), (3600.0, 3600.0));
+        assert_eq!(eph.sqrt_a, 2_702_000_000.0 * P2_19);
+        assert_eq!(eph.idot, -P2_43 * SC2RAD);
+
+        let mut corrupted = sf2.clone();
+        corrupted[4] ^= 1 << 20;
+        assert_eq!(
+            decode(&mut decoder, GnssId::GPS, 5, &corrupted, &mut messages),
+            Err(NavDecodeError::Parity)
+        );
+    }
+}
@@ -0,0 +1,187 @@
+//! Encoding of decoded navigation data as MGA assistance messages
+
+use super::*;
+use crate::ubx_packets::{
+    MgaBdsEphBuilder, MgaGalEphBuilder, MgaGloEphBuilder, MgaGpsEphBuilder, MgaGpsIonoBuilder,
+};
+
+/// MGA-GPS-EPH of a GPS ephemeris, `gnss` is not checked
+impl From<&Ephemeris> for MgaGpsEphBuilder {
+    fn from(eph: &Ephemeris) -> Self {
+        Self {
+            msg_type: 0x01,
+            sv_id: eph.sv_id,
+            fit_interval: eph.fit_interval.into(),
+            ura_index: eph.accuracy,
+            sv_health: eph.health as u8,
+            tgd: eph.tgd[0],
+            iodc: eph.iodc,
+            toc: eph.toc,
+            af2: eph.af2,
+            afl: eph.af1,
+            af0: eph.af0,
+            crs: eph.crs,
+            delta_n: eph.delta_n / SC2RAD,
+            m0: eph.m0 / SC2RAD,
+            cuc: eph.cuc,
+            cus: eph.cus,
+            e: eph.e,
+            sqrt_a: eph.sqrt_a,
+            toe: eph.toe,
+            cic: eph.cic,
+            omega0: eph.omega0 / SC2RAD,
+            cis: eph.cis,
+            crc: eph.crc,
+            i0: eph.i0 / SC2RAD,
+            omega: eph.omega / SC2RAD,
+            omega_dot: eph.omega_dot / SC2RAD,
+            idot: eph.idot / SC2RAD,
+            ..Default::default()
+        }
+    }
+}
+
+/// MGA-GAL-EPH of a Galileo ephemeris, `gnss` is not checked
+impl From<&Ephemeris> for MgaGalEphBuilder {
+    fn from(eph: &Ephemeris) -> Self {
+        Self {
+            msg_type: 0x01,
+            sv_id: eph.sv_id,
+            iod_nav: eph.iode,
+            delta_n: eph.delta_n / SC2RAD,
+            m0: eph.m0 / SC2RAD,
+            e: eph.e,
+            sqrt_a: eph.sqrt_a,
+            omega0: eph.omega0 / SC2RAD,
+            i0: eph.i0 / SC2RAD,
+            omega: eph.omega / SC2RAD,
+            omega_dot: eph.omega_dot / SC2RAD,
+            idot: eph.idot / SC2RAD,
+            cuc: eph.cuc,
+            cus: eph.cus,
+            crc: eph.crc,
+            crs: eph.crs,
+            cic: eph.cic,
+            cis: eph.cis,
+            toe: eph.toe,
+            af0: eph.af0,
+            af1: eph.af1,
+            af2: eph.af2,
+            sisa_index_e1_e5b: eph.accuracy,
+            toc: eph.toc,
+            bgd_e1_e5b: eph.tgd[1],
+            // See `Ephemeris::health` for the bit layout
+            health_e1b: ((eph.health >> 1) & 0x3) as u8,
+            data_validity_e1b: (eph.health & 0x1) as u8,
+            health_e5b: ((eph.health >> 7) & 0x3) as u8,
+            data_validity_e5b: ((eph.health >> 6) & 0x1) as u8,
+            ..Default::default()
+        }
+    }
+}
+
+/// MGA-BDS-EPH of a BeiDou ephemeris, `gnss` is not checked
+impl From<&Ephemeris> for MgaBdsEphBuilder {
+    fn from(eph: &Ephemeris) -> Self {
+        Self {
+            msg_type: 0x01,
+            sv_id: eph.sv_id,
+            sat_h1: eph.health as u8,
+            iodc: eph.iodc as u8,
+            a2: eph.af2,
+            a1: eph.af1,
+            a0: eph.af0,
+            toc: eph.toc,
+            tgd1: eph.tgd[0],
+            urai: eph.accuracy,
+            iode: eph.iode as u8,
+            toe: eph.toe,
+            sqrt_a: eph.sqrt_a,
+            e: eph.e,
+            omega: eph.omega / SC2RAD,
+            delta_n: eph.delta_n / SC2RAD,
+            idot: eph.idot / SC2RAD,
+            m0: eph.m0 / SC2RAD,
+            omega0: eph.omega0 / SC2RAD,
+            omega_dot: eph.omega_dot / SC2RAD,
+            i0: eph.i0 / SC2RAD,
+            cuc: eph.cuc,
+            cus: eph.cus,
+            crc: eph.crc,
+            crs: eph.crs,
+            cic: eph.cic,
+            cis: eph.cis,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&GlonassEphemeris> for MgaGloEphBuilder {
+    fn from(eph: &GlonassEphemeris) -> Self {
+        let km = |value: f64, scale: f64| libm::round(value * 1e-3 / scale) as i32;
+        Self {
+            msg_type: 0x01,
+            sv_id: eph.sv_id,
+            ft: eph.accuracy,
+            b: eph.health << 2,
+            m: eph.m,
+            h: eph.frequency,
+            x: km(eph.position[0], P2_11),
+            y: km(eph.position[1], P2_11),
+            z: km(eph.position[2], P2_11),
+            dx: km(eph.velocity[0], P2_20),
+            dy: km(eph.velocity[1], P2_20),
+            dz: km(eph.velocity[2], P2_20),
+            ddx: km(eph.acceleration[0], P2_30) as i8,
+            ddy: km(eph.acceleration[1], P2_30) as i8,
+            ddz: km(eph.acceleration[2], P2_30) as i8,
+            tb: (eph.tb / 900) as u8,
+            gamma: libm::round(eph.gamma_n / P2_40) as i16 as u16,
+            e: eph.age,
+            delta_tau: libm::round(eph.delta_tau_n / P2_30) as i8 as u8,
+            tau: libm::round(eph.tau_n / P2_30) as i32,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&KlobucharModel> for MgaGpsIonoBuilder {
+    fn from(model: &KlobucharModel) -> Self {
+        Self {
+            msg_type: 0x06,
+            alpha0: model.alpha[0],
+            alpha1: model.alpha[1],
+            alpha2: model.alpha[2],
+            alpha3: model.alpha[3],
+            beta0: model.beta[0],
+            beta1: model.beta[1],
+            beta2: model.beta[2],
+            beta3: model.beta[3],
+            ..Default::default()
+        }
+    }
+}
+
+impl NavMessage {
+    /// Encodes the message as a MGA assistance frame.
+    ///
+    /// Supported are GPS, Galileo, BeiDou and GLONASS ephemerides and the
+    /// GPS ionosphere model; `None` is returned for all other messages.
+    pub fn to_mga(&self) -> Option<Vec<u8>> {
+        match self {
+            NavMessage::Ephemeris(eph) => match eph.gnss {
+                GnssId::GPS => Some(MgaGpsEphBuilder::from(eph).into_packet_bytes().to_vec()),
+                GnssId::GALILEO => Some(MgaGalEphBuilder::from(eph).into_packet_bytes().to_vec()),
+                GnssId::BEIDOU => Some(MgaBdsEphBuilder::from(eph).into_packet_bytes().to_vec()),
+                _ => None,
+            },
+            NavMessage::GlonassEphemeris(eph) => {
+                Some(MgaGloEphBuilder::from(eph).into_packet_bytes().to_vec())
+            },
+            NavMessage::Klobuchar(GnssId::GPS, model) => {
+                Some(MgaGpsIonoBuilder::from(model).into_packet_bytes().to_vec())
+            },
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,372 @@
+//! Satellite position, velocity and clock from broadcast ephemerides
+//! (IS-GPS-200 20.3.3.4.3, Galileo OS SIS ICD 5.1.1, BDS-SIS-ICD-B1I 5.2.4,
+//! GLONASS ICD A.3.1.2)
+
+use super::*;
+use crate::ubx_packets::{GnssTime, LeapSeconds, PositionECEF, TimeScale};
+
+/// Speed of light in m/s
+const SPEED_OF_LIGHT: f64 = 299_792_458.0;
+
+const GPS_MU: f64 = 3.986_005e14;
+const GPS_OMEGA_E: f64 = 7.292_115_146_7e-5;
+const GAL_MU: f64 = 3.986_004_418e14;
+const BDS_MU: f64 = 3.986_004_418e14;
+const BDS_OMEGA_E: f64 = 7.292_115e-5;
+
+const GLO_MU: f64 = 3.986_004_4e14;
+const GLO_OMEGA_E: f64 = 7.292_115e-5;
+const GLO_J2: f64 = 1.082_625_7e-3;
+const GLO_RADIUS: f64 = 6_378_136.0;
+/// Integration step of the GLONASS equations of motion in seconds
+const GLO_STEP: f64 = 60.0;
+
+const HALF_WEEK: f64 = 302_400.0;
+const HALF_DAY: f64 = 43_200.0;
+
+/// Position, velocity and clock of a satellite at a given time
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy)]
+pub struct SatelliteState {
+    /// Position in the ECEF frame (WGS84, PZ-90 for GLONASS)
+    pub position: PositionECEF,
+    /// Velocity in the ECEF frame in m/s
+    pub velocity: [f64; 3],
+    /// Clock offset in seconds, without group delays
+    pub clock_bias: f64,
+    /// Clock drift in s/s
+    pub clock_drift: f64,
+}
+
+/// Time difference `t - reference` wrapped into `-half_period..half_period`
+fn wrap(t: f64, reference: f64, half_period: f64) -> f64 {
+    let dt = t - reference;
+    if dt > half_period {
+        dt - 2.0 * half_period
+    } else if dt < -half_period {
+        dt + 2.0 * half_period
+    } else {
+        dt
+    }
+}
+
+impl Ephemeris {
+    fn scale(&self) -> TimeScale {
+        match self.gnss {
+            GnssId::GALILEO => TimeScale::Galileo,
+            GnssId::BEIDOU => TimeScale::BeiDou,
+            _ => TimeScale::Gps,
+        }
+    }
+
+    /// Gravitational constant and earth rotation rate of the system
+    fn constants(&self) -> (f64, f64) {
+        match self.gnss {
+            GnssId::GALILEO => (GAL_MU, GPS_OMEGA_E),
+            GnssId::BEIDOU => (BDS_MU, BDS_OMEGA_E),
+            _ => (GPS_MU, GPS_OMEGA_E),
+        }
+    }
+
+    /// BeiDou GEO satellites use a rotated orbital frame
+    fn is_beidou_geo(&self) -> bool {
+        self.gnss == GnssId::BEIDOU && (self.sv_id <= 5 || self.sv_id >= 59)
+    }
+
+    /// Satellite state at `time`, which is converted to the system time of the
+    /// ephemeris. The leap seconds are only used for UTC and GLONASS times.
+    ///
+    /// Only the time of week is used, so `time` must be within half a week of
+    /// the ephemeris reference time.
+    pub fn state(&self, time: &GnssTime, leap_seconds: &LeapSeconds) -> SatelliteState {
+        let t = time.to_scale(self.scale(), leap_seconds).tow();
+        let (position, eccentric_anomaly) = self.position(t);
+        // Velocity by central difference
+        let (before, _) = self.position(t - 0.5);
+        let (after, _) = self.position(t + 0.5);
+
+        let (mu, _) = self.constants();
+        let dt = wrap(t, self.toc, HALF_WEEK);
+        let relativistic = -2.0 * libm::sqrt(mu) / (SPEED_OF_LIGHT * SPEED_OF_LIGHT)
+            * self.e
+            * self.sqrt_a
+            * libm::sin(eccentric_anomaly);
+        SatelliteState {
+            position,
+            velocity: [after.x - before.x, after.y - before.y, after.z - before.z],
+            clock_bias: self.af0 + self.af1 * dt + self.af2 * dt * dt + relativistic,
+            clock_drift: self.af1 + 2.0 * self.af2 * dt,
+        }
+    }
+
+    /// Position at `t` seconds of week, along with the eccentric anomaly
+    fn position(&self, t: f64) -> (PositionECEF, f64) {
+        let (mu, omega_e) = self.constants();
+        let a = self.sqrt_a * self.sqrt_a;
+        let tk = wrap(t, self.toe, HALF_WEEK);
+        let n = libm::sqrt(mu / (a * a * a)) + self.delta_n;
+        let m = self.m0 + n * tk;
+
+        let mut e_anomaly = m;
+        for _ in 0..30 {
+            let next = m + self.e * libm::sin(e_anomaly);
+            let done = libm::fabs(next - e_anomaly) < 1e-13;
+            e_anomaly = next;
+            if done {
+                break;
+            }
+        }
+        let (sin_e, cos_e) = libm::sincos(e_anomaly);
+        let nu = libm::atan2(libm::sqrt(1.0 - self.e * self.e) * sin_e, cos_e - self.e);
+        let phi = nu + self.omega;
+        let (sin_2phi, cos_2phi) = libm::sincos(2.0 * phi);
+
+        let u = phi + self.cus * sin_2phi + self.cuc * cos_2phi;
+        let r = a * (1.0 - self.e * cos_e) + self.crs * sin_2phi + self.crc * cos_2phi;
+        let i = self.i0 + self.cis * sin_2phi + self.cic * cos_2phi + self.idot * tk;
+        let (sin_u, cos_u) = libm::sincos(u);
+        let (x, y) = (r * cos_u, r * sin_u);
+        let (sin_i, cos_i) = libm::sincos(i);
+
+        let position = if self.is_beidou_geo() {
+            // Inertial orbit, rotated by -5° about x and by the earth rotation about z
+            let omega = self.omega0 + self.omega_dot * tk - omega_e * self.toe;
+            let (sin_o, cos_o) = libm::sincos(omega);
+            let xg = x * cos_o - y * cos_i * sin_o;
+            let yg = x * sin_o + y * cos_i * cos_o;
+            let zg = y * sin_i;
+            let (sin_5, cos_5) = libm::sincos((-5.0f64).to_radians());
+            let (sin_r, cos_r) = libm::sincos(omega_e * tk);
+            PositionECEF {
+                x: xg * cos_r + yg * sin_r * cos_5 + zg * sin_r * sin_5,
+                y: -xg * sin_r + yg * cos_r * cos_5 + zg * cos_r * sin_5,
+                z: -yg * sin_5 + zg * cos_5,
+            }
+        } else {
+            let omega = self.omega0 + (self.omega_dot - omega_e) * tk - omega_e * self.toe;
+            let (sin_o, cos_o) = libm::sincos(omega);
+            PositionECEF {
+                x: x * cos_o - y * cos_i * sin_o,
+                y: x * sin_o + y * cos_i * cos_o,
+                z: y * sin_i,
+            }
+        };
+        (position, e_anomaly)
+    }
+}
+
+impl GlonassEphemeris {
+    /// Satellite state at `time`, by numerical integration from the reference
+    /// time `tb`. The leap seconds relate `time` to GLONASS time.
+    ///
+    /// Only the time of day is used, so `time` must be within half a day of `tb`,
+    /// the ephemeris is valid for about 15 minutes around it.
+    pub fn state(&self, time: &GnssTime, leap_seconds: &LeapSeconds) -> SatelliteState {
+        let tod = time.to_scale(TimeScale::Glonass, leap_seconds).tow() % (2.0 * HALF_DAY);
+        let dt = wrap(tod, f64::from(self.tb), HALF_DAY);
+
+        let mut state = [
+            self.position[0],
+            self.position[1],
+            self.position[2],
+            self.velocity[0],
+            self.velocity[1],
+            self.velocity[2],
+        ];
+        let mut remaining = dt;
+        while libm::fabs(remaining) > 1e-9 {
+            let step = if remaining < 0.0 {
+                remaining.max(-GLO_STEP)
+            } else {
+                remaining.min(GLO_STEP)
+            };
+            state = self.runge_kutta(&state, step);
+            remaining -= step;
+        }
+
+        SatelliteState {
+            position: PositionECEF {
+                x: state[0],
+                y: state[1],
+                z: state[2],
+            },
+            velocity: [state[3], state[4], state[5]],
+            clock_bias: -self.tau_n + self.gamma_n * dt,
+            clock_drift: self.gamma_n,
+        }
+    }
+
+    fn runge_kutta(&self, state: &[f64; 6], step: f64) -> [f64; 6] {
+        let add = |a: &[f64; 6], b: &[f64; 6], factor: f64| {
+            let mut sum = *a;
+            for (s, b) in sum.iter_mut().zip(b) {
+                *s += b * factor;
+            }
+            sum
+        };
+        let k1 = self.derivative(state);
+        let k2 = self.derivative(&add(state, &k1, step / 2.0));
+        let k3 = self.derivative(&add(state, &k2, step / 2.0));
+        let k4 = self.derivative(&add(state, &k3, step));
+        let mut next = *state;
+        for i in 0..6 {
+            next[i] += (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i]) * step / 6.0;
+        }
+        next
+    }
+
+    /// Equations of motion in the rotating PZ-90 frame with the J2 term
+    /// and the broadcast luni-solar acceleration
+    fn derivative(&self, state: &[f64; 6]) -> [f64; 6] {
+        let [x, y, z, vx, vy, vz] = *state;
+        let r2 = x * x + y * y + z * z;
+        if r2 <= 0.0 {
+            return [0.0; 6];
+        }
+        let r3 = r2 * libm::sqrt(r2);
+        let omega2 = GLO_OMEGA_E * GLO_OMEGA_E;
+        let a = 1.5 * GLO_J2 * GLO_MU * GLO_RADIUS * GLO_RADIUS / r2 / r3;
+        let b = 5.0 * z * z / r2;
+        let c = -GLO_MU / r3 - a * (1.0 - b);
+        [
+            vx,
+            vy,
+            vz,
+            (c + omega2) * x + 2.0 * GLO_OMEGA_E * vy + self.acceleration[0],
+            (c + omega2) * y - 2.0 * GLO_OMEGA_E * vx + self.acceleration[1],
+            (c - 2.0 * a) * z + self.acceleration[2],
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64, tolerance: f64) {
+        assert!(libm::fabs(a - b) < tolerance, "{} != {}", a, b);
+    }
+
+    fn norm(x: f64, y: f64, z: f64) -> f64 {
+        libm::sqrt(x * x + y * y + z * z)
+    }
+
+    fn circular(gnss: GnssId, sv_id: u8) -> Ephemeris {
+        Ephemeris {
+            gnss,
+            sv_id,
+            week: 0,
+            toe: 7200.0,
+            toc: 7200.0,
+            iode: 0,
+            iodc: 0,
+            accuracy: 0,
+            health: 0,
+            sqrt_a: 5153.6,
+            e: 0.0,
+            i0: 55f64.to_radians(),
+            idot: 0.0,
+            omega0: 1.0,
+            omega_dot: 0.0,
+            omega: 0.0,
+            m0: 0.5,
+            delta_n: 0.0,
+            cuc: 0.0,
+            cus: 0.0,
+            crc: 0.0,
+            crs: 0.0,
+            cic: 0.0,
+            cis: 0.0,
+            af0: 1e-4,
+            af1: 1e-11,
+            af2: 0.0,
+            tgd: [0.0; 2],
+            l2_codes: 0,
+            l2p_flag: false,
+            fit_interval: false,
+        }
+    }
+
+    #[test]
+    fn kepler_orbit() {
+        let leap_seconds = LeapSeconds::new(18);
+        let time = GnssTime::from_week_tow_secs(TimeScale::Gps, 2300, 9000.0);
+        for eph in [circular(GnssId::GPS, 1), circular(GnssId::BEIDOU, 2)] {
+            let state = eph.state(&time, &leap_seconds);
+            let p = state.position;
+            let a = eph.sqrt_a * eph.sqrt_a;
+            assert_close(norm(p.x, p.y, p.z), a, 1e-3);
+            // Inclined by 55° (60° for the GEO frame rotated by 5°)
+            let inclination = if eph.is_beidou_geo() { 60.0 } else { 55.0 };
+            assert!(libm::fabs(p.z) <= a * libm::sin(f64::to_radians(inclination)) + 1.0);
+
+            let [vx, vy, vz] = state.velocity;
+            let speed = norm(vx, vy, vz);
+            assert!(speed > 1000.0 && speed < 4000.0, "{}", speed);
+            assert_close(state.clock_drift, 1e-11, 1e-20);
+        }
+
+        // BeiDou time lags GPS time by 14 s
+        let eph = circular(GnssId::GPS, 1);
+        let dt = 1800.0;
+        assert_close(
+            eph.state(&time, &leap_seconds).clock_bias,
+            1e-4 + 1e-11 * dt,
+            1e-15,
+        );
+        let eph = circular(GnssId::BEIDOU, 20);
+        let dt = 1800.0 - 14.0;
+        assert_close(
+            eph.state(&time, &leap_seconds).clock_bias,
+            1e-4 + 1e-11 * dt,
+            1e-15,
+        );
+    }
+
+    #[test]
+    fn glonass_integration() {
+        let r = 25_510_000.0;
+        let inclination = 64.8f64.to_radians();
+        let v = libm::sqrt(GLO_MU / r);
+        let eph = GlonassEphemeris {
+            sv_id: 1,
+            frequency: 1,
+            tk: 0,
+            tb: 3600,
+            nt: 0,
+            position: [r, 0.0, 0.0],
+            // Inertial circular velocity, less the rotation of the frame
+            velocity: [
+                0.0,
+                v * libm::cos(inclination) - GLO_OMEGA_E * r,
+                v * libm::sin(inclination),
+            ],
+            acceleration: [0.0; 3],
+            tau_n: 2e-5,
+            gamma_n: 1e-12,
+            delta_tau_n: 0.0,
+            health: 0,
+            age: 0,
+            accuracy: 0,
+            m: 1,
+        };
+        let leap_seconds = LeapSeconds::new(18);
+        // tb is Moscow time, UTC + 3 h
+        let at_tb = GnssTime::from_week_tow_secs(TimeScale::Utc, 2300, 86_400.0 - 7200.0);
+        let state = eph.state(&at_tb, &leap_seconds);
+        assert_eq!(state.position.x, r);
+        assert_eq!(state.clock_bias, -2e-5);
+
+        for minutes in [-15, 15, 30] {
+            let time = GnssTime::new(at_tb.scale(), at_tb.nanos() + minutes * 60_000_000_000);
+            let state = eph.state(&time, &leap_seconds);
+            let p = state.position;
+            assert_close(norm(p.x, p.y, p.z), r, 0.002 * r);
+            assert_close(
+                state.clock_bias,
+                -2e-5 + 1e-12 * (minutes * 60) as f64,
+                1e-15,
+            );
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! NMEA 0183 sentences.
+//!
+//! [NmeaGenerator] derives GGA, RMC, VTG, GSA, GSV, GST and ZDA sentences in
+//! NMEA 4.11 format from UBX navigation messages, so NMEA output can be disabled
+//! on the receiver and produced on the host instead.
+//!
+//! [NmeaSentence::parse] reads the standard sentences and the u-blox PUBX,00,
+//! PUBX,03 and PUBX,04 messages. [PubxRateBuilder] and [PubxConfigBuilder]
+//! build the PUBX,40 and PUBX,41 configuration messages.
+
+use alloc::{format, string::String};
+
+mod generate;
+mod parse;
+mod pubx;
+
+pub use generate::NmeaGenerator;
+pub use parse::{Gga, Gns, Gsa, Gst, Gsv, GsvSatellite, NmeaSentence, Rmc, Txt, Vtg, Zda};
+pub use pubx::{
+    PubxConfigBuilder, PubxPosition, PubxRateBuilder, PubxSatellite, PubxSatellites, PubxTime,
+};
+
+/// Talker identifier, the first two characters of a sentence address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Talker {
+    /// GPS and SBAS
+    Gps,
+    Glonass,
+    Galileo,
+    BeiDou,
+    Qzss,
+    NavIc,
+    /// Solutions combining several systems
+    Combined,
+}
+
+impl Talker {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Talker::Gps => "GP",
+            Talker::Glonass => "GL",
+            Talker::Galileo => "GA",
+            Talker::BeiDou => "GB",
+            Talker::Qzss => "GQ",
+            Talker::NavIc => "GI",
+            Talker::Combined => "GN",
+        }
+    }
+
+    /// Talker of a two-letter identifier, `BD` being accepted for BeiDou
+    pub fn from_id(id: &str) -> Option<Self> {
+        let talker = match id {
+            "GP" => Talker::Gps,
+            "GL" => Talker::Glonass,
+            "GA" => Talker::Galileo,
+            "GB" | "BD" => Talker::BeiDou,
+            "GQ" => Talker::Qzss,
+            "GI" => Talker::NavIc,
+            "GN" => Talker::Combined,
+            _ => return None,
+        };
+        Some(talker)
+    }
+
+    /// Talker of a u-blox `gnss_id`
+    pub fn from_gnss_id(gnss_id: u8) -> Option<Self> {
+        let talker = match gnss_id {
+            0 | 1 => Talker::Gps,
+            2 => Talker::Galileo,
+            3 => Talker::BeiDou,
+            5 => Talker::Qzss,
+            6 => Talker::Glonass,
+            7 => Talker::NavIc,
+            _ => return None,
+        };
+        Some(talker)
+    }
+
+    /// NMEA 4.11 system identifier of GSA and GSV, `None` for [Talker::Combined]
+    pub fn system_id(self) -> Option<u8> {
+        let id = match self {
+            Talker::Gps => 1,
+            Talker::Glonass => 2,
+            Talker::Galileo => 3,
+            Talker::BeiDou => 4,
+            Talker::Qzss => 5,
+            Talker::NavIc => 6,
+            Talker::Combined => return None,
+        };
+        Some(id)
+    }
+}
+
+/// XOR of all characters between `$` and `*`
+pub fn checksum(body: &str) -> u8 {
+    body.bytes().fold(0, |checksum, b| checksum ^ b)
+}
+
+/// Completes a sentence `body` (address and fields) with `$`, checksum and line end
+pub fn sentence(body: &str) -> String {
+    format!("${body}*{:02X}\r\n", checksum(body))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sentence_checksum() {
+        assert_eq!(
+            sentence("GPGLL,4916.45,N,12311.12,W,225444,A"),
+            "$GPGLL,4916.45,N,12311.12,W,225444,A*31\r\n"
+        );
+    }
+}
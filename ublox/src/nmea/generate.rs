@@ -0,0 +1,713 @@
+//! Generation of NMEA sentences from UBX navigation messages
+
+use alloc::{format, string::String, vec::Vec};
+use core::fmt::Write;
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+use super::{sentence, Talker};
+use crate::{GpsFix, NavPvtFlags, NavTimeUtcFlags, PacketRef};
+
+/// Meters per second in knots
+const KNOTS: f64 = 3600.0 / 1852.0;
+
+/// Satellites per GSA sentence
+const GSA_SATELLITES: usize = 12;
+/// Satellites per GSV sentence
+const GSV_SATELLITES: usize = 4;
+
+/// Navigation solution of NAV-PVT
+#[derive(Debug, Clone)]
+struct Fix {
+    time: Option<NaiveDateTime>,
+    date_valid: bool,
+    fix_type: GpsFix,
+    fix_ok: bool,
+    differential: bool,
+    rtk_float: bool,
+    rtk_fixed: bool,
+    num_satellites: u8,
+    lat: f64,
+    lon: f64,
+    height: f64,
+    height_msl: f64,
+    h_acc: f64,
+    v_acc: f64,
+    speed: f64,
+    course: f64,
+    pdop: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Dop {
+    pdop: f32,
+    hdop: f32,
+    vdop: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Satellite {
+    gnss_id: u8,
+    sv_id: u8,
+    cno: u8,
+    elev: i8,
+    azim: i16,
+    used: bool,
+    /// Pseudorange residual in meters
+    pr_res: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Signal {
+    gnss_id: u8,
+    sv_id: u8,
+    sig_id: u8,
+    cno: u8,
+}
+
+/// Derives NMEA sentences from the latest NAV-PVT, NAV-DOP, NAV-SAT, NAV-SIG
+/// and NAV-TIMEUTC messages passed to [NmeaGenerator::handle].
+///
+/// Without NAV-SIG, GSV reports the main signal of each system with the C/N0
+/// of NAV-SAT. Sentences are `None` or empty until the messages they are
+/// derived from were received.
+#[derive(Debug, Clone, Default)]
+pub struct NmeaGenerator {
+    fix: Option<Fix>,
+    dop: Option<Dop>,
+    satellites: Vec<Satellite>,
+    signals: Option<Vec<Signal>>,
+    utc: Option<NaiveDateTime>,
+}
+
+impl NmeaGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the navigation messages, other packets are ignored
+    pub fn handle(&mut self, packet: &PacketRef<'_>) {
+        match packet {
+            PacketRef::NavPvt(pvt) => {
+                let flags = pvt.flags();
+                let date = NaiveDate::from_ymd_opt(
+                    i32::from(pvt.year()),
+                    u32::from(pvt.month()),
+                    u32::from(pvt.day()),
+                );
+                let time = NaiveTime::from_hms_opt(
+                    u32::from(pvt.hour()),
+                    u32::from(pvt.min()),
+                    u32::from(pvt.sec()),
+                );
+                let time = rounded_datetime(date, time, pvt.nanosec());
+                self.fix = Some(Fix {
+                    // Bit 1 is validTime, bit 0 validDate
+                    time: time.filter(|_| pvt.valid() & 0x02 != 0),
+                    date_valid: pvt.valid() & 0x01 != 0,
+                    fix_type: pvt.fix_type(),
+                    fix_ok: flags.contains(NavPvtFlags::GPS_FIX_OK),
+                    differential: flags.contains(NavPvtFlags::DIFF_SOLN),
+                    rtk_float: flags.contains(NavPvtFlags::CARR_SOLN_FLOAT),
+                    rtk_fixed: flags.contains(NavPvtFlags::CARR_SOLN_FIXED),
+                    num_satellites: pvt.num_satellites(),
+                    lat: pvt.latitude(),
+                    lon: pvt.longitude(),
+                    height: pvt.height_above_ellipsoid(),
+                    height_msl: pvt.height_msl(),
+                    h_acc: pvt.horizontal_accuracy(),
+                    v_acc: pvt.vertical_accuracy(),
+                    speed: pvt.ground_speed_2d(),
+                    course: pvt.heading_motion(),
+                    pdop: pvt.pdop(),
+                });
+            },
+            PacketRef::NavDop(dop) => {
+                self.dop = Some(Dop {
+                    pdop: dop.position_dop(),
+                    hdop: dop.horizontal_dop(),
+                    vdop: dop.vertical_dop(),
+                });
+            },
+            PacketRef::NavSat(sat) => {
+                self.satellites = sat
+                    .svs()
+                    .map(|sv| Satellite {
+                        gnss_id: sv.gnss_id(),
+                        sv_id: sv.sv_id(),
+                        cno: sv.cno(),
+                        elev: sv.elev(),
+                        azim: sv.azim(),
+                        used: sv.flags().sv_used(),
+                        pr_res: f64::from(sv.pr_res()) * 0.1,
+                    })
+                    .collect();
+            },
+            #[cfg(any(feature = "ubx_proto27", feature = "ubx_proto31"))]
+            PacketRef::NavSig(sig) => {
+                self.signals = Some(
+                    sig.sigs()
+                        .map(|sig| Signal {
+                            gnss_id: sig.gnss_id(),
+                            sv_id: sig.sv_id(),
+                            sig_id: sig.sig_id(),
+                            cno: sig.cno(),
+                        })
+                        .collect(),
+                );
+            },
+            PacketRef::NavTimeUTC(time) => {
+                let date = NaiveDate::from_ymd_opt(
+                    i32::from(time.year()),
+                    u32::from(time.month()),
+                    u32::from(time.day()),
+                );
+                let hms = NaiveTime::from_hms_opt(
+                    u32::from(time.hour()),
+                    u32::from(time.min()),
+                    u32::from(time.sec()),
+                );
+                self.utc = rounded_datetime(date, hms, time.nanos())
+                    .filter(|_| time.valid().contains(NavTimeUtcFlags::VALID_UTC));
+            },
+            _ => {},
+        }
+    }
+
+    /// All available sentences of the epoch in the order u-blox receivers send them
+    pub fn sentences(&self) -> Vec<String> {
+        let mut sentences: Vec<String> = [self.rmc(), self.vtg(), self.gga()]
+            .into_iter()
+            .flatten()
+            .collect();
+        sentences.extend(self.gsa());
+        sentences.extend(self.gsv());
+        sentences.extend(self.gst());
+        sentences.extend(self.zda());
+        sentences
+    }
+
+    /// Global positioning system fix data
+    pub fn gga(&self) -> Option<String> {
+        let fix = self.fix.as_ref()?;
+        let mut body = format!("{}GGA,{},", self.talker().as_str(), time(fix.time));
+        if fix.valid() {
+            let hdop = self.dop.map(|dop| format!("{:.2}", dop.hdop));
+            let _ = write!(
+                body,
+                "{},{},{:02},{},{:.1},M,{:.1},M,,",
+                position(fix.lat, fix.lon),
+                fix.quality(),
+                fix.num_satellites,
+                hdop.unwrap_or_default(),
+                fix.height_msl,
+                fix.height - fix.height_msl,
+            );
+        } else {
+            let _ = write!(body, ",,,,0,{:02},,,,,,,", fix.num_satellites);
+        }
+        Some(sentence(&body))
+    }
+
+    /// Recommended minimum data
+    pub fn rmc(&self) -> Option<String> {
+        let fix = self.fix.as_ref()?;
+        let date = fix
+            .time
+            .filter(|_| fix.date_valid)
+            .map(|time| {
+                format!(
+                    "{:02}{:02}{:02}",
+                    time.day(),
+                    time.month(),
+                    time.year() % 100
+                )
+            })
+            .unwrap_or_default();
+        let (status, position, speed, course) = if fix.valid() {
+            (
+                'A',
+                position(fix.lat, fix.lon),
+                format!("{:.3}", fix.speed * KNOTS),
+                format!("{:.2}", fix.course),
+            )
+        } else {
+            ('V', String::from(",,,"), String::new(), String::new())
+        };
+        let body = format!(
+            "{}RMC,{},{status},{position},{speed},{course},{date},,,{},V",
+            self.talker().as_str(),
+            time(fix.time),
+            fix.mode(),
+        );
+        Some(sentence(&body))
+    }
+
+    /// Course over ground and ground speed
+    pub fn vtg(&self) -> Option<String> {
+        let fix = self.fix.as_ref()?;
+        let body = if fix.valid() {
+            format!(
+                "{}VTG,{:.2},T,,M,{:.3},N,{:.3},K,{}",
+                self.talker().as_str(),
+                fix.course,
+                fix.speed * KNOTS,
+                fix.speed * 3.6,
+                fix.mode()
+            )
+        } else {
+            format!("{}VTG,,T,,M,,N,,K,N", self.talker().as_str())
+        };
+        Some(sentence(&body))
+    }
+
+    /// DOP and active satellites, one sentence per system with 12 satellites each
+    pub fn gsa(&self) -> Vec<String> {
+        let Some(fix) = &self.fix else {
+            return Vec::new();
+        };
+        let nav_mode = match fix.fix_type {
+            _ if !fix.valid() => 1,
+            GpsFix::Fix2D => 2,
+            _ => 3,
+        };
+        let dops = match self.dop {
+            Some(dop) => format!("{:.2},{:.2},{:.2}", dop.pdop, dop.hdop, dop.vdop),
+            None => format!("{:.2},,", fix.pdop),
+        };
+
+        let mut systems: Vec<(Talker, Vec<u8>)> = Vec::new();
+        for sat in self.satellites.iter().filter(|sat| sat.used) {
+            let (Some(talker), Some(number)) = (Talker::from_gnss_id(sat.gnss_id), sat.number())
+            else {
+                continue;
+            };
+            match systems.iter_mut().find(|(t, _)| *t == talker) {
+                Some((_, numbers)) => numbers.push(number),
+                None => systems.push((talker, alloc::vec![number])),
+            }
+        }
+        if systems.is_empty() {
+            systems.push((Talker::Gps, Vec::new()));
+        }
+        systems.sort_by_key(|(talker, _)| *talker);
+
+        let talker = self.talker();
+        let mut sentences = Vec::new();
+        for (system, numbers) in &systems {
+            let mut chunks: Vec<&[u8]> = numbers.chunks(GSA_SATELLITES).collect();
+            if chunks.is_empty() {
+                chunks.push(&[]);
+            }
+            for chunk in chunks {
+                let mut body = format!("{}GSA,A,{nav_mode},", talker.as_str());
+                for i in 0..GSA_SATELLITES {
+                    if let Some(number) = chunk.get(i) {
+                        let _ = write!(body, "{number:02}");
+                    }
+                    body.push(',');
+                }
+                let _ = write!(body, "{dops},{}", system.system_id().unwrap_or(1));
+                sentences.push(sentence(&body));
+            }
+        }
+        sentences
+    }
+
+    /// Satellites in view, one group of sentences per system and signal
+    pub fn gsv(&self) -> Vec<String> {
+        // Satellite, C/N0 and NMEA signal id of each reported signal
+        let mut signals: Vec<(Talker, u8, &Satellite, u8)> = Vec::new();
+        match &self.signals {
+            Some(received) => {
+                for signal in received {
+                    let Some(sat) = self
+                        .satellites
+                        .iter()
+                        .find(|sat| sat.gnss_id == signal.gnss_id && sat.sv_id == signal.sv_id)
+                    else {
+                        continue;
+                    };
+                    if let (Some(talker), Some(id)) = (
+                        Talker::from_gnss_id(signal.gnss_id),
+                        signal_id(signal.gnss_id, signal.sig_id),
+                    ) {
+                        signals.push((talker, id, sat, signal.cno));
+                    }
+                }
+            },
+            None => {
+                for sat in &self.satellites {
+                    if let (Some(talker), Some(id)) =
+                        (Talker::from_gnss_id(sat.gnss_id), signal_id(sat.gnss_id, 0))
+                    {
+                        signals.push((talker, id, sat, sat.cno));
+                    }
+                }
+            },
+        }
+        signals.retain(|(_, _, sat, _)| sat.number().is_some());
+        signals.sort_by_key(|(talker, id, sat, _)| (*talker, *id, sat.number()));
+
+        let mut sentences = Vec::new();
+        let mut start = 0;
+        while start < signals.len() {
+            let (talker, id, ..) = signals[start];
+            let group_len = signals[start..]
+                .iter()
+                .take_while(|(t, i, ..)| (*t, *i) == (talker, id))
+                .count();
+            let group = &signals[start..start + group_len];
+            let count = group.len().div_ceil(GSV_SATELLITES);
+            for (i, chunk) in group.chunks(GSV_SATELLITES).enumerate() {
+                let mut body = format!(
+                    "{}GSV,{count},{},{:02}",
+                    talker.as_str(),
+                    i + 1,
+                    group.len()
+                );
+                for (_, _, sat, cno) in chunk {
+                    let _ = write!(body, ",{:02},", sat.number().unwrap_or_default());
+                    if (-90..=90).contains(&sat.elev) && sat.azim >= 0 {
+                        let _ = write!(body, "{:02},{:03}", sat.elev, sat.azim);
+                    } else {
+                        body.push(',');
+                    }
+                    body.push(',');
+                    if *cno > 0 {
+                        let _ = write!(body, "{cno:02}");
+                    }
+                }
+                let _ = write!(body, ",{id:X}");
+                sentences.push(sentence(&body));
+            }
+            start += group_len;
+        }
+        sentences
+    }
+
+    /// Pseudorange error statistics.
+    ///
+    /// The RMS is computed from the residuals of the used satellites. NAV-PVT
+    /// only has a horizontal accuracy, which is split evenly on latitude and
+    /// longitude and reported as a circular error ellipse.
+    pub fn gst(&self) -> Option<String> {
+        let fix = self.fix.as_ref()?;
+        let residuals: Vec<f64> = self
+            .satellites
+            .iter()
+            .filter(|sat| sat.used)
+            .map(|sat| sat.pr_res)
+            .collect();
+        let rms = if residuals.is_empty() {
+            String::new()
+        } else {
+            let mean_sq = residuals.iter().map(|r| r * r).sum::<f64>() / residuals.len() as f64;
+            format!("{:.1}", libm::sqrt(mean_sq))
+        };
+        let sigma = fix.h_acc / core::f64::consts::SQRT_2;
+        let body = format!(
+            "{}GST,{},{rms},{sigma:.1},{sigma:.1},0.0,{sigma:.1},{sigma:.1},{:.1}",
+            self.talker().as_str(),
+            time(fix.time),
+            fix.v_acc
+        );
+        Some(sentence(&body))
+    }
+
+    /// Time and date, from NAV-TIMEUTC or else NAV-PVT
+    pub fn zda(&self) -> Option<String> {
+        let utc = self.utc.or_else(|| {
+            let fix = self.fix.as_ref()?;
+            fix.time.filter(|_| fix.date_valid)
+        })?;
+        let body = format!(
+            "{}ZDA,{},{:02},{:02},{:04},00,00",
+            self.talker().as_str(),
+            time(Some(utc)),
+            utc.day(),
+            utc.month(),
+            utc.year()
+        );
+        Some(sentence(&body))
+    }
+
+    /// Talker of the solution: the system used if there is only one, GN otherwise
+    fn talker(&self) -> Talker {
+        let mut used = self
+            .satellites
+            .iter()
+            .filter(|sat| sat.used)
+            .filter_map(|sat| Talker::from_gnss_id(sat.gnss_id));
+        match used.next() {
+            Some(first) if used.all(|talker| talker == first) => first,
+            _ => Talker::Combined,
+        }
+    }
+}
+
+impl Fix {
+    fn valid(&self) -> bool {
+        self.fix_ok
+            && matches!(
+                self.fix_type,
+                GpsFix::DeadReckoningOnly
+                    | GpsFix::Fix2D
+                    | GpsFix::Fix3D
+                    | GpsFix::GPSPlusDeadReckoning
+            )
+    }
+
+    /// GGA quality indicator
+    fn quality(&self) -> u8 {
+        match self.fix_type {
+            GpsFix::DeadReckoningOnly => 6,
+            _ if self.rtk_fixed => 4,
+            _ if self.rtk_float => 5,
+            _ if self.differential => 2,
+            _ => 1,
+        }
+    }
+
+    /// Mode indicator of RMC and VTG
+    fn mode(&self) -> char {
+        match self.fix_type {
+            _ if !self.valid() => 'N',
+            GpsFix::DeadReckoningOnly => 'E',
+            _ if self.rtk_fixed => 'R',
+            _ if self.rtk_float => 'F',
+            _ if self.differential => 'D',
+            _ => 'A',
+        }
+    }
+}
+
+impl Satellite {
+    /// NMEA satellite number: GPS 1-32, SBAS 33-64, GLONASS 65-96,
+    /// Galileo 1-36, BeiDou 1-63, QZSS 1-10, NavIC 1-14
+    fn number(&self) -> Option<u8> {
+        match (self.gnss_id, self.sv_id) {
+            (1, prn @ 120..=158) => Some(prn - 87),
+            (6, slot @ 1..=32) => Some(slot + 64),
+            (0 | 2 | 3 | 5 | 7, sv) if sv > 0 => Some(sv),
+            _ => None,
+        }
+    }
+}
+
+/// NMEA 4.11 signal identifier of a u-blox signal
+fn signal_id(gnss_id: u8, sig_id: u8) -> Option<u8> {
+    let id = match (gnss_id, sig_id) {
+        // GPS: L1C/A, L2 CL, L2 CM, L5 I, L5 Q
+        (0, 0) => 1,
+        (0, 3) => 6,
+        (0, 4) => 5,
+        (0, 6) => 7,
+        (0, 7) => 8,
+        // SBAS L1C/A
+        (1, 0) => 1,
+        // Galileo: E1 C and B, E5a, E5b
+        (2, 0 | 1) => 7,
+        (2, 3 | 4) => 1,
+        (2, 5 | 6) => 2,
+        // BeiDou: B1I, B2I, B1C, B2a
+        (3, 0 | 1) => 1,
+        (3, 2 | 3) => 0xb,
+        (3, 5) => 3,
+        (3, 7) => 5,
+        // QZSS: L1C/A, L1S, L2 CM, L2 CL, L5 I, L5 Q
+        (5, 0) => 1,
+        (5, 1) => 4,
+        (5, 4) => 5,
+        (5, 5) => 6,
+        (5, 8) => 7,
+        (5, 9) => 8,
+        // GLONASS: L1 OF, L2 OF
+        (6, 0) => 1,
+        (6, 2) => 3,
+        // NavIC L5 A
+        (7, 0) => 1,
+        _ => return None,
+    };
+    Some(id)
+}
+
+/// UTC date and time with the signed nanoseconds of NAV-PVT or NAV-TIMEUTC,
+/// rounded to the centiseconds of the sentences so that time and date agree
+/// around midnight
+fn rounded_datetime(
+    date: Option<NaiveDate>,
+    time: Option<NaiveTime>,
+    nanos: i32,
+) -> Option<NaiveDateTime> {
+    if nanos.unsigned_abs() >= 1_000_000_000 {
+        return None;
+    }
+    let datetime = NaiveDateTime::new(date?, time?)
+        + chrono::Duration::nanoseconds(i64::from(nanos) + 5_000_000);
+    datetime.with_nanosecond(datetime.nanosecond() / 10_000_000 * 10_000_000)
+}
+
+/// UTC time of day as hhmmss.ss, empty if unknown
+fn time(time: Option<NaiveDateTime>) -> String {
+    let Some(time) = time else {
+        return String::new();
+    };
+    format!(
+        "{:02}{:02}{:02}.{:02}",
+        time.hour(),
+        time.minute(),
+        time.second(),
+        time.nanosecond() / 10_000_000
+    )
+}
+
+/// Latitude and longitude as ddmm.mmmmm,N,dddmm.mmmmm,E
+fn position(lat: f64, lon: f64) -> String {
+    let angle = |value: f64, width: usize| {
+        // Minutes in units of 1e-5, rounded before splitting off the degrees
+        let total = libm::round(libm::fabs(value) * 60.0 * 1e5) as u64;
+        let minutes = total % 6_000_000;
+        format!(
+            "{:0width$}{:02}.{:05}",
+            total / 6_000_000,
+            minutes / 100_000,
+            minutes % 100_000
+        )
+    };
+    format!(
+        "{},{},{},{}",
+        angle(lat, 2),
+        if lat < 0.0 { 'S' } else { 'N' },
+        angle(lon, 3),
+        if lon < 0.0 { 'W' } else { 'E' }
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Parser, UbxUnknownPacketRef};
+
+    fn frame(msg_id: u8, payload: &[u8]) -> Vec<u8> {
+        UbxUnknownPacketRef {
+            payload,
+            class: 0x01,
+            msg_id,
+        }
+        .into_packet_vec()
+    }
+
+    fn nav_pvt() -> Vec<u8> {
+        let mut p = [0u8; 92];
+        p[4..6].copy_from_slice(&2024u16.to_le_bytes());
+        p[6..11].copy_from_slice(&[3, 4, 12, 35, 19]);
+        // Valid date and time
+        p[11] = 0x03;
+        p[20] = 3;
+        p[21] = 0x01;
+        p[23] = 8;
+        p[24..28].copy_from_slice(&115_166_667i32.to_le_bytes());
+        p[28..32].copy_from_slice(&481_173_000i32.to_le_bytes());
+        p[32..36].copy_from_slice(&592_300i32.to_le_bytes());
+        p[36..40].copy_from_slice(&545_400i32.to_le_bytes());
+        p[40..44].copy_from_slice(&1_414u32.to_le_bytes());
+        p[44..48].copy_from_slice(&2_000u32.to_le_bytes());
+        p[60..64].copy_from_slice(&1_000u32.to_le_bytes());
+        p[64..68].copy_from_slice(&8_440_000i32.to_le_bytes());
+        p[76..78].copy_from_slice(&150u16.to_le_bytes());
+        frame(0x07, &p)
+    }
+
+    fn nav_sat() -> Vec<u8> {
+        let mut p = alloc::vec![0, 0, 0, 0, 1, 3, 0, 0];
+        for (gnss_id, sv_id, cno, elev, azim, pr_res, used) in [
+            (0u8, 5u8, 46u8, 40i8, 83i16, 12i16, true),
+            (0, 12, 0, 5, 300, -16, true),
+            (2, 3, 40, 60, 10, 0, false),
+        ] {
+            p.extend([gnss_id, sv_id, cno, elev as u8]);
+            p.extend(azim.to_le_bytes());
+            p.extend(pr_res.to_le_bytes());
+            p.extend((u32::from(used) << 3).to_le_bytes());
+        }
+        frame(0x35, &p)
+    }
+
+    fn nav_dop() -> Vec<u8> {
+        let mut p = alloc::vec![0; 4];
+        for dop in [200u16, 150, 100, 120, 90, 60, 60] {
+            p.extend(dop.to_le_bytes());
+        }
+        frame(0x04, &p)
+    }
+
+    #[test]
+    fn generates_epoch() {
+        let mut generator = NmeaGenerator::new();
+        assert!(generator.sentences().is_empty());
+
+        let mut parser = Parser::default();
+        for bytes in [nav_pvt(), nav_sat(), nav_dop()] {
+            let mut it = parser.consume(&bytes);
+            while let Some(Ok(packet)) = it.next() {
+                generator.handle(&packet);
+            }
+        }
+        assert_eq!(
+            generator.sentences(),
+            [
+                "$GPRMC,123519.00,A,4807.03800,N,01131.00000,E,1.944,84.40,040324,,,A,V*2A\r\n",
+                "$GPVTG,84.40,T,,M,1.944,N,3.600,K,A*08\r\n",
+                "$GPGGA,123519.00,4807.03800,N,01131.00000,E,1,08,0.90,545.4,M,46.9,M,,*59\r\n",
+                "$GPGSA,A,3,05,12,,,,,,,,,,,1.50,0.90,1.20,1*17\r\n",
+                "$GPGSV,1,1,02,05,40,083,46,12,05,300,,1*6B\r\n",
+                "$GAGSV,1,1,01,03,60,010,40,7*42\r\n",
+                "$GPGST,123519.00,1.4,1.0,1.0,0.0,1.0,1.0,2.0*5D\r\n",
+                "$GPZDA,123519.00,04,03,2024,00,00*68\r\n",
+            ]
+        );
+    }
+
+    #[test]
+    fn formats_angles_and_time() {
+        assert_eq!(
+            position(48.117_3, -11.516_666_666),
+            "4807.03800,N,01131.00000,W"
+        );
+        // Rounding carries into the degrees
+        assert_eq!(
+            position(-0.999_999_999, 179.0),
+            "0100.00000,S,17900.00000,E"
+        );
+        let t = rounded_datetime(
+            NaiveDate::from_ymd_opt(2024, 2, 29),
+            NaiveTime::from_hms_opt(23, 59, 59),
+            996_000_000,
+        );
+        assert_eq!(t.map(|t| t.date()), NaiveDate::from_ymd_opt(2024, 3, 1));
+        assert_eq!(time(t), "000000.00");
+        assert_eq!(time(None), "");
+    }
+
+    #[test]
+    fn date_follows_rounded_time_at_midnight() {
+        // 2024-03-04 00:00:00 with nano = -2000, i.e. 2024-03-03 23:59:59.999998
+        let mut bytes = nav_pvt();
+        let payload = &mut bytes[6..98];
+        payload[8..11].copy_from_slice(&[0, 0, 0]);
+        payload[16..20].copy_from_slice(&(-2000i32).to_le_bytes());
+        let bytes = frame(0x07, payload);
+
+        let mut generator = NmeaGenerator::new();
+        let mut parser = Parser::default();
+        let mut it = parser.consume(&bytes);
+        generator.handle(&it.next().unwrap().unwrap());
+
+        let rmc = generator.rmc().unwrap();
+        assert!(rmc.starts_with("$GNRMC,000000.00,"));
+        assert!(rmc.contains(",040324,"));
+        let zda = generator.zda().unwrap();
+        assert!(zda.starts_with("$GNZDA,000000.00,04,03,2024,"));
+    }
+}
@@ -0,0 +1,803 @@
+//! Parsing of standard NMEA sentences
+
+use alloc::{string::String, vec::Vec};
+use core::{convert::TryFrom, str::FromStr};
+
+use chrono::{NaiveDate, NaiveTime};
+
+use super::{
+    checksum,
+    pubx::{PubxPosition, PubxSatellites, PubxTime},
+    Talker,
+};
+use crate::{error::NmeaError, Position, Velocity};
+
+/// A parsed sentence
+#[derive(Debug, Clone, PartialEq)]
+pub enum NmeaSentence {
+    Gga(Gga),
+    Rmc(Rmc),
+    Gsa(Gsa),
+    Gsv(Gsv),
+    Gst(Gst),
+    Vtg(Vtg),
+    Zda(Zda),
+    Gns(Gns),
+    Txt(Txt),
+    /// PUBX,00
+    PubxPosition(PubxPosition),
+    /// PUBX,03
+    PubxSatellites(PubxSatellites),
+    /// PUBX,04
+    PubxTime(PubxTime),
+    /// A valid sentence of another type, by address (e.g. `GPGLL` or `PUBX,05`)
+    Other(String),
+}
+
+impl NmeaSentence {
+    /// Parses a sentence, with or without the trailing line break
+    pub fn parse(line: &str) -> Result<Self, NmeaError> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        let body = line.strip_prefix('$').ok_or(NmeaError::InvalidFraming)?;
+        let (body, sum) = body.rsplit_once('*').ok_or(NmeaError::InvalidFraming)?;
+        let got = u8::from_str_radix(sum, 16).map_err(|_| NmeaError::InvalidFraming)?;
+        if sum.len() != 2 {
+            return Err(NmeaError::InvalidFraming);
+        }
+        let expect = checksum(body);
+        if expect != got {
+            return Err(NmeaError::InvalidChecksum { expect, got });
+        }
+        if !body.is_ascii() {
+            return Err(NmeaError::InvalidFraming);
+        }
+
+        let (address, fields) = body.split_once(',').unwrap_or((body, ""));
+        if address == "PUBX" {
+            let (msg_id, fields) = fields.split_once(',').unwrap_or((fields, ""));
+            return match msg_id {
+                "00" => PubxPosition::parse(fields).map(Self::PubxPosition),
+                "03" => PubxSatellites::parse(fields).map(Self::PubxSatellites),
+                "04" => PubxTime::parse(fields).map(Self::PubxTime),
+                _ => Ok(Self::Other(alloc::format!("PUBX,{msg_id}"))),
+            };
+        }
+        if address.len() != 5 {
+            return Ok(Self::Other(address.into()));
+        }
+        let talker = Talker::from_id(&address[..2]);
+        let sentence = match &address[2..] {
+            "GGA" => Self::Gga(Gga::parse(talker, fields)?),
+            "RMC" => Self::Rmc(Rmc::parse(talker, fields)?),
+            "GSA" => Self::Gsa(Gsa::parse(talker, fields)?),
+            "GSV" => Self::Gsv(Gsv::parse(talker, fields)?),
+            "GST" => Self::Gst(Gst::parse(talker, fields)?),
+            "VTG" => Self::Vtg(Vtg::parse(talker, fields)?),
+            "ZDA" => Self::Zda(Zda::parse(talker, fields)?),
+            "GNS" => Self::Gns(Gns::parse(talker, fields)?),
+            "TXT" => Self::Txt(Txt::parse(talker, fields)?),
+            _ => Self::Other(address.into()),
+        };
+        Ok(sentence)
+    }
+}
+
+/// Global positioning system fix data
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gga {
+    pub talker: Option<Talker>,
+    /// UTC time of the position
+    pub time: Option<NaiveTime>,
+    /// Latitude in degrees, north positive
+    pub lat: Option<f64>,
+    /// Longitude in degrees, east positive
+    pub lon: Option<f64>,
+    /// 0 no fix, 1 autonomous, 2 differential, 4 RTK fixed, 5 RTK float, 6 dead reckoning
+    pub quality: u8,
+    pub num_satellites: u8,
+    pub hdop: Option<f32>,
+    /// Altitude above mean sea level in meters
+    pub altitude: Option<f64>,
+    /// Geoid height above the ellipsoid in meters
+    pub geoid_separation: Option<f64>,
+    /// Age of the differential corrections in seconds
+    pub diff_age: Option<f32>,
+    pub diff_station: Option<u16>,
+}
+
+impl Gga {
+    fn parse(talker: Option<Talker>, fields: &str) -> Result<Self, NmeaError> {
+        let mut f = Fields::new("GGA", fields);
+        Ok(Self {
+            talker,
+            time: f.time("time")?,
+            lat: f.angle("lat")?,
+            lon: f.angle("lon")?,
+            quality: f.opt("quality")?.unwrap_or(0),
+            num_satellites: f.opt("numSV")?.unwrap_or(0),
+            hdop: f.opt("HDOP")?,
+            altitude: f.opt_with_unit("alt")?,
+            geoid_separation: f.opt_with_unit("sep")?,
+            diff_age: f.opt("diffAge")?,
+            diff_station: f.opt("diffStation")?,
+        })
+    }
+}
+
+/// Recommended minimum data
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rmc {
+    pub talker: Option<Talker>,
+    pub time: Option<NaiveTime>,
+    /// Status `A`, data valid
+    pub valid: bool,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    /// Speed over ground in knots
+    pub speed: Option<f64>,
+    /// Course over ground in degrees
+    pub course: Option<f64>,
+    pub date: Option<NaiveDate>,
+    /// Magnetic variation in degrees, east positive
+    pub magnetic_variation: Option<f64>,
+    /// N no fix, A autonomous, D differential, F RTK float, R RTK fixed, E dead reckoning
+    pub mode: Option<char>,
+    /// Navigational status, NMEA 4.1 and later
+    pub nav_status: Option<char>,
+}
+
+impl Rmc {
+    fn parse(talker: Option<Talker>, fields: &str) -> Result<Self, NmeaError> {
+        let mut f = Fields::new("RMC", fields);
+        Ok(Self {
+            talker,
+            time: f.time("time")?,
+            valid: f.opt::<char>("status")? == Some('A'),
+            lat: f.angle("lat")?,
+            lon: f.angle("lon")?,
+            speed: f.opt("spd")?,
+            course: f.opt("cog")?,
+            date: f.date("date")?,
+            magnetic_variation: f.signed("mv", 'W')?,
+            mode: f.opt("posMode")?,
+            nav_status: f.opt("navStatus")?,
+        })
+    }
+}
+
+/// DOP and active satellites
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gsa {
+    pub talker: Option<Talker>,
+    /// Automatic 2D/3D mode selection
+    pub automatic: bool,
+    /// 1 no fix, 2 2D fix, 3 3D fix
+    pub nav_mode: u8,
+    /// Satellite numbers used in the solution
+    pub satellites: Vec<u8>,
+    pub pdop: Option<f32>,
+    pub hdop: Option<f32>,
+    pub vdop: Option<f32>,
+    /// NMEA 4.10 and later
+    pub system_id: Option<u8>,
+}
+
+impl Gsa {
+    fn parse(talker: Option<Talker>, fields: &str) -> Result<Self, NmeaError> {
+        let mut f = Fields::new("GSA", fields);
+        let automatic = f.opt::<char>("opMode")? == Some('A');
+        let nav_mode = f.opt("navMode")?.unwrap_or(1);
+        let mut satellites = Vec::new();
+        for _ in 0..12 {
+            satellites.extend(f.opt::<u8>("svid")?);
+        }
+        Ok(Self {
+            talker,
+            automatic,
+            nav_mode,
+            satellites,
+            pdop: f.opt("PDOP")?,
+            hdop: f.opt("HDOP")?,
+            vdop: f.opt("VDOP")?,
+            system_id: f.opt_hex("systemId")?,
+        })
+    }
+}
+
+/// Satellite of a GSV sentence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GsvSatellite {
+    pub number: u8,
+    /// Elevation in degrees
+    pub elevation: Option<i8>,
+    /// Azimuth in degrees
+    pub azimuth: Option<u16>,
+    /// Carrier to noise ratio in dBHz, `None` if not tracked
+    pub cno: Option<u8>,
+}
+
+/// Satellites in view, one of a group of sentences
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gsv {
+    pub talker: Option<Talker>,
+    /// Number of sentences of the group
+    pub count: u8,
+    /// Number of this sentence, starting at 1
+    pub number: u8,
+    /// Satellites in view of the whole group
+    pub in_view: u8,
+    pub satellites: Vec<GsvSatellite>,
+    /// NMEA 4.10 and later
+    pub signal_id: Option<u8>,
+}
+
+impl Gsv {
+    fn parse(talker: Option<Talker>, fields: &str) -> Result<Self, NmeaError> {
+        let mut f = Fields::new("GSV", fields);
+        let count = f.req("numMsg")?;
+        let number = f.req("msgNum")?;
+        let in_view = f.opt("numSV")?.unwrap_or(0);
+        let mut satellites = Vec::new();
+        while f.remaining() >= 4 {
+            let Some(number) = f.opt("svid")? else {
+                f.skip(3);
+                continue;
+            };
+            satellites.push(GsvSatellite {
+                number,
+                elevation: f.opt("elv")?,
+                azimuth: f.opt("az")?,
+                cno: f.opt("cno")?,
+            });
+        }
+        Ok(Self {
+            talker,
+            count,
+            number,
+            in_view,
+            satellites,
+            signal_id: f.opt_hex("signalId")?,
+        })
+    }
+}
+
+/// Pseudorange error statistics, all values in meters
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gst {
+    pub talker: Option<Talker>,
+    pub time: Option<NaiveTime>,
+    /// RMS of the pseudorange residuals
+    pub rms: Option<f64>,
+    pub semi_major: Option<f64>,
+    pub semi_minor: Option<f64>,
+    /// Orientation of the semi-major axis in degrees from true north
+    pub orientation: Option<f64>,
+    pub lat_sigma: Option<f64>,
+    pub lon_sigma: Option<f64>,
+    pub alt_sigma: Option<f64>,
+}
+
+impl Gst {
+    fn parse(talker: Option<Talker>, fields: &str) -> Result<Self, NmeaError> {
+        let mut f = Fields::new("GST", fields);
+        Ok(Self {
+            talker,
+            time: f.time("time")?,
+            rms: f.opt("rangeRms")?,
+            semi_major: f.opt("stdMajor")?,
+            semi_minor: f.opt("stdMinor")?,
+            orientation: f.opt("orient")?,
+            lat_sigma: f.opt("stdLat")?,
+            lon_sigma: f.opt("stdLong")?,
+            alt_sigma: f.opt("stdAlt")?,
+        })
+    }
+}
+
+/// Course over ground and ground speed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vtg {
+    pub talker: Option<Talker>,
+    /// Course over ground in degrees from true north
+    pub course_true: Option<f64>,
+    /// Course over ground in degrees from magnetic north
+    pub course_magnetic: Option<f64>,
+    pub speed_knots: Option<f64>,
+    pub speed_kmh: Option<f64>,
+    pub mode: Option<char>,
+}
+
+impl Vtg {
+    fn parse(talker: Option<Talker>, fields: &str) -> Result<Self, NmeaError> {
+        let mut f = Fields::new("VTG", fields);
+        Ok(Self {
+            talker,
+            course_true: f.opt_with_unit("cogt")?,
+            course_magnetic: f.opt_with_unit("cogm")?,
+            speed_knots: f.opt_with_unit("sogn")?,
+            speed_kmh: f.opt_with_unit("sogk")?,
+            mode: f.opt("posMode")?,
+        })
+    }
+}
+
+/// Time and date
+#[derive(Debug, Clone, PartialEq)]
+pub struct Zda {
+    pub talker: Option<Talker>,
+    pub time: Option<NaiveTime>,
+    pub date: Option<NaiveDate>,
+    /// Local time zone offset in hours
+    pub zone_hours: Option<i8>,
+    pub zone_minutes: Option<u8>,
+}
+
+impl Zda {
+    fn parse(talker: Option<Talker>, fields: &str) -> Result<Self, NmeaError> {
+        let mut f = Fields::new("ZDA", fields);
+        let time = f.time("time")?;
+        let day: Option<u32> = f.opt("day")?;
+        let month: Option<u32> = f.opt("month")?;
+        let year: Option<i32> = f.opt("year")?;
+        let date = match (year, month, day) {
+            (Some(year), Some(month), Some(day)) => {
+                Some(NaiveDate::from_ymd_opt(year, month, day).ok_or(f.invalid("date"))?)
+            },
+            _ => None,
+        };
+        Ok(Self {
+            talker,
+            time,
+            date,
+            zone_hours: f.opt("ltzh")?,
+            zone_minutes: f.opt("ltzn")?,
+        })
+    }
+}
+
+/// GNSS fix data
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gns {
+    pub talker: Option<Talker>,
+    pub time: Option<NaiveTime>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    /// Mode indicator per system: GPS, GLONASS, Galileo, BeiDou (and more)
+    pub modes: String,
+    pub num_satellites: u8,
+    pub hdop: Option<f32>,
+    pub altitude: Option<f64>,
+    pub geoid_separation: Option<f64>,
+    pub diff_age: Option<f32>,
+    pub diff_station: Option<u16>,
+    /// Navigational status, NMEA 4.1 and later
+    pub nav_status: Option<char>,
+}
+
+impl Gns {
+    fn parse(talker: Option<Talker>, fields: &str) -> Result<Self, NmeaError> {
+        let mut f = Fields::new("GNS", fields);
+        Ok(Self {
+            talker,
+            time: f.time("time")?,
+            lat: f.angle("lat")?,
+            lon: f.angle("lon")?,
+            modes: f.next().unwrap_or_default().into(),
+            num_satellites: f.opt("numSV")?.unwrap_or(0),
+            hdop: f.opt("HDOP")?,
+            altitude: f.opt("alt")?,
+            geoid_separation: f.opt("sep")?,
+            diff_age: f.opt("diffAge")?,
+            diff_station: f.opt("diffStation")?,
+            nav_status: f.opt("navStatus")?,
+        })
+    }
+}
+
+/// Text transmission
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Txt {
+    pub talker: Option<Talker>,
+    pub count: u8,
+    pub number: u8,
+    /// 0 error, 1 warning, 2 notice, 7 user
+    pub kind: u8,
+    pub text: String,
+}
+
+impl Txt {
+    fn parse(talker: Option<Talker>, fields: &str) -> Result<Self, NmeaError> {
+        let mut f = Fields::new("TXT", fields);
+        let count = f.req("numMsg")?;
+        let number = f.req("msgNum")?;
+        let kind = f.req("msgType")?;
+        // The text may contain commas
+        let text = f.rest().into();
+        Ok(Self {
+            talker,
+            count,
+            number,
+            kind,
+            text,
+        })
+    }
+}
+
+/// Position with the altitude above mean sea level
+impl TryFrom<&Gga> for Position {
+    type Error = NmeaError;
+    fn try_from(gga: &Gga) -> Result<Self, Self::Error> {
+        match (gga.quality, gga.lat, gga.lon) {
+            (1.., Some(lat), Some(lon)) => Ok(Position {
+                lat,
+                lon,
+                alt: gga.altitude.unwrap_or_default(),
+            }),
+            _ => Err(NmeaError::NoFix),
+        }
+    }
+}
+
+/// Position with the altitude above mean sea level
+impl TryFrom<&Gns> for Position {
+    type Error = NmeaError;
+    fn try_from(gns: &Gns) -> Result<Self, Self::Error> {
+        let fix = gns.modes.chars().any(|mode| mode != 'N');
+        match (fix, gns.lat, gns.lon) {
+            (true, Some(lat), Some(lon)) => Ok(Position {
+                lat,
+                lon,
+                alt: gns.altitude.unwrap_or_default(),
+            }),
+            _ => Err(NmeaError::NoFix),
+        }
+    }
+}
+
+impl TryFrom<&Rmc> for Velocity {
+    type Error = NmeaError;
+    fn try_from(rmc: &Rmc) -> Result<Self, Self::Error> {
+        match (rmc.valid, rmc.speed) {
+            (true, Some(speed)) => Ok(Velocity {
+                speed: speed * 1852.0 / 3600.0,
+                heading: rmc.course.unwrap_or_default(),
+            }),
+            _ => Err(NmeaError::NoFix),
+        }
+    }
+}
+
+/// Comma separated fields of a sentence
+pub(super) struct Fields<'a> {
+    sentence: &'static str,
+    rest: Option<&'a str>,
+}
+
+impl<'a> Fields<'a> {
+    pub(super) fn new(sentence: &'static str, fields: &'a str) -> Self {
+        Self {
+            sentence,
+            rest: Some(fields),
+        }
+    }
+
+    pub(super) fn invalid(&self, field: &'static str) -> NmeaError {
+        NmeaError::InvalidField {
+            sentence: self.sentence,
+            field,
+        }
+    }
+
+    pub(super) fn next(&mut self) -> Option<&'a str> {
+        let rest = self.rest?;
+        match rest.split_once(',') {
+            Some((field, rest)) => {
+                self.rest = Some(rest);
+                Some(field)
+            },
+            None => {
+                self.rest = None;
+                Some(rest)
+            },
+        }
+    }
+
+    pub(super) fn skip(&mut self, n: usize) {
+        for _ in 0..n {
+            self.next();
+        }
+    }
+
+    /// Number of fields left
+    pub(super) fn remaining(&self) -> usize {
+        self.rest.map_or(0, |rest| rest.matches(',').count() + 1)
+    }
+
+    /// All remaining fields as text
+    pub(super) fn rest(&mut self) -> &'a str {
+        self.rest.take().unwrap_or_default()
+    }
+
+    /// A field that may be empty or missing
+    pub(super) fn opt<T: FromStr>(&mut self, field: &'static str) -> Result<Option<T>, NmeaError> {
+        match self.next() {
+            None | Some("") => Ok(None),
+            Some(value) => value.parse().map(Some).map_err(|_| self.invalid(field)),
+        }
+    }
+
+    pub(super) fn req<T: FromStr>(&mut self, field: &'static str) -> Result<T, NmeaError> {
+        self.opt(field)?.ok_or(self.invalid(field))
+    }
+
+    pub(super) fn opt_hex(&mut self, field: &'static str) -> Result<Option<u8>, NmeaError> {
+        match self.next() {
+            None | Some("") => Ok(None),
+            Some(value) => u8::from_str_radix(value, 16)
+                .map(Some)
+                .map_err(|_| self.invalid(field)),
+        }
+    }
+
+    /// A value followed by its unit field
+    pub(super) fn opt_with_unit<T: FromStr>(
+        &mut self,
+        field: &'static str,
+    ) -> Result<Option<T>, NmeaError> {
+        let value = self.opt(field)?;
+        self.next();
+        Ok(value)
+    }
+
+    /// A value followed by a direction field, negative for `negative`
+    pub(super) fn signed(
+        &mut self,
+        field: &'static str,
+        negative: char,
+    ) -> Result<Option<f64>, NmeaError> {
+        let value: Option<f64> = self.opt(field)?;
+        let direction: Option<char> = self.opt(field)?;
+        Ok(value.map(|value| match direction {
+            Some(d) if d == negative => -value,
+            _ => value,
+        }))
+    }
+
+    /// Latitude or longitude as (d)ddmm.mmmmm followed by the hemisphere
+    pub(super) fn angle(&mut self, field: &'static str) -> Result<Option<f64>, NmeaError> {
+        let value: Option<f64> = self.opt(field)?;
+        let hemisphere: Option<char> = self.opt(field)?;
+        let Some(value) = value else {
+            return Ok(None);
+        };
+        let degrees = libm::trunc(value / 100.0);
+        let angle = degrees + (value - degrees * 100.0) / 60.0;
+        match hemisphere {
+            Some('N' | 'E') => Ok(Some(angle)),
+            Some('S' | 'W') => Ok(Some(-angle)),
+            _ => Err(self.invalid(field)),
+        }
+    }
+
+    /// UTC time as hhmmss.ss
+    pub(super) fn time(&mut self, field: &'static str) -> Result<Option<NaiveTime>, NmeaError> {
+        let invalid = self.invalid(field);
+        match self.next() {
+            None | Some("") => Ok(None),
+            Some(value) => {
+                let number = |range: core::ops::Range<usize>| -> Result<u32, NmeaError> {
+                    value.get(range).and_then(|s| s.parse().ok()).ok_or(invalid)
+                };
+                let seconds: f64 = value.get(4..).and_then(|s| s.parse().ok()).ok_or(invalid)?;
+                let nanos = libm::round(libm::fmod(seconds, 1.0) * 1e9) as u32;
+                NaiveTime::from_hms_nano_opt(number(0..2)?, number(2..4)?, seconds as u32, nanos)
+                    .map(Some)
+                    .ok_or(invalid)
+            },
+        }
+    }
+
+    /// Date as ddmmyy, in the years 2000 to 2099
+    pub(super) fn date(&mut self, field: &'static str) -> Result<Option<NaiveDate>, NmeaError> {
+        let invalid = self.invalid(field);
+        match self.next() {
+            None | Some("") => Ok(None),
+            Some(value) if value.len() == 6 => {
+                let number = |range: core::ops::Range<usize>| -> Result<u32, NmeaError> {
+                    value.get(range).and_then(|s| s.parse().ok()).ok_or(invalid)
+                };
+                NaiveDate::from_ymd_opt(2000 + number(4..6)? as i32, number(2..4)?, number(0..2)?)
+                    .map(Some)
+                    .ok_or(invalid)
+            },
+            Some(_) => Err(invalid),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::nmea::sentence;
+
+    fn parse(body: &str) -> NmeaSentence {
+        NmeaSentence::parse(&sentence(body)).unwrap()
+    }
+
+    #[test]
+    fn rejects_invalid_framing() {
+        assert_eq!(
+            NmeaSentence::parse("GPGLL,4916.45,N*31"),
+            Err(NmeaError::InvalidFraming)
+        );
+        assert_eq!(
+            NmeaSentence::parse("$GPGLL,4916.45,N,12311.12,W,225444,A*32\r\n"),
+            Err(NmeaError::InvalidChecksum {
+                expect: 0x31,
+                got: 0x32
+            })
+        );
+        assert_eq!(
+            NmeaSentence::parse("$GPGLL,4916.45,N,12311.12,W,225444,A*31\r\n"),
+            Ok(NmeaSentence::Other("GPGLL".into()))
+        );
+    }
+
+    #[test]
+    fn rejects_non_ascii() {
+        assert_eq!(
+            NmeaSentence::parse(&sentence("AéBC,1")),
+            Err(NmeaError::InvalidFraming)
+        );
+        assert_eq!(
+            NmeaSentence::parse(&sentence("GPRMC,123519.00,A,,,,,,,1é234,,,A,V")),
+            Err(NmeaError::InvalidFraming)
+        );
+        let mut f = Fields::new("RMC", "1é234");
+        assert_eq!(f.date("date"), Err(f.invalid("date")));
+    }
+
+    #[test]
+    fn parses_generated_epoch() {
+        let gga = match NmeaSentence::parse(
+            "$GPGGA,123519.00,4807.03800,N,01131.00000,E,1,08,0.90,545.4,M,46.9,M,,*59\r\n",
+        ) {
+            Ok(NmeaSentence::Gga(gga)) => gga,
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(gga.talker, Some(Talker::Gps));
+        assert_eq!(gga.time, NaiveTime::from_hms_opt(12, 35, 19));
+        assert!((gga.lat.unwrap() - 48.1173).abs() < 1e-9);
+        assert!((gga.lon.unwrap() - 11.516_666_666_666_667).abs() < 1e-9);
+        assert_eq!(gga.quality, 1);
+        assert_eq!(gga.num_satellites, 8);
+        assert_eq!(gga.hdop, Some(0.9));
+        assert_eq!(gga.altitude, Some(545.4));
+        assert_eq!(gga.geoid_separation, Some(46.9));
+        assert_eq!(gga.diff_age, None);
+        let position = Position::try_from(&gga).unwrap();
+        assert_eq!(position.alt, 545.4);
+
+        let rmc = match NmeaSentence::parse(
+            "$GPRMC,123519.00,A,4807.03800,N,01131.00000,E,1.944,84.40,040324,,,A,V*2A\r\n",
+        ) {
+            Ok(NmeaSentence::Rmc(rmc)) => rmc,
+            other => panic!("{other:?}"),
+        };
+        assert!(rmc.valid);
+        assert_eq!(rmc.date, NaiveDate::from_ymd_opt(2024, 3, 4));
+        assert_eq!(rmc.mode, Some('A'));
+        assert_eq!(rmc.nav_status, Some('V'));
+        let velocity = Velocity::try_from(&rmc).unwrap();
+        assert!((velocity.speed - 1.0).abs() < 1e-3);
+        assert_eq!(velocity.heading, 84.4);
+
+        assert_eq!(
+            NmeaSentence::parse("$GPGSA,A,3,05,12,,,,,,,,,,,1.50,0.90,1.20,1*17\r\n"),
+            Ok(NmeaSentence::Gsa(Gsa {
+                talker: Some(Talker::Gps),
+                automatic: true,
+                nav_mode: 3,
+                satellites: alloc::vec![5, 12],
+                pdop: Some(1.5),
+                hdop: Some(0.9),
+                vdop: Some(1.2),
+                system_id: Some(1),
+            }))
+        );
+        assert_eq!(
+            NmeaSentence::parse("$GPGSV,1,1,02,05,40,083,46,12,05,300,,1*6B\r\n"),
+            Ok(NmeaSentence::Gsv(Gsv {
+                talker: Some(Talker::Gps),
+                count: 1,
+                number: 1,
+                in_view: 2,
+                satellites: alloc::vec![
+                    GsvSatellite {
+                        number: 5,
+                        elevation: Some(40),
+                        azimuth: Some(83),
+                        cno: Some(46),
+                    },
+                    GsvSatellite {
+                        number: 12,
+                        elevation: Some(5),
+                        azimuth: Some(300),
+                        cno: None,
+                    },
+                ],
+                signal_id: Some(1),
+            }))
+        );
+        assert_eq!(
+            NmeaSentence::parse("$GPVTG,84.40,T,,M,1.944,N,3.600,K,A*08\r\n"),
+            Ok(NmeaSentence::Vtg(Vtg {
+                talker: Some(Talker::Gps),
+                course_true: Some(84.4),
+                course_magnetic: None,
+                speed_knots: Some(1.944),
+                speed_kmh: Some(3.6),
+                mode: Some('A'),
+            }))
+        );
+        match NmeaSentence::parse("$GPGST,123519.00,1.4,1.0,1.0,0.0,1.0,1.0,2.0*5D\r\n") {
+            Ok(NmeaSentence::Gst(gst)) => {
+                assert_eq!(gst.rms, Some(1.4));
+                assert_eq!(gst.alt_sigma, Some(2.0));
+            },
+            other => panic!("{other:?}"),
+        }
+        assert_eq!(
+            NmeaSentence::parse("$GPZDA,123519.00,04,03,2024,00,00*68\r\n"),
+            Ok(NmeaSentence::Zda(Zda {
+                talker: Some(Talker::Gps),
+                time: NaiveTime::from_hms_opt(12, 35, 19),
+                date: NaiveDate::from_ymd_opt(2024, 3, 4),
+                zone_hours: Some(0),
+                zone_minutes: Some(0),
+            }))
+        );
+    }
+
+    #[test]
+    fn parses_gns_and_txt() {
+        match parse("GNGNS,091547.50,3338.11803,S,15047.42185,W,AANN,10,0.83,53.2,20.1,,,V") {
+            NmeaSentence::Gns(gns) => {
+                assert_eq!(gns.talker, Some(Talker::Combined));
+                assert_eq!(gns.time, NaiveTime::from_hms_milli_opt(9, 15, 47, 500));
+                assert!((gns.lat.unwrap() + 33.635_300_5).abs() < 1e-9);
+                assert!(gns.lon.unwrap() < -150.0);
+                assert_eq!(gns.modes, "AANN");
+                assert_eq!(gns.num_satellites, 10);
+                assert_eq!(gns.nav_status, Some('V'));
+                let position = Position::try_from(&gns).unwrap();
+                assert_eq!(position.alt, 53.2);
+            },
+            other => panic!("{other:?}"),
+        }
+        assert_eq!(
+            parse("GPTXT,01,01,02,ANTSTATUS=OK, ANTPOWER=ON"),
+            NmeaSentence::Txt(Txt {
+                talker: Some(Talker::Gps),
+                count: 1,
+                number: 1,
+                kind: 2,
+                text: "ANTSTATUS=OK, ANTPOWER=ON".into(),
+            })
+        );
+        match parse("GNGGA,,,,,,0,00,99.99,,,,,,") {
+            NmeaSentence::Gga(gga) => {
+                assert_eq!(gga.time, None);
+                assert!(matches!(Position::try_from(&gga), Err(NmeaError::NoFix)));
+            },
+            other => panic!("{other:?}"),
+        }
+        assert_eq!(
+            NmeaSentence::parse(&sentence("GPGGA,123519.00,4807.038,X,01131.000,E,1")),
+            Err(NmeaError::InvalidField {
+                sentence: "GGA",
+                field: "lat"
+            })
+        );
+    }
+}
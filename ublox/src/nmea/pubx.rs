@@ -0,0 +1,294 @@
+//! u-blox proprietary PUBX messages
+
+use alloc::{format, string::String, vec::Vec};
+
+use chrono::{NaiveDate, NaiveTime};
+
+use super::{parse::Fields, sentence};
+use crate::{error::NmeaError, InProtoMask, OutProtoMask};
+
+/// PUBX,00 position data
+#[derive(Debug, Clone, PartialEq)]
+pub struct PubxPosition {
+    pub time: Option<NaiveTime>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    /// Altitude above the user datum ellipsoid in meters
+    pub alt_ref: Option<f64>,
+    /// NF, DR, G2, G3, D2, D3, RK or TT
+    pub nav_status: String,
+    /// Horizontal accuracy estimate in meters
+    pub h_acc: Option<f64>,
+    /// Vertical accuracy estimate in meters
+    pub v_acc: Option<f64>,
+    /// Speed over ground in km/h
+    pub speed: Option<f64>,
+    /// Course over ground in degrees
+    pub course: Option<f64>,
+    /// Vertical velocity in m/s, positive downwards
+    pub vertical_velocity: Option<f64>,
+    /// Age of the differential corrections in seconds
+    pub diff_age: Option<u16>,
+    pub hdop: Option<f32>,
+    pub vdop: Option<f32>,
+    pub tdop: Option<f32>,
+    pub num_satellites: u8,
+}
+
+impl PubxPosition {
+    pub(super) fn parse(fields: &str) -> Result<Self, NmeaError> {
+        let mut f = Fields::new("PUBX,00", fields);
+        Ok(Self {
+            time: f.time("time")?,
+            lat: f.angle("lat")?,
+            lon: f.angle("long")?,
+            alt_ref: f.opt("altRef")?,
+            nav_status: f.next().unwrap_or_default().into(),
+            h_acc: f.opt("hAcc")?,
+            v_acc: f.opt("vAcc")?,
+            speed: f.opt("SOG")?,
+            course: f.opt("COG")?,
+            vertical_velocity: f.opt("vVel")?,
+            diff_age: f.opt("diffAge")?,
+            hdop: f.opt("HDOP")?,
+            vdop: f.opt("VDOP")?,
+            tdop: f.opt("TDOP")?,
+            num_satellites: f.opt("numSvs")?.unwrap_or(0),
+        })
+    }
+}
+
+/// Satellite of a PUBX,03 message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PubxSatellite {
+    pub sv: u8,
+    /// `U` used in the solution, `e` ephemeris available but not used,
+    /// `-` not used
+    pub status: char,
+    /// Azimuth in degrees
+    pub azimuth: Option<u16>,
+    /// Elevation in degrees
+    pub elevation: Option<i8>,
+    /// Carrier to noise ratio in dBHz
+    pub cno: u8,
+    /// Carrier lock time in seconds, 64 meaning 64 s or more
+    pub lock_time: u8,
+}
+
+/// PUBX,03 satellite status
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PubxSatellites {
+    pub satellites: Vec<PubxSatellite>,
+}
+
+impl PubxSatellites {
+    pub(super) fn parse(fields: &str) -> Result<Self, NmeaError> {
+        let mut f = Fields::new("PUBX,03", fields);
+        let count: usize = f.req("GT")?;
+        let satellites = (0..count)
+            .map(|_| {
+                Ok(PubxSatellite {
+                    sv: f.req("sv")?,
+                    status: f.req("s")?,
+                    azimuth: f.opt("az")?,
+                    elevation: f.opt("el")?,
+                    cno: f.opt("cno")?.unwrap_or(0),
+                    lock_time: f.opt("lck")?.unwrap_or(0),
+                })
+            })
+            .collect::<Result<_, NmeaError>>()?;
+        Ok(Self { satellites })
+    }
+}
+
+/// PUBX,04 time of day and clock information
+#[derive(Debug, Clone, PartialEq)]
+pub struct PubxTime {
+    pub time: Option<NaiveTime>,
+    pub date: Option<NaiveDate>,
+    /// UTC time of week in seconds
+    pub utc_tow: Option<f64>,
+    pub utc_week: Option<u16>,
+    pub leap_seconds: Option<u8>,
+    /// The leap seconds are the firmware default, not yet received from the
+    /// navigation data
+    pub leap_seconds_default: bool,
+    /// Receiver clock bias in nanoseconds
+    pub clock_bias: Option<i64>,
+    /// Receiver clock drift in nanoseconds per second
+    pub clock_drift: Option<f64>,
+    /// Time pulse granularity in nanoseconds
+    pub timepulse_granularity: Option<u32>,
+}
+
+impl PubxTime {
+    pub(super) fn parse(fields: &str) -> Result<Self, NmeaError> {
+        let mut f = Fields::new("PUBX,04", fields);
+        let time = f.time("time")?;
+        let date = f.date("date")?;
+        let utc_tow = f.opt("utcTow")?;
+        let utc_week = f.opt("utcWk")?;
+        let leap = f.next().unwrap_or_default();
+        let (leap, leap_seconds_default) = match leap.strip_suffix('D') {
+            Some(leap) => (leap, true),
+            None => (leap, false),
+        };
+        let leap_seconds = match leap {
+            "" => None,
+            leap => Some(leap.parse().map_err(|_| f.invalid("leapSec"))?),
+        };
+        Ok(Self {
+            time,
+            date,
+            utc_tow,
+            utc_week,
+            leap_seconds,
+            leap_seconds_default,
+            clock_bias: f.opt("clkBias")?,
+            clock_drift: f.opt("clkDrift")?,
+            timepulse_granularity: f.opt("tpGran")?,
+        })
+    }
+}
+
+/// PUBX,40 sets the output rate of a standard NMEA sentence on each port.
+/// A rate of `n` outputs the sentence once every `n` navigation solutions,
+/// 0 disables it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PubxRateBuilder<'a> {
+    /// Sentence formatter, e.g. `GLL`
+    pub msg_id: &'a str,
+    pub ddc: u8,
+    pub uart1: u8,
+    pub uart2: u8,
+    pub usb: u8,
+    pub spi: u8,
+}
+
+impl PubxRateBuilder<'_> {
+    pub fn into_sentence(self) -> String {
+        sentence(&format!(
+            "PUBX,40,{},{},{},{},{},{},0",
+            self.msg_id, self.ddc, self.uart1, self.uart2, self.usb, self.spi
+        ))
+    }
+}
+
+/// PUBX,41 sets the protocols and baud rate of a port
+#[derive(Debug)]
+pub struct PubxConfigBuilder {
+    /// 0 DDC, 1 UART1, 2 UART2, 3 USB, 4 SPI
+    pub port_id: u8,
+    pub in_proto: InProtoMask,
+    pub out_proto: OutProtoMask,
+    pub baud_rate: u32,
+    pub autobauding: bool,
+}
+
+impl PubxConfigBuilder {
+    pub fn into_sentence(self) -> String {
+        sentence(&format!(
+            "PUBX,41,{},{:04X},{:04X},{},{}",
+            self.port_id,
+            self.in_proto.bits(),
+            self.out_proto.bits(),
+            self.baud_rate,
+            u8::from(self.autobauding)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::nmea::NmeaSentence;
+
+    #[test]
+    fn parses_position() {
+        let line = sentence(
+            "PUBX,00,081350.00,4717.113210,N,00833.915187,E,546.589,G3,2.1,2.0,0.007,77.52,0.007,,0.92,1.19,0.77,9,0,0",
+        );
+        match NmeaSentence::parse(&line) {
+            Ok(NmeaSentence::PubxPosition(position)) => {
+                assert_eq!(position.time, NaiveTime::from_hms_opt(8, 13, 50));
+                assert!((position.lat.unwrap() - 47.285_220_166_666_67).abs() < 1e-9);
+                assert_eq!(position.alt_ref, Some(546.589));
+                assert_eq!(position.nav_status, "G3");
+                assert_eq!(position.h_acc, Some(2.1));
+                assert_eq!(position.course, Some(77.52));
+                assert_eq!(position.diff_age, None);
+                assert_eq!(position.tdop, Some(0.77));
+                assert_eq!(position.num_satellites, 9);
+            },
+            other => panic!("{other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_satellites() {
+        let line = sentence("PUBX,03,02,23,-,,,45,010,29,U,171,67,39,064");
+        assert_eq!(
+            NmeaSentence::parse(&line),
+            Ok(NmeaSentence::PubxSatellites(PubxSatellites {
+                satellites: alloc::vec![
+                    PubxSatellite {
+                        sv: 23,
+                        status: '-',
+                        azimuth: None,
+                        elevation: None,
+                        cno: 45,
+                        lock_time: 10,
+                    },
+                    PubxSatellite {
+                        sv: 29,
+                        status: 'U',
+                        azimuth: Some(171),
+                        elevation: Some(67),
+                        cno: 39,
+                        lock_time: 64,
+                    },
+                ],
+            }))
+        );
+    }
+
+    #[test]
+    fn parses_time() {
+        let line = sentence("PUBX,04,073731.00,091202,113851.00,1196,15D,1930035,-2660.664,43");
+        assert_eq!(
+            NmeaSentence::parse(&line),
+            Ok(NmeaSentence::PubxTime(PubxTime {
+                time: NaiveTime::from_hms_opt(7, 37, 31),
+                date: NaiveDate::from_ymd_opt(2002, 12, 9),
+                utc_tow: Some(113851.0),
+                utc_week: Some(1196),
+                leap_seconds: Some(15),
+                leap_seconds_default: true,
+                clock_bias: Some(1930035),
+                clock_drift: Some(-2660.664),
+                timepulse_granularity: Some(43),
+            }))
+        );
+    }
+
+    #[test]
+    fn builds_configuration() {
+        let rate = PubxRateBuilder {
+            msg_id: "GLL",
+            uart1: 1,
+            ..Default::default()
+        };
+        assert_eq!(rate.into_sentence(), "$PUBX,40,GLL,0,1,0,0,0,0*5D\r\n");
+        let config = PubxConfigBuilder {
+            port_id: 1,
+            in_proto: InProtoMask::UBLOX | InProtoMask::NMEA | InProtoMask::RTCM,
+            out_proto: OutProtoMask::NMEA,
+            baud_rate: 19200,
+            autobauding: false,
+        };
+        assert_eq!(
+            config.into_sentence(),
+            "$PUBX,41,1,0007,0002,19200,0*24\r\n"
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! RINEX 3.04 export of u-blox raw data.
+//!
+//! The writers format into any [core::fmt::Write], so they can be used with a
+//! `String` as well as with an adapter around a file or socket.
+
+use core::fmt::{self, Write};
+
+mod nav;
+mod obs;
+
+pub use nav::{NavHeader, NavWriter};
+pub use obs::{ObsHeader, ObsWriter};
+
+/// RINEX satellite system identifier and number of a satellite,
+/// from its u-blox `gnss_id` and `sv_id`
+pub fn satellite(gnss_id: u8, sv_id: u8) -> Option<(char, u8)> {
+    let sat = match (gnss_id, sv_id) {
+        (0, 1..=32) => ('G', sv_id),
+        (1, 120..=158) => ('S', sv_id - 100),
+        (2, 1..=36) => ('E', sv_id),
+        (3, 1..=63) => ('C', sv_id),
+        (5, 1..=10) => ('J', sv_id),
+        (6, 1..=32) => ('R', sv_id),
+        (7, 1..=14) => ('I', sv_id),
+        _ => return None,
+    };
+    Some(sat)
+}
+
+/// RINEX 3 observation code (band and attribute, e.g. `"1C"`) of a u-blox signal.
+///
+/// `sig_id` is 0 on receivers before protocol version 27, which maps to the
+/// only signal they track (L1 C/A, E1 C, B1I, ...).
+pub fn signal_code(gnss_id: u8, sig_id: u8) -> Option<&'static str> {
+    let code = match (gnss_id, sig_id) {
+        // GPS
+        (0, 0) => "1C",
+        (0, 3) => "2L",
+        (0, 4) => "2S",
+        (0, 6) => "5I",
+        (0, 7) => "5Q",
+        // SBAS
+        (1, 0) => "1C",
+        // Galileo
+        (2, 0) => "1C",
+        (2, 1) => "1B",
+        (2, 3) => "5I",
+        (2, 4) => "5Q",
+        (2, 5) => "7I",
+        (2, 6) => "7Q",
+        (2, 8) => "6B",
+        (2, 9) => "6C",
+        (2, 10) => "6A",
+        // BeiDou
+        (3, 0) | (3, 1) => "2I",
+        (3, 2) | (3, 3) => "7I",
+        (3, 5) => "1P",
+        (3, 7) => "5P",
+        // QZSS
+        (5, 0) => "1C",
+        (5, 1) => "1Z",
+        (5, 4) => "2S",
+        (5, 5) => "2L",
+        (5, 8) => "5I",
+        (5, 9) => "5Q",
+        // GLONASS
+        (6, 0) => "1C",
+        (6, 2) => "2C",
+        // NavIC
+        (7, 0) => "5A",
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// Writes a header line, `content` padded or truncated to 60 columns followed by the label
+fn header_line<W: Write>(out: &mut W, content: fmt::Arguments<'_>, label: &str) -> fmt::Result {
+    let mut line = alloc::string::String::new();
+    line.write_fmt(content)?;
+    let content = match line.char_indices().nth(60) {
+        Some((end, _)) => &line[..end],
+        None => &line,
+    };
+    writeln!(out, "{:<60}{}", content, label)
+}
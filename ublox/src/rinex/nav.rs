@@ -0,0 +1,632 @@
+use alloc::{collections::BTreeMap, format, string::String};
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use core::fmt::{self, Write};
+
+use super::header_line;
+use crate::{
+    navmsg::{
+        Ephemeris, GlonassEphemeris, GlonassTimeParameters, KlobucharModel, NavMessage,
+        NeQuickModel, UtcParameters,
+    },
+    ubx_packets::{GnssId, GnssTime, LeapSeconds, TimeScale},
+};
+
+const NANOS_PER_SEC: i64 = 1_000_000_000;
+const NANOS_PER_DAY: i64 = 86_400 * NANOS_PER_SEC;
+const NANOS_PER_WEEK: i64 = 7 * NANOS_PER_DAY;
+
+/// Galileo weeks in RINEX are aligned to GPS weeks
+const GAL_WEEK_OFFSET: i64 = 1024;
+
+/// Data sources of Galileo I/NAV ephemerides: I/NAV E1-B, clock for E5b,E1
+const GAL_INAV_SOURCES: f64 = 513.0;
+
+/// GPS URA index to meters (IS-GPS-200 20.3.3.3.1.3), also used for BeiDou URAI
+const URA_METERS: [f64; 15] = [
+    2.4, 3.4, 4.85, 6.85, 9.65, 13.65, 24.0, 48.0, 96.0, 192.0, 384.0, 768.0, 1536.0, 3072.0,
+    6144.0,
+];
+
+/// Information for the navigation file header
+#[derive(Debug, Clone, Default)]
+pub struct NavHeader {
+    pub agency: String,
+    /// File creation date (UTC), defaults to the time of the first message
+    pub date: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone)]
+enum Record {
+    Kepler {
+        eph: Ephemeris,
+        toc: GnssTime,
+        toe: GnssTime,
+        transmission: GnssTime,
+    },
+    Glonass {
+        eph: GlonassEphemeris,
+        toc: GnssTime,
+        frame_time: f64,
+    },
+}
+
+/// Collects ephemerides, ionosphere and time system parameters and writes them
+/// as a RINEX 3.04 mixed navigation file.
+///
+/// Ephemerides carry their time of week only, so every message is pushed with
+/// the time it was received. The ephemeris times are taken to be those
+/// closest to it, which also resolves the week number rollovers.
+#[derive(Debug, Clone)]
+pub struct NavWriter {
+    header: NavHeader,
+    /// Ephemerides by satellite and time of clock
+    records: BTreeMap<((char, u8), i64), Record>,
+    klobuchar: BTreeMap<&'static str, KlobucharModel>,
+    nequick: Option<NeQuickModel>,
+    /// Time system corrections a0, a1, reference time and week by RINEX identifier
+    corrections: BTreeMap<&'static str, (f64, f64, u32, i64)>,
+    /// Leap second information and the system it was broadcast by
+    leap: Option<(UtcParameters, i64, &'static str)>,
+    leap_seconds: LeapSeconds,
+    first: Option<GnssTime>,
+}
+
+impl Default for NavWriter {
+    fn default() -> Self {
+        Self::new(NavHeader::default())
+    }
+}
+
+impl NavWriter {
+    pub fn new(header: NavHeader) -> Self {
+        Self {
+            header,
+            records: BTreeMap::new(),
+            klobuchar: BTreeMap::new(),
+            nequick: None,
+            corrections: BTreeMap::new(),
+            leap: None,
+            // Only needed to relate GLONASS to GPS time, updated from the UTC parameters
+            leap_seconds: LeapSeconds::new(18),
+            first: None,
+        }
+    }
+
+    pub fn header_mut(&mut self) -> &mut NavHeader {
+        &mut self.header
+    }
+
+    /// Number of ephemerides collected so far
+    pub fn ephemerides(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Adds a navigation message received at `received`.
+    /// Messages of satellites unknown to RINEX and almanacs are skipped.
+    pub fn push(&mut self, message: &NavMessage, received: &GnssTime) {
+        self.first.get_or_insert(*received);
+        match message {
+            NavMessage::Ephemeris(eph) => self.push_ephemeris(eph, received),
+            NavMessage::GlonassEphemeris(eph) => self.push_glonass(eph, received),
+            NavMessage::Almanac(_) => {},
+            NavMessage::Klobuchar(gnss, model) => {
+                if let Some((label, _)) = labels(*gnss) {
+                    self.klobuchar.insert(label, *model);
+                }
+            },
+            NavMessage::NeQuick(model) => self.nequick = Some(*model),
+            NavMessage::Utc(gnss, utc) => self.push_utc(*gnss, utc, received),
+            NavMessage::GlonassTime(time) => self.push_glonass_time(time),
+        }
+    }
+
+    /// Adds a GPS, QZSS, Galileo or BeiDou ephemeris, e.g. one converted from
+    /// MGA-GPS-EPH, see [NavWriter::push]
+    pub fn push_ephemeris(&mut self, eph: &Ephemeris, received: &GnssTime) {
+        let Some(scale) = time_scale(eph.gnss) else {
+            return;
+        };
+        let Some(sat) = super::satellite(eph.gnss as u8, eph.sv_id) else {
+            return;
+        };
+        let reference = received.to_scale(scale, &self.leap_seconds);
+        let toc = nearest(&reference, eph.toc, NANOS_PER_WEEK);
+        let toe = nearest(&reference, eph.toe, NANOS_PER_WEEK);
+        self.records.insert(
+            (sat, toc.nanos()),
+            Record::Kepler {
+                eph: eph.clone(),
+                toc,
+                toe,
+                transmission: reference,
+            },
+        );
+    }
+
+    /// Adds a GLONASS ephemeris, see [NavWriter::push]
+    pub fn push_glonass(&mut self, eph: &GlonassEphemeris, received: &GnssTime) {
+        let Some(sat) = super::satellite(GnssId::GLONASS as u8, eph.sv_id) else {
+            return;
+        };
+        // tb is given in Moscow time, RINEX uses UTC
+        let reference = received.to_scale(TimeScale::Glonass, &self.leap_seconds);
+        let toc = nearest(&reference, f64::from(eph.tb), NANOS_PER_DAY).to_utc(&self.leap_seconds);
+        let frame_time = libm::round(reference.to_utc(&self.leap_seconds).tow());
+        self.records.insert(
+            (sat, toc.nanos()),
+            Record::Glonass {
+                eph: eph.clone(),
+                toc,
+                frame_time,
+            },
+        );
+    }
+
+    fn push_utc(&mut self, gnss: GnssId, utc: &UtcParameters, received: &GnssTime) {
+        let (Some(scale), Some((_, label))) = (time_scale(gnss), labels(gnss)) else {
+            return;
+        };
+        let reference = received.to_scale(scale, &self.leap_seconds);
+        // Weeks are broadcast modulo 256
+        let resolve = |week: u16| nearest_week(reference.week(), i64::from(week), 256);
+        let rinex_week = |week: i64| match gnss {
+            GnssId::GALILEO => week + GAL_WEEK_OFFSET,
+            _ => week,
+        };
+        let (tot, wnt) = match gnss {
+            // The BeiDou polynomial has no reference time, it is evaluated at the time of week
+            GnssId::BEIDOU => (0, reference.week()),
+            _ => (utc.tot, resolve(utc.wnt)),
+        };
+        self.corrections
+            .insert(label, (utc.a0, utc.a1, tot, rinex_week(wnt)));
+
+        // RINEX only knows GPS and BeiDou leap seconds, GPS is preferred
+        let system = match gnss {
+            GnssId::GPS => "",
+            GnssId::BEIDOU => "BDS",
+            _ => return,
+        };
+        if gnss == GnssId::GPS {
+            self.leap_seconds = LeapSeconds::new(utc.delta_t_ls);
+        }
+        if gnss == GnssId::GPS || !matches!(self.leap, Some((_, _, ""))) {
+            self.leap = Some((*utc, resolve(utc.wn_lsf), system));
+        }
+    }
+
+    fn push_glonass_time(&mut self, time: &GlonassTimeParameters) {
+        self.corrections.insert("GLUT", (time.tau_c, 0.0, 0, 0));
+        self.corrections.insert("GLGP", (time.tau_gps, 0.0, 0, 0));
+    }
+
+    /// Writes header and all ephemerides
+    pub fn write<W: Write>(&self, out: &mut W) -> fmt::Result {
+        self.write_header(out)?;
+        for ((sat, _), record) in &self.records {
+            match record {
+                Record::Kepler {
+                    eph,
+                    toc,
+                    toe,
+                    transmission,
+                } => write_kepler(out, *sat, eph, toc, toe, transmission)?,
+                Record::Glonass {
+                    eph,
+                    toc,
+                    frame_time,
+                } => write_glonass(out, *sat, eph, toc, *frame_time)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn write_header<W: Write>(&self, out: &mut W) -> fmt::Result {
+        header_line(
+            out,
+            format_args!("{:9.2}{:11}{:<20}{:<20}", 3.04, "", "N: GNSS NAV DATA", "M"),
+            "RINEX VERSION / TYPE",
+        )?;
+        let first = self
+            .first
+            .map(|time| time.to_utc(&self.leap_seconds).to_naive_datetime());
+        let date = match self.header.date.or(first) {
+            Some(date) => format!(
+                "{:04}{:02}{:02} {:02}{:02}{:02} UTC",
+                date.year(),
+                date.month(),
+                date.day(),
+                date.hour(),
+                date.minute(),
+                date.second()
+            ),
+            None => String::new(),
+        };
+        header_line(
+            out,
+            format_args!("{:<20}{:<20}{:<20}", "ublox", self.header.agency, date),
+            "PGM / RUN BY / DATE",
+        )?;
+
+        if let Some(model) = self.nequick {
+            let [a0, a1, a2] = model.ai;
+            header_line(
+                out,
+                format_args!(
+                    "GAL  {}{}{}{}",
+                    sci(a0, 12, 4),
+                    sci(a1, 12, 4),
+                    sci(a2, 12, 4),
+                    sci(0.0, 12, 4)
+                ),
+                "IONOSPHERIC CORR",
+            )?;
+        }
+        for (label, model) in &self.klobuchar {
+            for (suffix, values) in [('A', model.alpha), ('B', model.beta)] {
+                let [v0, v1, v2, v3] = values;
+                header_line(
+                    out,
+                    format_args!(
+                        "{}{} {}{}{}{}",
+                        label,
+                        suffix,
+                        sci(v0, 12, 4),
+                        sci(v1, 12, 4),
+                        sci(v2, 12, 4),
+                        sci(v3, 12, 4)
+                    ),
+                    "IONOSPHERIC CORR",
+                )?;
+            }
+        }
+
+        for (label, (a0, a1, tot, week)) in &self.corrections {
+            header_line(
+                out,
+                format_args!(
+                    "{} {}{} {:6} {:4}",
+                    label,
+                    sci(*a0, 17, 10),
+                    sci(*a1, 16, 9),
+                    tot,
+                    week
+                ),
+                "TIME SYSTEM CORR",
+            )?;
+        }
+
+        if let Some((utc, wn_lsf, system)) = &self.leap {
+            header_line(
+                out,
+                format_args!(
+                    "{:6}{:6}{:6}{:6}{:<3}",
+                    utc.delta_t_ls, utc.delta_t_lsf, wn_lsf, utc.dn, system
+                ),
+                "LEAP SECONDS",
+            )?;
+        }
+
+        header_line(out, format_args!(""), "END OF HEADER")
+    }
+}
+
+/// Time scale of the ephemerides of a system
+fn time_scale(gnss: GnssId) -> Option<TimeScale> {
+    match gnss {
+        GnssId::GPS | GnssId::QZSS => Some(TimeScale::Gps),
+        GnssId::GALILEO => Some(TimeScale::Galileo),
+        GnssId::BEIDOU => Some(TimeScale::BeiDou),
+        _ => None,
+    }
+}
+
+/// Ionosphere label prefix and time system correction identifier of a system
+fn labels(gnss: GnssId) -> Option<(&'static str, &'static str)> {
+    match gnss {
+        GnssId::GPS => Some(("GPS", "GPUT")),
+        GnssId::GALILEO => Some(("GAL", "GAUT")),
+        GnssId::BEIDOU => Some(("BDS", "BDUT")),
+        GnssId::QZSS => Some(("QZS", "QZUT")),
+        _ => None,
+    }
+}
+
+/// Time with the given seconds into the day or week that is closest to `reference`
+fn nearest(reference: &GnssTime, seconds: f64, period: i64) -> GnssTime {
+    let start = reference.nanos() - reference.nanos().rem_euclid(period);
+    let mut nanos = start + (seconds * 1e9) as i64;
+    if nanos - reference.nanos() > period / 2 {
+        nanos -= period;
+    } else if reference.nanos() - nanos > period / 2 {
+        nanos += period;
+    }
+    GnssTime::new(reference.scale(), nanos)
+}
+
+/// Full week number closest to `reference` of a week broadcast modulo `modulus`
+fn nearest_week(reference: i64, week: i64, modulus: i64) -> i64 {
+    let offset = (week - reference).rem_euclid(modulus);
+    if offset > modulus / 2 {
+        reference + offset - modulus
+    } else {
+        reference + offset
+    }
+}
+
+/// Accuracy in meters of a URA, SISA or URAI index
+fn accuracy_meters(gnss: GnssId, index: u8) -> f64 {
+    let value = f64::from(index);
+    match (gnss, index) {
+        (GnssId::GALILEO, 0..=49) => value * 0.01,
+        (GnssId::GALILEO, 50..=74) => 0.5 + (value - 50.0) * 0.02,
+        (GnssId::GALILEO, 75..=99) => 1.0 + (value - 75.0) * 0.04,
+        (GnssId::GALILEO, 100..=125) => 2.0 + (value - 100.0) * 0.16,
+        // No accuracy prediction available
+        (GnssId::GALILEO, _) => -1.0,
+        _ => URA_METERS[usize::from(index).min(URA_METERS.len() - 1)],
+    }
+}
+
+/// Formats `value` in Fortran `D` notation, `E` exponent with two digits
+fn sci(value: f64, width: usize, precision: usize) -> String {
+    let formatted = format!("{:.*e}", precision, value);
+    let (mantissa, exponent) = formatted.split_once('e').unwrap_or((&formatted, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    let sign = if exponent < 0 { '-' } else { '+' };
+    format!(
+        "{:>width$}",
+        format!("{}E{}{:02}", mantissa, sign, exponent.abs()),
+        width = width
+    )
+}
+
+/// Writes the epoch line of a record: satellite, epoch and three clock values
+fn write_epoch<W: Write>(
+    out: &mut W,
+    (system, prn): (char, u8),
+    epoch: NaiveDateTime,
+    clock: [f64; 3],
+) -> fmt::Result {
+    writeln!(
+        out,
+        "{}{:02} {:04} {:02} {:02} {:02} {:02} {:02}{}{}{}",
+        system,
+        prn,
+        epoch.year(),
+        epoch.month(),
+        epoch.day(),
+        epoch.hour(),
+        epoch.minute(),
+        epoch.second(),
+        sci(clock[0], 19, 12),
+        sci(clock[1], 19, 12),
+        sci(clock[2], 19, 12)
+    )
+}
+
+/// Writes a broadcast orbit line, `None` values are left blank
+fn write_orbit<W: Write>(out: &mut W, values: [Option<f64>; 4]) -> fmt::Result {
+    let mut line = String::from("    ");
+    for value in values {
+        match value {
+            Some(value) => line.push_str(&sci(value, 19, 12)),
+            None => line.push_str(&format!("{:19}", "")),
+        }
+    }
+    writeln!(out, "{}", line.trim_end())
+}
+
+fn write_kepler<W: Write>(
+    out: &mut W,
+    sat: (char, u8),
+    eph: &Ephemeris,
+    toc: &GnssTime,
+    toe: &GnssTime,
+    transmission: &GnssTime,
+) -> fmt::Result {
+    write_epoch(
+        out,
+        sat,
+        toc.to_naive_datetime(),
+        [eph.af0, eph.af1, eph.af2],
+    )?;
+    let iode = f64::from(eph.iode);
+    write_orbit(
+        out,
+        [Some(iode), Some(eph.crs), Some(eph.delta_n), Some(eph.m0)],
+    )?;
+    write_orbit(
+        out,
+        [Some(eph.cuc), Some(eph.e), Some(eph.cus), Some(eph.sqrt_a)],
+    )?;
+    write_orbit(
+        out,
+        [
+            Some(toe.tow()),
+            Some(eph.cic),
+            Some(eph.omega0),
+            Some(eph.cis),
+        ],
+    )?;
+    write_orbit(
+        out,
+        [
+            Some(eph.i0),
+            Some(eph.crc),
+            Some(eph.omega),
+            Some(eph.omega_dot),
+        ],
+    )?;
+
+    let week = toe.week() as f64;
+    let accuracy = accuracy_meters(eph.gnss, eph.accuracy);
+    let health = f64::from(eph.health);
+    let [tgd1, tgd2] = eph.tgd;
+    let iodc = f64::from(eph.iodc);
+    let transmission = libm::round(transmission.tow());
+    match eph.gnss {
+        GnssId::GALILEO => {
+            let week = week + GAL_WEEK_OFFSET as f64;
+            write_orbit(
+                out,
+                [Some(eph.idot), Some(GAL_INAV_SOURCES), Some(week), None],
+            )?;
+            write_orbit(out, [Some(accuracy), Some(health), Some(tgd1), Some(tgd2)])?;
+            write_orbit(out, [Some(transmission), None, None, None])
+        },
+        GnssId::BEIDOU => {
+            write_orbit(out, [Some(eph.idot), None, Some(week), None])?;
+            write_orbit(out, [Some(accuracy), Some(health), Some(tgd1), Some(tgd2)])?;
+            write_orbit(out, [Some(transmission), Some(iodc), None, None])
+        },
+        _ => {
+            let l2_codes = f64::from(eph.l2_codes);
+            let l2p_flag = f64::from(u8::from(eph.l2p_flag));
+            // Curve fits longer than four hours are nominally six hours
+            let fit_interval = if eph.fit_interval { 6.0 } else { 4.0 };
+            write_orbit(
+                out,
+                [Some(eph.idot), Some(l2_codes), Some(week), Some(l2p_flag)],
+            )?;
+            write_orbit(out, [Some(accuracy), Some(health), Some(tgd1), Some(iodc)])?;
+            write_orbit(out, [Some(transmission), Some(fit_interval), None, None])
+        },
+    }
+}
+
+fn write_glonass<W: Write>(
+    out: &mut W,
+    sat: (char, u8),
+    eph: &GlonassEphemeris,
+    toc: &GnssTime,
+    frame_time: f64,
+) -> fmt::Result {
+    write_epoch(
+        out,
+        sat,
+        toc.to_naive_datetime(),
+        [-eph.tau_n, eph.gamma_n, frame_time],
+    )?;
+    // RINEX uses kilometers
+    let extra = [
+        f64::from(eph.health),
+        f64::from(eph.frequency),
+        f64::from(eph.age),
+    ];
+    for (axis, extra) in extra.into_iter().enumerate() {
+        write_orbit(
+            out,
+            [
+                Some(eph.position[axis] * 1e-3),
+                Some(eph.velocity[axis] * 1e-3),
+                Some(eph.acceleration[axis] * 1e-3),
+                Some(extra),
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fortran_notation() {
+        assert_eq!(sci(0.0, 19, 12), " 0.000000000000E+00");
+        assert_eq!(sci(-1.25e-9, 19, 12), "-1.250000000000E-09");
+        assert_eq!(sci(5153.6, 12, 4), "  5.1536E+03");
+    }
+
+    #[test]
+    fn resolves_weeks_and_days() {
+        assert_eq!(nearest_week(2300, 2300 % 1024, 1024), 2300);
+        assert_eq!(nearest_week(2300, 250, 256), 2298);
+        assert_eq!(nearest_week(2303, 1, 256), 2305);
+
+        // Saturday evening, ephemeris of the next week
+        let reference = GnssTime::from_week_tow_secs(TimeScale::Gps, 2300, 604_000.0);
+        let toc = nearest(&reference, 7200.0, NANOS_PER_WEEK);
+        assert_eq!((toc.week(), toc.tow()), (2301, 7200.0));
+    }
+
+    #[test]
+    fn writes_gps_and_glonass_records() {
+        let mut writer = NavWriter::new(NavHeader::default());
+        let received = GnssTime::from_week_tow_secs(TimeScale::Gps, 2300, 7000.0);
+        let eph = Ephemeris {
+            gnss: GnssId::GPS,
+            sv_id: 5,
+            week: 2300 % 1024,
+            toe: 7200.0,
+            toc: 7200.0,
+            iode: 77,
+            iodc: 77,
+            accuracy: 0,
+            health: 0,
+            sqrt_a: 5153.6,
+            e: 0.01,
+            i0: 0.96,
+            idot: 0.0,
+            omega0: 1.0,
+            omega_dot: -8e-9,
+            omega: 0.5,
+            m0: 2.0,
+            delta_n: 4e-9,
+            cuc: 0.0,
+            cus: 0.0,
+            crc: 200.0,
+            crs: 10.0,
+            cic: 0.0,
+            cis: 0.0,
+            af0: 1e-4,
+            af1: 0.0,
+            af2: 0.0,
+            tgd: [-1e-8, 0.0],
+            l2_codes: 1,
+            l2p_flag: false,
+            fit_interval: false,
+        };
+        writer.push(&NavMessage::Ephemeris(eph), &received);
+        writer.push(
+            &NavMessage::GlonassEphemeris(GlonassEphemeris {
+                sv_id: 3,
+                frequency: 5,
+                tk: 0,
+                tb: 5400 + 3 * 3600,
+                nt: 0,
+                position: [1e7, 2e7, 0.0],
+                velocity: [0.0; 3],
+                acceleration: [0.0; 3],
+                tau_n: 1e-5,
+                gamma_n: 0.0,
+                delta_tau_n: 0.0,
+                health: 0,
+                age: 0,
+                accuracy: 0,
+                m: 1,
+            }),
+            &received,
+        );
+        assert_eq!(writer.ephemerides(), 2);
+
+        let mut out = String::new();
+        writer.write(&mut out).unwrap();
+        let lines: alloc::vec::Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines[0],
+            "     3.04           N: GNSS NAV DATA    M                   RINEX VERSION / TYPE"
+        );
+        let eph = lines.iter().position(|l| l.starts_with("G05")).unwrap();
+        assert_eq!(
+            lines[eph],
+            "G05 2024 02 04 02 00 00 1.000000000000E-04 0.000000000000E+00 0.000000000000E+00"
+        );
+        assert!(lines[eph + 5].contains(" 2.300000000000E+03"));
+        // GLONASS epochs are in UTC
+        let glo = lines.iter().position(|l| l.starts_with("R03")).unwrap();
+        assert!(lines[glo].starts_with("R03 2024 02 04 01 30 00-1.000000000000E-05"));
+        assert!(lines[glo + 1].starts_with("     1.000000000000E+04"));
+    }
+}
@@ -0,0 +1,470 @@
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    format,
+    string::String,
+    vec::Vec,
+};
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use core::{
+    convert::TryFrom,
+    fmt::{self, Write},
+};
+
+use super::{header_line, satellite, signal_code};
+use crate::{
+    error::DateTimeError,
+    ubx_packets::{GnssTime, MonVerRef, PositionECEF, RxmRawxRef, TrkStatFlags},
+};
+
+/// Loss of lock indicator: lost lock, possible cycle slip
+const LLI_SLIP: u8 = 0x01;
+/// Loss of lock indicator: half-cycle ambiguity not resolved
+const LLI_HALF_CYCLE: u8 = 0x02;
+
+/// Observation types written for every signal code
+const OBS_KINDS: [char; 4] = ['C', 'L', 'D', 'S'];
+
+/// Station and receiver information for the observation file header
+#[derive(Debug, Clone, Default)]
+pub struct ObsHeader {
+    pub marker_name: String,
+    pub observer: String,
+    pub agency: String,
+    pub receiver_number: String,
+    pub receiver_type: String,
+    pub receiver_version: String,
+    pub antenna_number: String,
+    pub antenna_type: String,
+    /// Approximate marker position
+    pub approx_position: Option<PositionECEF>,
+    /// Antenna height, east and north eccentricities in meters
+    pub antenna_delta: [f64; 3],
+    /// File creation date (UTC), defaults to the time of the first observation
+    pub date: Option<NaiveDateTime>,
+}
+
+impl ObsHeader {
+    /// Takes receiver type and firmware version from a MON-VER message
+    pub fn set_receiver(&mut self, version: &MonVerRef<'_>) {
+        self.receiver_type = String::from(version.hardware_version());
+        self.receiver_version = String::from(version.software_version());
+        for extension in version.extension() {
+            if let Some(model) = extension.strip_prefix("MOD=") {
+                self.receiver_type = String::from(model);
+            } else if let Some(firmware) = extension.strip_prefix("FWVER=") {
+                self.receiver_version = String::from(firmware);
+            }
+        }
+    }
+}
+
+type Satellite = (char, u8);
+
+#[derive(Debug, Clone, Copy)]
+struct Observation {
+    pseudorange: Option<f64>,
+    carrier_phase: Option<f64>,
+    doppler: f64,
+    cno: u8,
+    lli: u8,
+}
+
+#[derive(Debug, Clone)]
+struct Epoch {
+    time: GnssTime,
+    observations: BTreeMap<Satellite, BTreeMap<&'static str, Observation>>,
+}
+
+/// Collects RXM-RAWX epochs and writes them as a RINEX 3.04 observation file.
+///
+/// The observation types listed in the header depend on all signals seen,
+/// so the file is written in one go once all epochs are pushed.
+/// Loss of lock is flagged when the lock time of a signal is shorter than
+/// the time since its previous observation.
+#[derive(Debug, Clone, Default)]
+pub struct ObsWriter {
+    header: ObsHeader,
+    epochs: Vec<Epoch>,
+    obs_types: BTreeMap<char, BTreeSet<&'static str>>,
+    glonass_slots: BTreeMap<u8, i8>,
+    /// Time and lock time (ms) of the previous observation of each signal
+    lock: BTreeMap<(Satellite, &'static str), (GnssTime, u16)>,
+}
+
+impl ObsWriter {
+    pub fn new(header: ObsHeader) -> Self {
+        Self {
+            header,
+            ..Default::default()
+        }
+    }
+
+    pub fn header_mut(&mut self) -> &mut ObsHeader {
+        &mut self.header
+    }
+
+    /// Number of epochs collected so far
+    pub fn epochs(&self) -> usize {
+        self.epochs.len()
+    }
+
+    /// Adds the measurements of one RXM-RAWX epoch.
+    /// Measurements of unknown satellites or signals are skipped.
+    pub fn push(&mut self, raw: &RxmRawxRef<'_>) -> Result<(), DateTimeError> {
+        let time = GnssTime::try_from(raw)?;
+        let mut observations: BTreeMap<Satellite, BTreeMap<_, _>> = BTreeMap::new();
+        for meas in raw.measurements() {
+            let sat = satellite(meas.gnss_id(), meas.sv_id());
+            let code = signal_code(meas.gnss_id(), meas.sig_id());
+            let (Some(sat), Some(code)) = (sat, code) else {
+                continue;
+            };
+
+            let trk_stat = meas.trk_stat();
+            let lock_time = meas.lock_time();
+            let mut lli = 0;
+            let previous = self.lock.insert((sat, code), (time, lock_time));
+            let slip = match previous {
+                Some((previous, _)) => {
+                    let elapsed_ms = (time.nanos() - previous.nanos()) / 1_000_000;
+                    i64::from(lock_time) < elapsed_ms
+                },
+                None => lock_time == 0,
+            };
+            if slip {
+                lli |= LLI_SLIP;
+            }
+            if trk_stat.contains(TrkStatFlags::CP_VALID)
+                && !trk_stat.contains(TrkStatFlags::HALF_CYCLE)
+            {
+                lli |= LLI_HALF_CYCLE;
+            }
+
+            if sat.0 == 'R' && meas.freq_id() <= 13 {
+                self.glonass_slots.insert(sat.1, meas.freq_id() as i8 - 7);
+            }
+            self.obs_types.entry(sat.0).or_default().insert(code);
+            observations.entry(sat).or_default().insert(
+                code,
+                Observation {
+                    pseudorange: trk_stat
+                        .contains(TrkStatFlags::PR_VALID)
+                        .then(|| meas.pr_mes()),
+                    carrier_phase: trk_stat
+                        .contains(TrkStatFlags::CP_VALID)
+                        .then(|| meas.cp_mes()),
+                    doppler: f64::from(meas.do_mes()),
+                    cno: meas.cno(),
+                    lli,
+                },
+            );
+        }
+        self.epochs.push(Epoch { time, observations });
+        Ok(())
+    }
+
+    /// Writes header and all epochs
+    pub fn write<W: Write>(&self, out: &mut W) -> fmt::Result {
+        self.write_header(out)?;
+        for epoch in &self.epochs {
+            self.write_epoch(out, epoch)?;
+        }
+        Ok(())
+    }
+
+    fn write_header<W: Write>(&self, out: &mut W) -> fmt::Result {
+        let header = &self.header;
+        let first = self
+            .epochs
+            .first()
+            .map(|epoch| epoch.time.to_naive_datetime());
+
+        header_line(
+            out,
+            format_args!("{:9.2}{:11}{:<20}{:<20}", 3.04, "", "OBSERVATION DATA", "M"),
+            "RINEX VERSION / TYPE",
+        )?;
+        let date = match header.date.or(first) {
+            Some(date) => format!(
+                "{:04}{:02}{:02} {:02}{:02}{:02} UTC",
+                date.year(),
+                date.month(),
+                date.day(),
+                date.hour(),
+                date.minute(),
+                date.second()
+            ),
+            None => String::new(),
+        };
+        header_line(
+            out,
+            format_args!("{:<20}{:<20}{:<20}", "ublox", header.agency, date),
+            "PGM / RUN BY / DATE",
+        )?;
+        header_line(out, format_args!("{}", header.marker_name), "MARKER NAME")?;
+        header_line(
+            out,
+            format_args!("{:<20}{:<40}", header.observer, header.agency),
+            "OBSERVER / AGENCY",
+        )?;
+        header_line(
+            out,
+            format_args!(
+                "{:<20}{:<20}{:<20}",
+                header.receiver_number, header.receiver_type, header.receiver_version
+            ),
+            "REC # / TYPE / VERS",
+        )?;
+        header_line(
+            out,
+            format_args!("{:<20}{:<20}", header.antenna_number, header.antenna_type),
+            "ANT # / TYPE",
+        )?;
+        let position = header.approx_position.unwrap_or(PositionECEF {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        });
+        header_line(
+            out,
+            format_args!("{:14.4}{:14.4}{:14.4}", position.x, position.y, position.z),
+            "APPROX POSITION XYZ",
+        )?;
+        let [h, e, n] = header.antenna_delta;
+        header_line(
+            out,
+            format_args!("{:14.4}{:14.4}{:14.4}", h, e, n),
+            "ANTENNA: DELTA H/E/N",
+        )?;
+
+        for (system, codes) in &self.obs_types {
+            let types: Vec<String> = codes
+                .iter()
+                .flat_map(|code| {
+                    OBS_KINDS
+                        .iter()
+                        .map(move |kind| format!("{}{}", kind, code))
+                })
+                .collect();
+            for (i, chunk) in types.chunks(13).enumerate() {
+                let mut line = if i == 0 {
+                    format!("{}  {:3}", system, types.len())
+                } else {
+                    String::from("      ")
+                };
+                for obs_type in chunk {
+                    line.push(' ');
+                    line.push_str(obs_type);
+                }
+                header_line(out, format_args!("{}", line), "SYS / # / OBS TYPES")?;
+            }
+        }
+        header_line(out, format_args!("DBHZ"), "SIGNAL STRENGTH UNIT")?;
+
+        if let Some(first) = first {
+            let seconds = f64::from(first.second()) + f64::from(first.nanosecond()) * 1e-9;
+            header_line(
+                out,
+                format_args!(
+                    "{:6}{:6}{:6}{:6}{:6}{:13.7}{:5}{:<3}",
+                    first.year(),
+                    first.month(),
+                    first.day(),
+                    first.hour(),
+                    first.minute(),
+                    seconds,
+                    "",
+                    "GPS"
+                ),
+                "TIME OF FIRST OBS",
+            )?;
+        }
+
+        for system in self.obs_types.keys() {
+            header_line(out, format_args!("{}", system), "SYS / PHASE SHIFT")?;
+        }
+
+        if self.obs_types.contains_key(&'R') {
+            let slots: Vec<_> = self.glonass_slots.iter().collect();
+            if slots.is_empty() {
+                header_line(out, format_args!("{:3}", 0), "GLONASS SLOT / FRQ #")?;
+            }
+            for (i, chunk) in slots.chunks(8).enumerate() {
+                let mut line = if i == 0 {
+                    format!("{:3}", slots.len())
+                } else {
+                    String::from("   ")
+                };
+                for (slot, frequency) in chunk {
+                    write!(line, " R{:02} {:2}", slot, frequency)?;
+                }
+                header_line(out, format_args!("{}", line), "GLONASS SLOT / FRQ #")?;
+            }
+            // Code-phase biases are unknown
+            header_line(
+                out,
+                format_args!(" C1C {0:8} C1P {0:8} C2C {0:8} C2P {0:8}", ""),
+                "GLONASS COD/PHS/BIS",
+            )?;
+        }
+
+        header_line(out, format_args!(""), "END OF HEADER")
+    }
+
+    fn write_epoch<W: Write>(&self, out: &mut W, epoch: &Epoch) -> fmt::Result {
+        let time = epoch.time.to_naive_datetime();
+        let seconds = f64::from(time.second()) + f64::from(time.nanosecond()) * 1e-9;
+        writeln!(
+            out,
+            "> {:04} {:02} {:02} {:02} {:02}{:11.7}  0{:3}",
+            time.year(),
+            time.month(),
+            time.day(),
+            time.hour(),
+            time.minute(),
+            seconds,
+            epoch.observations.len()
+        )?;
+
+        for ((system, prn), signals) in &epoch.observations {
+            let mut line = format!("{}{:02}", system, prn);
+            for code in &self.obs_types[system] {
+                let Some(obs) = signals.get(code) else {
+                    line.push_str(&format!("{:64}", ""));
+                    continue;
+                };
+                let ssi = (obs.cno / 6).clamp(1, 9);
+                write_value(&mut line, obs.pseudorange, 0, ssi)?;
+                write_value(&mut line, obs.carrier_phase, obs.lli, ssi)?;
+                write_value(&mut line, Some(obs.doppler), 0, ssi)?;
+                write_value(&mut line, Some(f64::from(obs.cno)), 0, ssi)?;
+            }
+            writeln!(out, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// One observation value in F14.3 with loss of lock and signal strength indicators.
+/// A missing value keeps its loss of lock indicator.
+fn write_value(line: &mut String, value: Option<f64>, lli: u8, ssi: u8) -> fmt::Result {
+    match value {
+        Some(value) if lli != 0 => write!(line, "{:14.3}{}{}", value, lli, ssi),
+        Some(value) => write!(line, "{:14.3} {}", value, ssi),
+        None if lli != 0 => write!(line, "{:14}{} ", "", lli),
+        None => write!(line, "{:16}", ""),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PacketRef, Parser, UbxUnknownPacketRef};
+
+    fn measurement(sv_id: u8, lock_time: u16) -> [u8; 32] {
+        let mut meas = [0u8; 32];
+        meas[0..8].copy_from_slice(&21_000_000.123f64.to_le_bytes());
+        meas[8..16].copy_from_slice(&110_000_000.5f64.to_le_bytes());
+        meas[16..20].copy_from_slice(&(-1200.5f32).to_le_bytes());
+        meas[21] = sv_id;
+        meas[24..26].copy_from_slice(&lock_time.to_le_bytes());
+        meas[26] = 45;
+        meas[30] = 0x07;
+        meas
+    }
+
+    fn rawx(tow: f64, meas: &[[u8; 32]]) -> Vec<u8> {
+        let mut payload = Vec::new();
+        payload.extend_from_slice(&tow.to_le_bytes());
+        payload.extend_from_slice(&2000u16.to_le_bytes());
+        payload.extend_from_slice(&[18, meas.len() as u8, 0x01, 0x01, 0, 0]);
+        for meas in meas {
+            payload.extend_from_slice(meas);
+        }
+        UbxUnknownPacketRef {
+            payload: &payload,
+            class: 0x02,
+            msg_id: 0x15,
+        }
+        .into_packet_vec()
+    }
+
+    fn push(writer: &mut ObsWriter, bytes: &[u8]) {
+        let mut parser = Parser::default();
+        let mut it = parser.consume(bytes);
+        match it.next() {
+            Some(Ok(PacketRef::RxmRawx(raw))) => writer.push(&raw).unwrap(),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn writes_header_and_epochs() {
+        let mut writer = ObsWriter::new(ObsHeader {
+            marker_name: String::from("TEST"),
+            ..Default::default()
+        });
+        push(
+            &mut writer,
+            &rawx(1.0, &[measurement(5, 10_000), measurement(12, 10_000)]),
+        );
+        // SV 12 re-acquired: lock time shorter than the epoch interval
+        push(
+            &mut writer,
+            &rawx(2.0, &[measurement(5, 11_000), measurement(12, 500)]),
+        );
+        assert_eq!(writer.epochs(), 2);
+
+        let mut out = String::new();
+        writer.write(&mut out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            "     3.04           OBSERVATION DATA    M                   RINEX VERSION / TYPE"
+        );
+        assert!(lines.iter().all(|line| line.len() <= 80));
+        assert!(lines.contains(
+            &"G    4 C1C L1C D1C S1C                                      SYS / # / OBS TYPES"
+        ));
+        assert!(lines.contains(
+            &"  2018     5     6     0     0    1.0000000     GPS         TIME OF FIRST OBS"
+        ));
+
+        let end = lines
+            .iter()
+            .position(|line| line.ends_with("END OF HEADER"));
+        let body = &lines[end.unwrap() + 1..];
+        assert_eq!(body[0], "> 2018 05 06 00 00  1.0000000  0  2");
+        assert_eq!(
+            body[1],
+            "G05  21000000.123 7 110000000.500 7     -1200.500 7        45.000 7"
+        );
+        assert_eq!(body[3], "> 2018 05 06 00 00  2.0000000  0  2");
+        assert_eq!(
+            body[4],
+            "G05  21000000.123 7 110000000.500 7     -1200.500 7        45.000 7"
+        );
+        assert_eq!(
+            body[5],
+            "G12  21000000.123 7 110000000.50017     -1200.500 7        45.000 7"
+        );
+    }
+
+    #[test]
+    fn keeps_loss_of_lock_without_carrier_phase() {
+        let mut writer = ObsWriter::new(ObsHeader::default());
+        push(&mut writer, &rawx(1.0, &[measurement(5, 10_000)]));
+        // Re-acquired, carrier phase not yet valid
+        let mut meas = measurement(5, 500);
+        meas[30] = 0x01;
+        push(&mut writer, &rawx(2.0, &[meas]));
+
+        let mut out = String::new();
+        writer.write(&mut out).unwrap();
+        assert_eq!(
+            out.lines().last(),
+            Some("G05  21000000.123 7              1      -1200.500 7        45.000 7")
+        );
+    }
+}
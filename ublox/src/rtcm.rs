@@ -0,0 +1,164 @@
+//! RTCM 3.3 differential correction messages.
+//!
+//! [MsmEncoder] turns RXM-RAWX epochs into Multiple Signal Messages,
+//! [station_arp] and [glonass_biases] generate the station messages 1005 and
+//! 1230. All messages are returned framed with the RTCM transport layer
+//! (preamble, length and CRC-24Q), ready to be sent to a rover.
+//!
+//! In the other direction [RtcmParser] splits a correction stream into frames,
+//! [RtcmMessage] decodes the station, MSM header, antenna and bias messages and
+//! [CorrectionMonitor] follows which constellations a base station sends and
+//! what the receiver does with them.
+
+use alloc::vec::Vec;
+
+use crate::{error::RtcmError, navmsg::crc24q};
+
+mod decode;
+mod monitor;
+mod msm;
+mod station;
+
+pub use decode::{AntennaDescriptor, MsmHeader, RtcmFrame, RtcmMessage, RtcmParser, StationArp};
+pub use monitor::{CorrectionEvent, CorrectionMonitor, MessageStats};
+pub use msm::{MsmEncoder, MsmType};
+pub use station::{glonass_biases, station_arp, GlonassBiases};
+
+/// First byte of every RTCM 3 frame
+pub const PREAMBLE: u8 = 0xd3;
+
+/// Largest message length the transport layer can carry
+pub const MAX_MESSAGE_LEN: usize = 1023;
+
+/// Speed of light in m/s
+const SPEED_OF_LIGHT: f64 = 299_792_458.0;
+/// Distance light travels in one millisecond
+const RANGE_MS: f64 = SPEED_OF_LIGHT * 1e-3;
+
+/// Frames a message with preamble, length and CRC-24Q.
+///
+/// # Panics
+/// If the message is longer than [MAX_MESSAGE_LEN]
+pub fn frame(message: &[u8]) -> Vec<u8> {
+    assert!(message.len() <= MAX_MESSAGE_LEN, "RTCM message too long");
+    let mut frame = Vec::with_capacity(message.len() + 6);
+    frame.push(PREAMBLE);
+    frame.extend((message.len() as u16).to_be_bytes());
+    frame.extend(message);
+    let crc = crc24q(&frame);
+    frame.extend(&crc.to_be_bytes()[1..]);
+    frame
+}
+
+/// Appends big endian bit fields to a message
+#[derive(Debug, Default)]
+struct BitWriter {
+    buf: Vec<u8>,
+    len: usize,
+}
+
+impl BitWriter {
+    /// Appends the `len` low bits of `value`
+    fn put(&mut self, value: u64, len: usize) {
+        for i in (0..len).rev() {
+            if self.len % 8 == 0 {
+                self.buf.push(0);
+            }
+            if (value >> i) & 1 == 1 {
+                *self.buf.last_mut().unwrap() |= 0x80 >> (self.len % 8);
+            }
+            self.len += 1;
+        }
+    }
+
+    /// Appends `value` in two's complement
+    fn put_signed(&mut self, value: i64, len: usize) {
+        self.put(value as u64, len);
+    }
+
+    fn into_frame(self) -> Vec<u8> {
+        frame(&self.buf)
+    }
+}
+
+/// Reads big endian bit fields from a message
+#[derive(Debug)]
+struct BitReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    /// Reads `len` bits as an unsigned value
+    fn get(&mut self, len: usize) -> Result<u64, RtcmError> {
+        if self.pos + len > self.buf.len() * 8 {
+            return Err(RtcmError::InvalidLength);
+        }
+        let value = (self.pos..self.pos + len).fold(0, |value, i| {
+            (value << 1) | u64::from((self.buf[i / 8] >> (7 - i % 8)) & 1)
+        });
+        self.pos += len;
+        Ok(value)
+    }
+
+    /// Reads `len` bits in two's complement
+    fn get_signed(&mut self, len: usize) -> Result<i64, RtcmError> {
+        let value = self.get(len)? as i64;
+        Ok((value << (64 - len)) >> (64 - len))
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), RtcmError> {
+        self.get(len).map(|_| ())
+    }
+}
+
+/// Wavelength in meters of a signal given by its RINEX system identifier and
+/// observation code, GLONASS FDMA signals at frequency channel `channel`
+fn wavelength(system: char, code: &str, channel: i8) -> Option<f64> {
+    let band = code.chars().next()?;
+    let frequency = match (system, band) {
+        ('G' | 'E' | 'J' | 'C', '1') => 1575.42e6,
+        ('C', '2') => 1561.098e6,
+        ('G' | 'J', '2') => 1227.60e6,
+        ('G' | 'E' | 'J' | 'C' | 'I', '5') => 1176.45e6,
+        ('E' | 'C', '7') => 1207.14e6,
+        ('E', '6') => 1278.75e6,
+        ('R', '1') => 1602.0e6 + f64::from(channel) * 0.5625e6,
+        ('R', '2') => 1246.0e6 + f64::from(channel) * 0.4375e6,
+        _ => return None,
+    };
+    Some(SPEED_OF_LIGHT / frequency)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn frames_with_crc() {
+        // The CRC over a frame including its own CRC is zero
+        let frame = frame(&[0x3e, 0xd0, 0x00]);
+        assert_eq!(&frame[..6], &[0xd3, 0x00, 0x03, 0x3e, 0xd0, 0x00]);
+        assert_eq!(crc24q(&frame), 0);
+    }
+
+    #[test]
+    fn writes_bit_fields() {
+        let mut writer = BitWriter::default();
+        writer.put(1005, 12);
+        writer.put_signed(-1, 4);
+        writer.put(1, 1);
+        assert_eq!(writer.buf, [0x3e, 0xdf, 0x80]);
+        assert_eq!(writer.len, 17);
+
+        let mut reader = BitReader::new(&writer.buf);
+        assert_eq!(reader.get(12), Ok(1005));
+        assert_eq!(reader.get_signed(4), Ok(-1));
+        assert_eq!(reader.get(1), Ok(1));
+        assert_eq!(reader.get(8), Err(RtcmError::InvalidLength));
+    }
+}
@@ -0,0 +1,426 @@
+//! Framing and decoding of received messages
+
+use alloc::{string::String, vec::Vec};
+
+use super::{BitReader, GlonassBiases, PREAMBLE};
+use crate::{error::RtcmError, navmsg::crc24q, PositionECEF};
+
+/// Splits a byte stream into RTCM 3 frames
+#[derive(Debug, Default)]
+pub struct RtcmParser {
+    buf: Vec<u8>,
+}
+
+impl RtcmParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `data` to the stream and returns the complete frames found.
+    ///
+    /// Bytes outside of frames are skipped. A frame failing the CRC check is
+    /// reported as [RtcmError::InvalidCrc] and the search continues after its preamble.
+    pub fn consume(&mut self, data: &[u8]) -> Vec<Result<RtcmFrame, RtcmError>> {
+        self.buf.extend_from_slice(data);
+        let mut frames = Vec::new();
+        let mut start = 0;
+        loop {
+            match self.buf[start..].iter().position(|b| *b == PREAMBLE) {
+                Some(offset) => start += offset,
+                None => {
+                    start = self.buf.len();
+                    break;
+                },
+            }
+            let Some(header) = self.buf.get(start + 1..start + 3) else {
+                break;
+            };
+            // The upper six bits of the length field are reserved and zero
+            if header[0] & 0xfc != 0 {
+                start += 1;
+                continue;
+            }
+            let len = (usize::from(header[0]) << 8 | usize::from(header[1])) + 6;
+            let Some(frame) = self.buf.get(start..start + len) else {
+                break;
+            };
+            if crc24q(frame) == 0 {
+                frames.push(Ok(RtcmFrame(frame.to_vec())));
+                start += len;
+            } else {
+                frames.push(Err(RtcmError::InvalidCrc));
+                start += 1;
+            }
+        }
+        self.buf.drain(..start);
+        frames
+    }
+}
+
+/// A received frame with valid CRC
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtcmFrame(Vec<u8>);
+
+impl RtcmFrame {
+    /// The complete frame, e.g. to forward it to a receiver
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// The message without preamble, length and CRC
+    pub fn message(&self) -> &[u8] {
+        &self.0[3..self.0.len() - 3]
+    }
+
+    /// Message number, zero for an empty message
+    pub fn message_type(&self) -> u16 {
+        let message = self.message();
+        match message {
+            [first, second, ..] => u16::from(*first) << 4 | u16::from(*second) >> 4,
+            _ => 0,
+        }
+    }
+
+    pub fn decode(&self) -> Result<RtcmMessage, RtcmError> {
+        RtcmMessage::decode(self.message())
+    }
+}
+
+/// Decoded message
+#[derive(Debug, Clone, PartialEq)]
+pub enum RtcmMessage {
+    /// 1005 and 1006
+    StationArp(StationArp),
+    /// Header of a Multiple Signal Message, 1071 to 1137
+    Msm(MsmHeader),
+    /// 1033
+    AntennaDescriptor(AntennaDescriptor),
+    /// 1230 with its reference station
+    GlonassBiases(u16, GlonassBiases),
+    /// Any other message, by number
+    Other(u16),
+}
+
+impl RtcmMessage {
+    /// Decodes a message without its framing
+    pub fn decode(message: &[u8]) -> Result<Self, RtcmError> {
+        let mut r = BitReader::new(message);
+        let message_type = r.get(12)? as u16;
+        match message_type {
+            1005 | 1006 => StationArp::decode(message_type, &mut r).map(Self::StationArp),
+            1071..=1137 if (1..=7).contains(&(message_type % 10)) => {
+                MsmHeader::decode(message_type, &mut r).map(Self::Msm)
+            },
+            1033 => AntennaDescriptor::decode(&mut r).map(Self::AntennaDescriptor),
+            1230 => {
+                let station_id = r.get(12)? as u16;
+                let aligned = r.get(1)? == 1;
+                r.skip(3)?;
+                let mask = r.get(4)?;
+                let mut bias = |bit: u64| -> Result<Option<f64>, RtcmError> {
+                    if mask & bit == 0 {
+                        return Ok(None);
+                    }
+                    Ok(Some(r.get_signed(16)? as f64 * 0.02))
+                };
+                let biases = GlonassBiases {
+                    aligned,
+                    l1_ca: bias(0b1000)?,
+                    l1_p: bias(0b0100)?,
+                    l2_ca: bias(0b0010)?,
+                    l2_p: bias(0b0001)?,
+                };
+                Ok(Self::GlonassBiases(station_id, biases))
+            },
+            _ => Ok(Self::Other(message_type)),
+        }
+    }
+
+    pub fn message_type(&self) -> u16 {
+        match self {
+            RtcmMessage::StationArp(arp) if arp.antenna_height.is_some() => 1006,
+            RtcmMessage::StationArp(_) => 1005,
+            RtcmMessage::Msm(header) => header.message_type,
+            RtcmMessage::AntennaDescriptor(_) => 1033,
+            RtcmMessage::GlonassBiases(..) => 1230,
+            RtcmMessage::Other(message_type) => *message_type,
+        }
+    }
+}
+
+/// Antenna reference point of a reference station
+#[derive(Debug, Clone, PartialEq)]
+pub struct StationArp {
+    pub station_id: u16,
+    /// ITRF realization year, 0 if not given
+    pub itrf_year: u8,
+    pub gps: bool,
+    pub glonass: bool,
+    pub galileo: bool,
+    /// The station is a physical one, not a virtual reference station
+    pub physical: bool,
+    /// ECEF coordinates in meters
+    pub position: PositionECEF,
+    /// Antenna height above the marker in meters, message 1006 only
+    pub antenna_height: Option<f64>,
+}
+
+impl StationArp {
+    fn decode(message_type: u16, r: &mut BitReader<'_>) -> Result<Self, RtcmError> {
+        let station_id = r.get(12)? as u16;
+        let itrf_year = r.get(6)? as u8;
+        let gps = r.get(1)? == 1;
+        let glonass = r.get(1)? == 1;
+        let galileo = r.get(1)? == 1;
+        let physical = r.get(1)? == 0;
+        let x = r.get_signed(38)?;
+        r.skip(2)?;
+        let y = r.get_signed(38)?;
+        r.skip(2)?;
+        let z = r.get_signed(38)?;
+        let antenna_height = match message_type {
+            1006 => Some(r.get(16)? as f64 * 1e-4),
+            _ => None,
+        };
+        Ok(Self {
+            station_id,
+            itrf_year,
+            gps,
+            glonass,
+            galileo,
+            physical,
+            position: PositionECEF {
+                x: x as f64 * 1e-4,
+                y: y as f64 * 1e-4,
+                z: z as f64 * 1e-4,
+            },
+            antenna_height,
+        })
+    }
+}
+
+/// Header of a Multiple Signal Message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MsmHeader {
+    pub message_type: u16,
+    /// RINEX system identifier: G, R, E, S, J, C or I
+    pub system: char,
+    pub station_id: u16,
+    /// Epoch time as transmitted: time of week in ms, for GLONASS the day of
+    /// week in the upper 3 bits and the time of day in ms
+    pub epoch: u32,
+    /// More messages follow for this epoch and station
+    pub multiple_message: bool,
+    /// Issue of data station
+    pub iods: u8,
+    /// Satellites, numbered from 1
+    pub satellites: Vec<u8>,
+    /// MSM signal identifiers, numbered from 1
+    pub signals: Vec<u8>,
+    /// Satellite and signal of each cell with data
+    pub cells: Vec<(u8, u8)>,
+}
+
+impl MsmHeader {
+    fn decode(message_type: u16, r: &mut BitReader<'_>) -> Result<Self, RtcmError> {
+        let system = match message_type / 10 {
+            107 => 'G',
+            108 => 'R',
+            109 => 'E',
+            110 => 'S',
+            111 => 'J',
+            112 => 'C',
+            _ => 'I',
+        };
+        let station_id = r.get(12)? as u16;
+        let epoch = r.get(30)? as u32;
+        let multiple_message = r.get(1)? == 1;
+        let iods = r.get(3)? as u8;
+        // Reserved, clock steering, external clock, smoothing indicator and interval
+        r.skip(7 + 2 + 2 + 1 + 3)?;
+        let sat_mask = r.get(64)?;
+        let satellites: Vec<u8> = (1..=64).filter(|i| sat_mask >> (64 - i) & 1 == 1).collect();
+        let sig_mask = r.get(32)?;
+        let signals: Vec<u8> = (1..=32).filter(|i| sig_mask >> (32 - i) & 1 == 1).collect();
+        let mut cells = Vec::new();
+        for sat in &satellites {
+            for sig in &signals {
+                if r.get(1)? == 1 {
+                    cells.push((*sat, *sig));
+                }
+            }
+        }
+        Ok(Self {
+            message_type,
+            system,
+            station_id,
+            epoch,
+            multiple_message,
+            iods,
+            satellites,
+            signals,
+            cells,
+        })
+    }
+
+    /// MSM type, 1 to 7
+    pub fn msm(&self) -> u8 {
+        (self.message_type % 10) as u8
+    }
+}
+
+/// Antenna and receiver descriptor
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AntennaDescriptor {
+    pub station_id: u16,
+    pub antenna: String,
+    pub setup_id: u8,
+    pub antenna_serial: String,
+    pub receiver: String,
+    pub firmware: String,
+    pub receiver_serial: String,
+}
+
+impl AntennaDescriptor {
+    fn decode(r: &mut BitReader<'_>) -> Result<Self, RtcmError> {
+        fn string(r: &mut BitReader<'_>) -> Result<String, RtcmError> {
+            let len = r.get(8)?;
+            let bytes = (0..len)
+                .map(|_| r.get(8).map(|c| c as u8))
+                .collect::<Result<Vec<u8>, _>>()?;
+            Ok(String::from_utf8_lossy(&bytes).into_owned())
+        }
+        let station_id = r.get(12)? as u16;
+        let antenna = string(r)?;
+        let setup_id = r.get(8)? as u8;
+        Ok(Self {
+            station_id,
+            antenna,
+            setup_id,
+            antenna_serial: string(r)?,
+            receiver: string(r)?,
+            firmware: string(r)?,
+            receiver_serial: string(r)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rtcm::{frame, glonass_biases, station_arp, BitWriter};
+
+    #[test]
+    fn parses_split_frames() {
+        let arp = station_arp(
+            7,
+            &PositionECEF {
+                x: -2694685.473,
+                y: -4293642.366,
+                z: 3857878.924,
+            },
+        );
+        let mut corrupted = frame(&[0x3e, 0xd0, 0x00]);
+        corrupted[4] ^= 0x01;
+
+        let mut stream = alloc::vec![0x00, PREAMBLE, 0x12];
+        stream.extend(&corrupted);
+        stream.extend(&arp);
+        let mut parser = RtcmParser::new();
+        let (first, second) = stream.split_at(10);
+        let mut frames = parser.consume(first);
+        assert!(frames.is_empty());
+        frames = parser.consume(second);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0], Err(RtcmError::InvalidCrc));
+
+        let frame = frames[1].as_ref().unwrap();
+        assert_eq!(frame.as_bytes(), arp.as_slice());
+        assert_eq!(frame.message_type(), 1005);
+        match frame.decode().unwrap() {
+            RtcmMessage::StationArp(arp) => {
+                assert_eq!(arp.station_id, 7);
+                assert!(arp.gps && arp.glonass && arp.galileo && arp.physical);
+                assert!((arp.position.x + 2694685.473).abs() < 1e-4);
+                assert!((arp.position.z - 3857878.924).abs() < 1e-4);
+                assert_eq!(arp.antenna_height, None);
+            },
+            message => panic!("unexpected {message:?}"),
+        }
+    }
+
+    #[test]
+    fn decodes_glonass_biases() {
+        let biases = GlonassBiases {
+            aligned: false,
+            l1_ca: Some(1.5),
+            l2_ca: Some(-2.0),
+            ..Default::default()
+        };
+        let frame = glonass_biases(12, &biases);
+        let message = RtcmMessage::decode(&frame[3..frame.len() - 3]).unwrap();
+        assert_eq!(message, RtcmMessage::GlonassBiases(12, biases));
+        assert_eq!(message.message_type(), 1230);
+    }
+
+    #[test]
+    fn decodes_antenna_descriptor() {
+        let mut w = BitWriter::default();
+        w.put(1033, 12);
+        w.put(3, 12);
+        for (i, text) in ["TRM59800.00 NONE", "", "SEPT POLARX5", "5.4.0", "3001"]
+            .iter()
+            .enumerate()
+        {
+            w.put(text.len() as u64, 8);
+            text.bytes().for_each(|c| w.put(c.into(), 8));
+            if i == 0 {
+                w.put(1, 8);
+            }
+        }
+        let message = RtcmMessage::decode(&w.buf).unwrap();
+        assert_eq!(
+            message,
+            RtcmMessage::AntennaDescriptor(AntennaDescriptor {
+                station_id: 3,
+                antenna: "TRM59800.00 NONE".into(),
+                setup_id: 1,
+                antenna_serial: "".into(),
+                receiver: "SEPT POLARX5".into(),
+                firmware: "5.4.0".into(),
+                receiver_serial: "3001".into(),
+            })
+        );
+        // Truncated in the receiver type
+        assert_eq!(
+            RtcmMessage::decode(&w.buf[..25]),
+            Err(RtcmError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn decodes_msm_header() {
+        let mut w = BitWriter::default();
+        w.put(1127, 12);
+        w.put(99, 12);
+        w.put(345_600_000, 30);
+        w.put(1, 1);
+        w.put(0, 18);
+        w.put((1 << 63) | (1 << 34), 64);
+        w.put((1 << 30) | (1 << 18), 32);
+        w.put(0b1101, 4);
+        match RtcmMessage::decode(&w.buf).unwrap() {
+            RtcmMessage::Msm(header) => {
+                assert_eq!(header.system, 'C');
+                assert_eq!(header.msm(), 7);
+                assert_eq!(header.station_id, 99);
+                assert_eq!(header.epoch, 345_600_000);
+                assert!(header.multiple_message);
+                assert_eq!(header.satellites, [1, 30]);
+                assert_eq!(header.signals, [2, 14]);
+                assert_eq!(header.cells, [(1, 2), (1, 14), (30, 14)]);
+            },
+            message => panic!("unexpected {message:?}"),
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Monitoring of a correction stream
+
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::time::Duration;
+
+use super::{RtcmMessage, StationArp};
+use crate::ubx_packets::{RxmRtcmFlags, RxmRtcmRef};
+
+/// Counters of one message type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MessageStats {
+    /// Messages seen in the correction stream
+    pub sent: u32,
+    /// Messages reported by the receiver with RXM-RTCM
+    pub received: u32,
+    /// Received messages the receiver used in its solution
+    pub used: u32,
+    /// Received messages the receiver did not use
+    pub not_used: u32,
+    /// Received messages that failed the receiver's CRC check
+    pub crc_failed: u32,
+    /// Reference station of the latest RXM-RTCM report
+    pub ref_station: Option<u16>,
+}
+
+/// Change of the correction stream reported by [CorrectionMonitor]
+#[derive(Debug, Clone, PartialEq)]
+pub enum CorrectionEvent {
+    /// No MSM of the system (RINEX identifier) arrived within the timeout
+    ConstellationLost(char),
+    /// MSM of a lost system arrive again
+    ConstellationResumed(char),
+    /// The station message differs from the previous one
+    StationChanged(StationArp),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SystemState {
+    last_seen: Duration,
+    lost: bool,
+}
+
+/// Follows the messages of a correction stream and the receiver's
+/// RXM-RTCM reports about them.
+///
+/// Times are given by the caller as the elapsed time since any fixed instant.
+#[derive(Debug, Clone)]
+pub struct CorrectionMonitor {
+    timeout: Duration,
+    systems: BTreeMap<char, SystemState>,
+    stats: BTreeMap<u16, MessageStats>,
+    station: Option<StationArp>,
+}
+
+impl CorrectionMonitor {
+    /// A system is considered lost when none of its MSM arrived for `timeout`
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            systems: BTreeMap::new(),
+            stats: BTreeMap::new(),
+            station: None,
+        }
+    }
+
+    /// Records a message of the correction stream received at `now`
+    pub fn on_message(&mut self, message: &RtcmMessage, now: Duration) -> Vec<CorrectionEvent> {
+        let mut events = Vec::new();
+        self.stats.entry(message.message_type()).or_default().sent += 1;
+        match message {
+            RtcmMessage::Msm(header) => {
+                let state = self.systems.entry(header.system).or_insert(SystemState {
+                    last_seen: now,
+                    lost: false,
+                });
+                state.last_seen = now;
+                if state.lost {
+                    state.lost = false;
+                    events.push(CorrectionEvent::ConstellationResumed(header.system));
+                }
+            },
+            RtcmMessage::StationArp(arp) => {
+                if self.station.as_ref().is_some_and(|station| station != arp) {
+                    events.push(CorrectionEvent::StationChanged(arp.clone()));
+                }
+                self.station = Some(arp.clone());
+            },
+            _ => {},
+        }
+        events
+    }
+
+    /// Records the receiver's status of an RTCM message it received
+    pub fn on_receiver_status(&mut self, status: &RxmRtcmRef<'_>) {
+        let flags = status.flags();
+        let stats = self.stats.entry(status.msg_type()).or_default();
+        stats.received += 1;
+        stats.ref_station = Some(status.ref_station());
+        if flags.contains(RxmRtcmFlags::CRC_FAILED) {
+            stats.crc_failed += 1;
+        }
+        if flags.contains(RxmRtcmFlags::USED) {
+            stats.used += 1;
+        }
+        if flags.contains(RxmRtcmFlags::NOT_USED) {
+            stats.not_used += 1;
+        }
+    }
+
+    /// Reports the systems whose MSM stopped arriving since the last check
+    pub fn check(&mut self, now: Duration) -> Vec<CorrectionEvent> {
+        let timeout = self.timeout;
+        self.systems
+            .iter_mut()
+            .filter(|(_, state)| !state.lost && now.saturating_sub(state.last_seen) > timeout)
+            .map(|(system, state)| {
+                state.lost = true;
+                CorrectionEvent::ConstellationLost(*system)
+            })
+            .collect()
+    }
+
+    /// Systems with MSM received within the timeout
+    pub fn active_systems(&self) -> impl Iterator<Item = char> + '_ {
+        self.systems
+            .iter()
+            .filter(|(_, state)| !state.lost)
+            .map(|(system, _)| *system)
+    }
+
+    /// Counters by message type
+    pub fn stats(&self) -> impl Iterator<Item = (u16, &MessageStats)> {
+        self.stats
+            .iter()
+            .map(|(message_type, stats)| (*message_type, stats))
+    }
+
+    /// The latest station message
+    pub fn station(&self) -> Option<&StationArp> {
+        self.station.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        rtcm::{MsmHeader, RtcmMessage},
+        PacketRef, Parser, UbxUnknownPacketRef,
+    };
+
+    fn msm(system: char) -> RtcmMessage {
+        RtcmMessage::Msm(MsmHeader {
+            message_type: 1077,
+            system,
+            station_id: 0,
+            epoch: 0,
+            multiple_message: false,
+            iods: 0,
+            satellites: Vec::new(),
+            signals: Vec::new(),
+            cells: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn reports_lost_constellations() {
+        let mut monitor = CorrectionMonitor::new(Duration::from_secs(5));
+        let t = Duration::from_secs;
+        assert!(monitor.on_message(&msm('G'), t(0)).is_empty());
+        assert!(monitor.on_message(&msm('E'), t(0)).is_empty());
+        monitor.on_message(&msm('G'), t(4));
+        assert!(monitor.check(t(5)).is_empty());
+        assert_eq!(
+            monitor.check(t(6)),
+            [CorrectionEvent::ConstellationLost('E')]
+        );
+        assert!(monitor.check(t(7)).is_empty());
+        assert_eq!(monitor.active_systems().collect::<Vec<_>>(), ['G']);
+        assert_eq!(
+            monitor.on_message(&msm('E'), t(8)),
+            [CorrectionEvent::ConstellationResumed('E')]
+        );
+    }
+
+    #[test]
+    fn counts_receiver_status() {
+        let mut monitor = CorrectionMonitor::new(Duration::from_secs(5));
+        monitor.on_message(&msm('G'), Duration::ZERO);
+        let payload = [0x02, 0x04, 0x00, 0x00, 0x07, 0x00, 0x35, 0x04];
+        let bytes = UbxUnknownPacketRef {
+            payload: &payload,
+            class: 0x02,
+            msg_id: 0x32,
+        }
+        .into_packet_vec();
+        let mut parser = Parser::default();
+        let mut it = parser.consume(&bytes);
+        match it.next() {
+            Some(Ok(PacketRef::RxmRtcm(status))) => monitor.on_receiver_status(&status),
+            _ => panic!(),
+        }
+        let stats: Vec<_> = monitor.stats().collect();
+        assert_eq!(
+            stats,
+            [(
+                1077,
+                &MessageStats {
+                    sent: 1,
+                    received: 1,
+                    used: 1,
+                    ref_station: Some(7),
+                    ..Default::default()
+                }
+            )]
+        );
+    }
+}
@@ -0,0 +1,510 @@
+//! Multiple Signal Messages MSM4 and MSM7 (RTCM 10403.3, 3.5.12)
+
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::convert::TryFrom;
+
+use super::{wavelength, BitWriter, RANGE_MS};
+use crate::{
+    error::DateTimeError,
+    rinex::{satellite, signal_code},
+    ubx_packets::{GnssTime, LeapSeconds, RxmRawxRef, TimeScale, TrkStatFlags},
+};
+
+/// Maximum number of cells (satellite and signal combinations) of one message
+const MAX_CELLS: usize = 64;
+
+/// Largest phase - pseudorange difference that fits the fine phase range fields, in ms
+const MAX_PHASE_OFFSET_MS: f64 = 1.0 / 256.0;
+
+const P2_10: f64 = 1.0 / (1u64 << 10) as f64;
+const P2_24: f64 = 1.0 / (1u64 << 24) as f64;
+const P2_29: f64 = 1.0 / (1u64 << 29) as f64;
+const P2_31: f64 = 1.0 / (1u64 << 31) as f64;
+
+/// Systems with MSM support: RINEX system identifier and number of the MSM1 message
+const SYSTEMS: [(char, u16); 4] = [('G', 1071), ('R', 1081), ('E', 1091), ('C', 1121)];
+
+/// Resolution of the generated messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MsmType {
+    /// Full pseudoranges and phase ranges with CNR (1074, 1084, 1094, 1124)
+    Msm4,
+    /// Full pseudoranges, phase ranges, phase range rates and CNR in
+    /// high resolution (1077, 1087, 1097, 1127)
+    Msm7,
+}
+
+impl MsmType {
+    fn number(self) -> u16 {
+        match self {
+            MsmType::Msm4 => 4,
+            MsmType::Msm7 => 7,
+        }
+    }
+}
+
+/// MSM signal identifier of a signal given by its RINEX observation code
+fn signal_id(system: char, code: &str) -> Option<u8> {
+    let id = match (system, code) {
+        ('G', "1C") => 2,
+        ('G', "2S") => 15,
+        ('G', "2L") => 16,
+        ('G', "5I") => 22,
+        ('G', "5Q") => 23,
+        ('R', "1C") => 2,
+        ('R', "2C") => 8,
+        ('E', "1C") => 2,
+        ('E', "1B") => 4,
+        ('E', "6C") => 8,
+        ('E', "6A") => 9,
+        ('E', "6B") => 10,
+        ('E', "7I") => 14,
+        ('E', "7Q") => 15,
+        ('E', "5I") => 22,
+        ('E', "5Q") => 23,
+        ('C', "2I") => 2,
+        ('C', "7I") => 14,
+        ('C', "5P") => 23,
+        ('C', "1P") => 31,
+        _ => return None,
+    };
+    Some(id)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Cell {
+    /// Pseudorange in ms
+    pseudorange: Option<f64>,
+    /// Phase range in ms
+    phase: Option<f64>,
+    /// Phase range rate in m/s
+    rate: f64,
+    lock_ms: u32,
+    half_cycle: bool,
+    cno: u8,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Satellite {
+    /// GLONASS frequency channel + 7
+    info: u8,
+    /// Cells by MSM signal id
+    cells: BTreeMap<u8, Cell>,
+}
+
+/// Generates MSM4 or MSM7 messages from RXM-RAWX epochs.
+///
+/// u-blox carrier phases start with an arbitrary offset from the pseudorange.
+/// The encoder removes whole cycles so that the phase ranges fit the MSM fields,
+/// and reports a loss of lock whenever it has to change that correction.
+#[derive(Debug, Clone)]
+pub struct MsmEncoder {
+    station_id: u16,
+    msm: MsmType,
+    /// Whole cycles removed from the phase by (system, satellite, signal id)
+    phase_offsets: BTreeMap<(char, u8, u8), f64>,
+}
+
+impl MsmEncoder {
+    pub fn new(station_id: u16, msm: MsmType) -> Self {
+        Self {
+            station_id,
+            msm,
+            phase_offsets: BTreeMap::new(),
+        }
+    }
+
+    /// Encodes the measurements of one RXM-RAWX epoch into framed messages,
+    /// one or more per GPS, GLONASS, Galileo and BeiDou.
+    ///
+    /// Signals without an MSM signal identifier are skipped.
+    pub fn encode(&mut self, raw: &RxmRawxRef<'_>) -> Result<Vec<Vec<u8>>, DateTimeError> {
+        let time = GnssTime::try_from(raw)?;
+        let leap = LeapSeconds::new(raw.leap_s());
+
+        let mut systems: BTreeMap<char, BTreeMap<u8, Satellite>> = BTreeMap::new();
+        for meas in raw.measurements() {
+            let Some((system, sv)) = satellite(meas.gnss_id(), meas.sv_id()) else {
+                continue;
+            };
+            let Some(code) = signal_code(meas.gnss_id(), meas.sig_id()) else {
+                continue;
+            };
+            let Some(signal) = signal_id(system, code) else {
+                continue;
+            };
+            let channel = meas.freq_id() as i8 - 7;
+            let Some(lambda) = wavelength(system, code, channel) else {
+                continue;
+            };
+
+            let trk_stat = meas.trk_stat();
+            let pseudorange = trk_stat
+                .contains(TrkStatFlags::PR_VALID)
+                .then(|| meas.pr_mes());
+            let mut lock_ms = u32::from(meas.lock_time());
+            let mut phase = None;
+            if let (Some(pseudorange), true) =
+                (pseudorange, trk_stat.contains(TrkStatFlags::CP_VALID))
+            {
+                let offset = self.phase_offsets.entry((system, sv, signal)).or_default();
+                let mut range = (meas.cp_mes() - *offset) * lambda;
+                if lock_ms == 0 || libm::fabs(range - pseudorange) / RANGE_MS > MAX_PHASE_OFFSET_MS
+                {
+                    *offset = libm::round((meas.cp_mes() * lambda - pseudorange) / lambda);
+                    range = (meas.cp_mes() - *offset) * lambda;
+                    lock_ms = 0;
+                }
+                phase = Some(range / RANGE_MS);
+            }
+
+            let sat = systems.entry(system).or_default().entry(sv).or_default();
+            if system == 'R' && meas.freq_id() <= 13 {
+                sat.info = meas.freq_id();
+            }
+            sat.cells.insert(
+                signal,
+                Cell {
+                    pseudorange: pseudorange.map(|pr| pr / RANGE_MS),
+                    phase,
+                    rate: -f64::from(meas.do_mes()) * lambda,
+                    lock_ms,
+                    half_cycle: !trk_stat.contains(TrkStatFlags::HALF_CYCLE),
+                    cno: meas.cno(),
+                },
+            );
+        }
+
+        let mut messages = Vec::new();
+        for (system, msm1) in SYSTEMS {
+            let Some(sats) = systems.get(&system) else {
+                continue;
+            };
+            let epoch = epoch_time(&time, system, &leap);
+            let signals: Vec<u8> = {
+                let mut ids: Vec<u8> = sats
+                    .values()
+                    .flat_map(|s| s.cells.keys().copied())
+                    .collect();
+                ids.sort_unstable();
+                ids.dedup();
+                ids
+            };
+            let sats: Vec<(&u8, &Satellite)> = sats.iter().collect();
+            for chunk in sats.chunks(MAX_CELLS / signals.len()) {
+                let number = msm1 + self.msm.number() - 1;
+                messages.push(self.message(number, epoch, chunk, &signals));
+            }
+        }
+
+        // The multiple message bit is set on all but the last message of the epoch
+        let last = messages.len().saturating_sub(1);
+        Ok(messages
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut writer)| {
+                if i != last {
+                    // Bit 54: after message number, station id and epoch time
+                    writer.buf[6] |= 0x02;
+                }
+                writer.into_frame()
+            })
+            .collect())
+    }
+
+    fn message(
+        &self,
+        number: u16,
+        epoch: u32,
+        sats: &[(&u8, &Satellite)],
+        signals: &[u8],
+    ) -> BitWriter {
+        let mut w = BitWriter::default();
+        w.put(number.into(), 12);
+        w.put(self.station_id.into(), 12);
+        w.put(epoch.into(), 30);
+        // Multiple message bit, IODS, reserved, clock steering, external clock,
+        // divergence free smoothing and smoothing interval
+        w.put(0, 1 + 3 + 7 + 2 + 2 + 1 + 3);
+
+        let sat_mask = sats
+            .iter()
+            .fold(0u64, |mask, (sv, _)| mask | (1 << (64 - u32::from(**sv))));
+        w.put(sat_mask, 64);
+        let sig_mask = signals
+            .iter()
+            .fold(0u64, |mask, id| mask | (1 << (32 - u32::from(*id))));
+        w.put(sig_mask, 32);
+        for (_, sat) in sats {
+            for id in signals {
+                w.put(sat.cells.contains_key(id).into(), 1);
+            }
+        }
+
+        // Rough range and phase range rate of each satellite, from its first valid signal
+        let rough: Vec<Option<f64>> = sats
+            .iter()
+            .map(|(_, sat)| {
+                let pr = sat.cells.values().find_map(|cell| cell.pseudorange)?;
+                let rough = libm::round(pr / P2_10) * P2_10;
+                (rough < 255.0).then_some(rough)
+            })
+            .collect();
+        let rough_rates: Vec<Option<f64>> = sats
+            .iter()
+            .map(|(_, sat)| {
+                let rate = libm::round(sat.cells.values().next()?.rate);
+                (libm::fabs(rate) < 8192.0).then_some(rate)
+            })
+            .collect();
+
+        for rough in &rough {
+            w.put(rough.map_or(0xff, |r| r as u64), 8);
+        }
+        if self.msm == MsmType::Msm7 {
+            for (_, sat) in sats {
+                w.put(sat.info.into(), 4);
+            }
+        }
+        for rough in &rough {
+            let fraction = rough.map_or(0, |r| libm::round((r - libm::floor(r)) / P2_10) as u64);
+            w.put(fraction, 10);
+        }
+        if self.msm == MsmType::Msm7 {
+            for rate in &rough_rates {
+                w.put_signed(rate.map_or(-8192, |r| r as i64), 14);
+            }
+        }
+
+        // Signal data, field by field over all cells
+        let cells: Vec<(Cell, Option<f64>, Option<f64>)> = sats
+            .iter()
+            .zip(rough.iter().zip(&rough_rates))
+            .flat_map(|((_, sat), (rough, rate))| {
+                signals
+                    .iter()
+                    .filter_map(|id| sat.cells.get(id))
+                    .map(move |cell| (*cell, *rough, *rate))
+            })
+            .collect();
+        let fine = |value: Option<f64>, rough: Option<f64>, scale: f64, bits: u32| {
+            let limit = 1i64 << (bits - 1);
+            value
+                .zip(rough)
+                .map(|(value, rough)| libm::round((value - rough) / scale) as i64)
+                .filter(|fine| fine.abs() < limit)
+                .unwrap_or(-limit)
+        };
+        let (pr_scale, pr_bits, ph_scale, ph_bits) = match self.msm {
+            MsmType::Msm4 => (P2_24, 15, P2_29, 22),
+            MsmType::Msm7 => (P2_29, 20, P2_31, 24),
+        };
+        for (cell, rough, _) in &cells {
+            w.put_signed(
+                fine(cell.pseudorange, *rough, pr_scale, pr_bits),
+                pr_bits as usize,
+            );
+        }
+        for (cell, rough, _) in &cells {
+            w.put_signed(
+                fine(cell.phase, *rough, ph_scale, ph_bits),
+                ph_bits as usize,
+            );
+        }
+        for (cell, _, _) in &cells {
+            match self.msm {
+                MsmType::Msm4 => w.put(lock_time_indicator(cell.lock_ms).into(), 4),
+                MsmType::Msm7 => w.put(lock_time_indicator_ext(cell.lock_ms).into(), 10),
+            }
+        }
+        for (cell, _, _) in &cells {
+            w.put(cell.half_cycle.into(), 1);
+        }
+        for (cell, _, _) in &cells {
+            match self.msm {
+                MsmType::Msm4 => w.put(cell.cno.min(63).into(), 6),
+                MsmType::Msm7 => w.put((u64::from(cell.cno) * 16).min(1023), 10),
+            }
+        }
+        if self.msm == MsmType::Msm7 {
+            for (cell, _, rate) in &cells {
+                w.put_signed(fine(Some(cell.rate), *rate, 1e-4, 15), 15);
+            }
+        }
+        w
+    }
+}
+
+/// Epoch time field: time of week in ms, for GLONASS day of week and time of day
+fn epoch_time(time: &GnssTime, system: char, leap: &LeapSeconds) -> u32 {
+    let scale = match system {
+        'R' => TimeScale::Glonass,
+        'C' => TimeScale::BeiDou,
+        _ => TimeScale::Gps,
+    };
+    let tow_ms = (libm::round(time.to_scale(scale, leap).tow() * 1e3) as u32) % 604_800_000;
+    match system {
+        'R' => ((tow_ms / 86_400_000) << 27) | (tow_ms % 86_400_000),
+        _ => tow_ms,
+    }
+}
+
+/// Lock time indicator DF402
+fn lock_time_indicator(lock_ms: u32) -> u8 {
+    match lock_ms {
+        0..=31 => 0,
+        _ => (32 - lock_ms.leading_zeros() - 5).min(15) as u8,
+    }
+}
+
+/// Extended lock time indicator DF407
+fn lock_time_indicator_ext(lock_ms: u32) -> u16 {
+    if lock_ms < 64 {
+        return lock_ms as u16;
+    }
+    // Each power of two interval is coded with 32 steps
+    let exponent = 31 - lock_ms.leading_zeros();
+    let step = exponent - 5;
+    let indicator = (lock_ms >> step) + 32 * step;
+    indicator.min(704) as u16
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{navmsg::crc24q, PacketRef, Parser, UbxUnknownPacketRef};
+
+    fn getbitu(buf: &[u8], pos: usize, len: usize) -> u64 {
+        (pos..pos + len).fold(0, |bits, i| {
+            (bits << 1) | u64::from((buf[i / 8] >> (7 - i % 8)) & 1)
+        })
+    }
+
+    fn getbits(buf: &[u8], pos: usize, len: usize) -> i64 {
+        let bits = getbitu(buf, pos, len) as i64;
+        (bits << (64 - len)) >> (64 - len)
+    }
+
+    fn measurement(gnss_id: u8, sv_id: u8, sig_id: u8, pr: f64, cp: f64) -> [u8; 32] {
+        let mut meas = [0u8; 32];
+        meas[0..8].copy_from_slice(&pr.to_le_bytes());
+        meas[8..16].copy_from_slice(&cp.to_le_bytes());
+        meas[16..20].copy_from_slice(&(-1200.5f32).to_le_bytes());
+        meas[20] = gnss_id;
+        meas[21] = sv_id;
+        meas[22] = sig_id;
+        meas[23] = 7;
+        meas[24..26].copy_from_slice(&10_000u16.to_le_bytes());
+        meas[26] = 45;
+        meas[30] = 0x07;
+        meas
+    }
+
+    fn encode(encoder: &mut MsmEncoder, tow: f64, meas: &[[u8; 32]]) -> Vec<Vec<u8>> {
+        let mut payload = Vec::new();
+        payload.extend_from_slice(&tow.to_le_bytes());
+        payload.extend_from_slice(&2000u16.to_le_bytes());
+        payload.extend_from_slice(&[18, meas.len() as u8, 0x01, 0x01, 0, 0]);
+        for meas in meas {
+            payload.extend_from_slice(meas);
+        }
+        let bytes = UbxUnknownPacketRef {
+            payload: &payload,
+            class: 0x02,
+            msg_id: 0x15,
+        }
+        .into_packet_vec();
+        let mut parser = Parser::default();
+        let mut it = parser.consume(&bytes);
+        match it.next() {
+            Some(Ok(PacketRef::RxmRawx(raw))) => encoder.encode(&raw).unwrap(),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn lock_time_indicators() {
+        assert_eq!(lock_time_indicator(31), 0);
+        assert_eq!(lock_time_indicator(32), 1);
+        assert_eq!(lock_time_indicator(1000), 5);
+        assert_eq!(lock_time_indicator(64_500), 11);
+        assert_eq!(lock_time_indicator_ext(63), 63);
+        assert_eq!(lock_time_indicator_ext(64), 64);
+        assert_eq!(lock_time_indicator_ext(127), 95);
+        assert_eq!(lock_time_indicator_ext(128), 96);
+        assert_eq!(lock_time_indicator_ext(64_500), 382);
+    }
+
+    #[test]
+    fn msm4_gps_glonass() {
+        let lambda_l1 = super::super::SPEED_OF_LIGHT / 1575.42e6;
+        let pr = 21_000_000.123;
+        let mut no_phase = measurement(0, 12, 0, pr + 1e6, 0.0);
+        no_phase[30] = 0x01;
+        let mut encoder = MsmEncoder::new(42, MsmType::Msm4);
+        let frames = encode(
+            &mut encoder,
+            1.5,
+            &[
+                measurement(0, 5, 0, pr, pr / lambda_l1 + 1e6),
+                no_phase,
+                measurement(6, 3, 0, pr, 0.0),
+            ],
+        );
+        assert_eq!(frames.len(), 2);
+        let gps = &frames[0];
+        assert_eq!(crc24q(gps), 0);
+        let msg = &gps[3..gps.len() - 3];
+        assert_eq!(getbitu(msg, 0, 12), 1074);
+        assert_eq!(getbitu(msg, 12, 12), 42);
+        assert_eq!(getbitu(msg, 24, 30), 1500);
+        // More messages follow for this epoch
+        assert_eq!(getbitu(msg, 54, 1), 1);
+        assert_eq!(getbitu(msg, 73, 64), (1 << 59) | (1 << 52));
+        assert_eq!(getbitu(msg, 137, 32), 1 << 30);
+        assert_eq!(getbitu(msg, 169, 2), 0b11);
+
+        // Rough range of SV 5 and the reconstructed pseudorange
+        let rough = getbitu(msg, 171, 8) as f64 + getbitu(msg, 187, 10) as f64 * P2_10;
+        let fine = getbits(msg, 207, 15) as f64;
+        assert!((rough * RANGE_MS - pr).abs() < RANGE_MS * P2_10);
+        assert!(((rough + fine * P2_24) * RANGE_MS - pr).abs() < 0.02);
+        // The phase offset was removed, SV 12 has no phase
+        let fine_phase = getbits(msg, 237, 22) as f64;
+        assert!(((rough + fine_phase * P2_29) * RANGE_MS - pr).abs() < 1.0);
+        assert_eq!(getbits(msg, 259, 22), -(1 << 21));
+        // Lock of SV 5 was reset when the phase offset was applied
+        assert_eq!(getbitu(msg, 281, 8), 0x09);
+
+        let glonass = &frames[1];
+        let msg = &glonass[3..glonass.len() - 3];
+        assert_eq!(getbitu(msg, 0, 12), 1084);
+        assert_eq!(getbitu(msg, 54, 1), 0);
+        // Moscow time: Sunday 03:00:01.5 minus 18 leap seconds
+        assert_eq!(getbitu(msg, 24, 3), 0);
+        assert_eq!(getbitu(msg, 27, 27), (3 * 3600 - 18) * 1000 + 1500);
+    }
+
+    #[test]
+    fn msm7_galileo() {
+        let mut encoder = MsmEncoder::new(1, MsmType::Msm7);
+        let frames = encode(
+            &mut encoder,
+            100.0,
+            &[
+                measurement(2, 11, 0, 25_000_000.0, 0.0),
+                measurement(2, 11, 5, 25_000_003.0, 0.0),
+            ],
+        );
+        let [galileo] = frames.as_slice() else {
+            panic!("expected one message");
+        };
+        let msg = &galileo[3..galileo.len() - 3];
+        assert_eq!(getbitu(msg, 0, 12), 1097);
+        assert_eq!(getbitu(msg, 137, 32), (1 << 30) | (1 << 18));
+        // Rough phase range rate from the Doppler of E1C
+        let lambda = super::super::SPEED_OF_LIGHT / 1575.42e6;
+        let rate = getbitu(msg, 169 + 2 + 8 + 4 + 10, 14) as f64;
+        assert_eq!(rate, (1200.5 * lambda).round());
+    }
+}
@@ -0,0 +1,100 @@
+//! Reference station messages
+
+use alloc::vec::Vec;
+
+use super::BitWriter;
+use crate::PositionECEF;
+
+/// Message 1005: stationary antenna reference point in ECEF coordinates,
+/// with GPS, GLONASS and Galileo service indicated
+pub fn station_arp(station_id: u16, position: &PositionECEF) -> Vec<u8> {
+    let coordinate = |value: f64| libm::round(value * 1e4) as i64;
+    let mut w = BitWriter::default();
+    w.put(1005, 12);
+    w.put(station_id.into(), 12);
+    // ITRF realization year
+    w.put(0, 6);
+    // GPS, GLONASS and Galileo indicators
+    w.put(0b111, 3);
+    // Reference station indicator
+    w.put(0, 1);
+    w.put_signed(coordinate(position.x), 38);
+    // Single receiver oscillator indicator and reserved bit
+    w.put(0, 2);
+    w.put_signed(coordinate(position.y), 38);
+    // Quarter cycle indicator
+    w.put(0, 2);
+    w.put_signed(coordinate(position.z), 38);
+    w.into_frame()
+}
+
+/// GLONASS code-phase biases in meters, `None` for biases not transmitted
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GlonassBiases {
+    /// The phase ranges are aligned to the code ranges, i.e. the biases were removed
+    pub aligned: bool,
+    pub l1_ca: Option<f64>,
+    pub l1_p: Option<f64>,
+    pub l2_ca: Option<f64>,
+    pub l2_p: Option<f64>,
+}
+
+/// Message 1230: GLONASS L1 and L2 code-phase biases
+pub fn glonass_biases(station_id: u16, biases: &GlonassBiases) -> Vec<u8> {
+    let values = [biases.l1_ca, biases.l1_p, biases.l2_ca, biases.l2_p];
+    let mut w = BitWriter::default();
+    w.put(1230, 12);
+    w.put(station_id.into(), 12);
+    w.put(biases.aligned.into(), 1);
+    // Reserved
+    w.put(0, 3);
+    let mask = values
+        .iter()
+        .fold(0, |mask, bias| (mask << 1) | u64::from(bias.is_some()));
+    w.put(mask, 4);
+    for bias in values.iter().flatten() {
+        w.put_signed(libm::round(bias / 0.02) as i64, 16);
+    }
+    w.into_frame()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::navmsg::crc24q;
+
+    #[test]
+    fn station_arp_message() {
+        let position = PositionECEF {
+            x: 4027881.3388,
+            y: 307045.6,
+            z: 4919474.9108,
+        };
+        let frame = station_arp(2003, &position);
+        assert_eq!(&frame[..3], &[0xd3, 0x00, 19]);
+        assert_eq!(&frame[3..6], &[0x3e, 0xd7, 0xd3]);
+        assert_eq!(crc24q(&frame), 0);
+        // X at bits 34 to 72 of the message
+        let x = frame[3..]
+            .iter()
+            .take(9)
+            .fold(0u128, |bits, byte| (bits << 8) | u128::from(*byte));
+        assert_eq!(x & ((1 << 38) - 1), 40_278_813_388);
+    }
+
+    #[test]
+    fn glonass_bias_message() {
+        let biases = GlonassBiases {
+            aligned: true,
+            l1_ca: Some(-0.5),
+            l2_p: Some(1.0),
+            ..Default::default()
+        };
+        let frame = glonass_biases(1, &biases);
+        assert_eq!(
+            &frame[3..frame.len() - 3],
+            &[0x4c, 0xe0, 0x01, 0x89, 0xff, 0xe7, 0x00, 0x32]
+        );
+        assert_eq!(crc24q(&frame), 0);
+    }
+}
@@ -1,9 +1,22 @@
 pub mod cfg_val;
+mod esf;
+mod geodesy;
+mod gnss_time;
+mod interference;
 mod packets;
+mod survey;
 mod types;
 
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
 use crate::error::MemWriterError;
+pub use esf::*;
+pub use geodesy::*;
+pub use gnss_time::*;
+pub use interference::*;
 pub use packets::*;
+pub use survey::*;
 pub use types::*;
 
 /// Information about concrete UBX protocol's packet
@@ -91,6 +104,45 @@ pub struct UbxUnknownPacketRef<'a> {
     pub msg_id: u8,
 }
 
+impl UbxUnknownPacketRef<'_> {
+    /// Frame the raw payload as a UBX packet, for example
+    /// to send back a configuration read from the receiver
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub fn into_packet_vec(self) -> Vec<u8> {
+        let mut vec = Vec::new();
+        self.extend_to(&mut vec);
+        vec
+    }
+
+    /// Frame the raw payload as a UBX packet into `out`
+    ///
+    /// # Panics
+    /// If the payload is longer than `u16::MAX` bytes
+    pub fn extend_to<T>(self, out: &mut T)
+    where
+        T: core::iter::Extend<u8>,
+    {
+        let len = u16::try_from(self.payload.len()).expect("UBX payload too long");
+        let len_bytes = len.to_le_bytes();
+        let header = [
+            SYNC_CHAR_1,
+            SYNC_CHAR_2,
+            self.class,
+            self.msg_id,
+            len_bytes[0],
+            len_bytes[1],
+        ];
+        out.extend(header);
+        out.extend(self.payload.iter().copied());
+        let mut checksum_calc = UbxChecksumCalc::default();
+        checksum_calc.update(&header[2..]);
+        checksum_calc.update(self.payload);
+        let (ck_a, ck_b) = checksum_calc.result();
+        out.extend([ck_a, ck_b]);
+    }
+}
+
 /// Request specific packet
 pub struct UbxPacketRequest {
     req_class: u8,
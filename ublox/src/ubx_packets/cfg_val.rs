@@ -1,5 +1,11 @@
+#[cfg(feature = "alloc")]
+use alloc::{collections::BTreeMap, vec::Vec};
+
 use super::{AlignmentToReferenceTime, CfgInfMask, DataBits, Parity, StopBits};
+#[cfg(feature = "alloc")]
+use super::{CfgLayer, CfgValSetBuilder};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct KeyId(u32);
 
 pub enum StorageSize {
@@ -24,6 +30,19 @@ impl StorageSize {
 impl KeyId {
     pub(crate) const SIZE: usize = 4;
 
+    pub const fn new(raw: u32) -> Self {
+        Self(raw)
+    }
+
+    /// Key selecting all items of a group, only valid in a CFG-VALGET poll
+    pub const fn group_wildcard(group_id: u8) -> Self {
+        Self(((group_id as u32) << 16) | 0x0fff)
+    }
+
+    pub const fn raw(&self) -> u32 {
+        self.0
+    }
+
     pub const fn value_size(&self) -> StorageSize {
         match (self.0 >> 28) & 0b111 {
             1 => StorageSize::OneBit,
@@ -42,82 +61,113 @@ impl KeyId {
     pub const fn item_id(&self) -> u8 {
         self.0 as u8
     }
+
+    pub fn extend_to<T>(&self, buf: &mut T) -> usize
+    where
+        T: core::iter::Extend<u8>,
+    {
+        let bytes = self.0.to_le_bytes();
+        buf.extend(bytes);
+        bytes.len()
+    }
 }
 
+/// Decodes a value, `None` if it is outside the range of its type
 macro_rules! from_cfg_v_bytes {
     ($buf:expr, bool) => {
         match $buf[0] {
-            0 => false,
-            1 => true,
-            _ => unreachable!(),
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
         }
     };
     ($buf:expr, u8) => {
-        $buf[0]
+        Some($buf[0])
+    };
+    ($buf:expr, i8) => {
+        Some($buf[0] as i8)
     };
     ($buf:expr, u16) => {
-        u16::from_le_bytes([$buf[0], $buf[1]])
+        Some(u16::from_le_bytes([$buf[0], $buf[1]]))
     };
     ($buf:expr, i16) => {
-        i16::from_le_bytes([$buf[0], $buf[1]])
+        Some(i16::from_le_bytes([$buf[0], $buf[1]]))
     };
     ($buf:expr, u32) => {
-        u32::from_le_bytes([$buf[0], $buf[1], $buf[2], $buf[3]])
+        Some(u32::from_le_bytes([$buf[0], $buf[1], $buf[2], $buf[3]]))
+    };
+    ($buf:expr, i32) => {
+        Some(i32::from_le_bytes([$buf[0], $buf[1], $buf[2], $buf[3]]))
     };
     ($buf:expr, u64) => {
-        u64::from_le_bytes([
+        Some(u64::from_le_bytes([
             $buf[0], $buf[1], $buf[2], $buf[3], $buf[4], $buf[5], $buf[6], $buf[7],
-        ])
+        ]))
     };
     ($buf:expr, CfgInfMask) => {
-        CfgInfMask::from_bits_truncate($buf[0])
+        Some(CfgInfMask::from_bits_truncate($buf[0]))
     };
     ($buf:expr, DataBits) => {
         match $buf[0] {
-            0 => DataBits::Eight,
-            1 => DataBits::Seven,
-            _ => unreachable!(),
+            0 => Some(DataBits::Eight),
+            1 => Some(DataBits::Seven),
+            _ => None,
         }
     };
     ($buf:expr, Parity) => {
         match $buf[0] {
-            0 => Parity::None,
-            1 => Parity::Odd,
-            2 => Parity::Even,
-            _ => unreachable!(),
+            0 => Some(Parity::None),
+            1 => Some(Parity::Odd),
+            2 => Some(Parity::Even),
+            _ => None,
         }
     };
     ($buf:expr, StopBits) => {
         match $buf[0] {
-            0 => StopBits::Half,
-            1 => StopBits::One,
-            2 => StopBits::OneHalf,
-            3 => StopBits::Two,
-            _ => unreachable!(),
+            0 => Some(StopBits::Half),
+            1 => Some(StopBits::One),
+            2 => Some(StopBits::OneHalf),
+            3 => Some(StopBits::Two),
+            _ => None,
         }
     };
     ($buf:expr, AlignmentToReferenceTime) => {
         match $buf[0] {
-            0 => AlignmentToReferenceTime::Utc,
-            1 => AlignmentToReferenceTime::Gps,
-            2 => AlignmentToReferenceTime::Glo,
-            3 => AlignmentToReferenceTime::Bds,
-            4 => AlignmentToReferenceTime::Gal,
-            _ => unreachable!(),
+            0 => Some(AlignmentToReferenceTime::Utc),
+            1 => Some(AlignmentToReferenceTime::Gps),
+            2 => Some(AlignmentToReferenceTime::Glo),
+            3 => Some(AlignmentToReferenceTime::Bds),
+            4 => Some(AlignmentToReferenceTime::Gal),
+            _ => None,
         }
     };
     ($buf:expr, TpPulse) => {
         match $buf[0] {
-            0 => TpPulse::Period,
-            1 => TpPulse::Freq,
-            _ => unreachable!(),
+            0 => Some(TpPulse::Period),
+            1 => Some(TpPulse::Freq),
+            _ => None,
         }
     };
     ($buf:expr, TpPulseLength) => {
         match $buf[0] {
-            0 => TpPulseLength::Ratio,
-            1 => TpPulseLength::Length,
-            _ => unreachable!(),
+            0 => Some(TpPulseLength::Ratio),
+            1 => Some(TpPulseLength::Length),
+            _ => None,
+        }
+    };
+    ($buf:expr, TmodeRcvrMode) => {
+        match $buf[0] {
+            0 => Some(TmodeRcvrMode::Disabled),
+            1 => Some(TmodeRcvrMode::SurveyIn),
+            2 => Some(TmodeRcvrMode::Fixed),
+            _ => None,
+        }
+    };
+    ($buf:expr, TmodePositionType) => {
+        match $buf[0] {
+            0 => Some(TmodePositionType::Ecef),
+            1 => Some(TmodePositionType::Llh),
+            _ => None,
         }
     };
 }
@@ -139,6 +189,9 @@ macro_rules! into_cfg_kv_bytes {
     ($this:expr, u8) => {{
       into_cfg_kv_bytes!(@inner [$this.0])
     }};
+    ($this:expr, i8) => {{
+      into_cfg_kv_bytes!(@inner [$this.0 as u8])
+    }};
     ($this:expr, u16) => {{
       let bytes = $this.0.to_le_bytes();
       into_cfg_kv_bytes!(@inner [bytes[0], bytes[1]])
@@ -151,6 +204,10 @@ macro_rules! into_cfg_kv_bytes {
       let bytes = $this.0.to_le_bytes();
       into_cfg_kv_bytes!(@inner [bytes[0], bytes[1], bytes[2], bytes[3]])
     }};
+    ($this:expr, i32) => {{
+      let bytes = $this.0.to_le_bytes();
+      into_cfg_kv_bytes!(@inner [bytes[0], bytes[1], bytes[2], bytes[3]])
+    }};
     ($this:expr, u64) => {{
       let bytes = $this.0.to_le_bytes();
       into_cfg_kv_bytes!(@inner [bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7]])
@@ -202,6 +259,16 @@ macro_rules! into_cfg_kv_bytes {
           $this.0 as u8
       ])
     };
+    ($this:expr, TmodeRcvrMode) => {
+      into_cfg_kv_bytes!(@inner [
+          $this.0 as u8
+      ])
+    };
+    ($this:expr, TmodePositionType) => {
+      into_cfg_kv_bytes!(@inner [
+          $this.0 as u8
+      ])
+    };
 }
 
 macro_rules! cfg_val {
@@ -212,6 +279,7 @@ macro_rules! cfg_val {
     )*
   ) => {
     #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
     #[non_exhaustive]
     pub enum CfgVal {
       $(
@@ -221,6 +289,15 @@ macro_rules! cfg_val {
     }
 
     impl CfgVal {
+      /// Key of this configuration item
+      pub const fn key(&self) -> KeyId {
+        match self {
+          $(
+            Self::$cfg_item(_) => $cfg_item::KEY,
+          )*
+        }
+      }
+
       pub const fn len(&self) -> usize {
         match self {
           $(
@@ -235,19 +312,49 @@ macro_rules! cfg_val {
           self.len() == 0
       }
 
+      /// Maximum length of an encoded key/value pair
+      const MAX_LEN: usize = KeyId::SIZE + StorageSize::EightBytes.to_usize();
+
+      /// Panics on input rejected by [CfgVal::try_parse], use that for data read from a receiver
       #[track_caller]
       pub fn parse(buf: &[u8]) -> Self {
-        let key_id = u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]);
+        match Self::try_parse(buf) {
+          Some(value) => value,
+          None => {
+            let key_id = u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]);
+            unimplemented!("unknown key ID or invalid value: 0x{:8X}", key_id)
+          },
+        }
+      }
+
+      /// Same as [CfgVal::parse], but returns `None` for keys missing from this table,
+      /// values outside the range of their type and truncated input
+      pub fn try_parse(buf: &[u8]) -> Option<Self> {
+        let key_id = u32::from_le_bytes(buf.get(..KeyId::SIZE)?.try_into().ok()?);
         match key_id {
           $(
             $cfg_key_id => {
-              Self::$cfg_item(from_cfg_v_bytes!(&buf[4..], $cfg_value_type))
+              let value = buf.get(KeyId::SIZE..$cfg_item::SIZE)?;
+              Some(Self::$cfg_item(from_cfg_v_bytes!(value, $cfg_value_type)?))
             },
           )*
-          _ => unimplemented!("unknown key ID: 0x{:8X}", key_id),
+          _ => None,
         }
       }
 
+      /// Group IDs of all configuration items in this table, without duplicates
+      pub fn known_groups() -> impl Iterator<Item = u8> {
+        const KEYS: &[KeyId] = &[$(KeyId($cfg_key_id)),*];
+        KEYS.iter().enumerate().filter_map(|(i, key)| {
+          let group = key.group_id();
+          if KEYS[..i].iter().any(|prev| prev.group_id() == group) {
+            None
+          } else {
+            Some(group)
+          }
+        })
+      }
+
       pub fn extend_to<T>(&self, buf: &mut T) -> usize
       where
           T: core::iter::Extend<u8>
@@ -277,6 +384,19 @@ macro_rules! cfg_val {
       }
     }
 
+    /// Two items are equal if they have the same key and the same encoded value
+    impl PartialEq for CfgVal {
+      fn eq(&self, other: &Self) -> bool {
+        let mut buf = [0; CfgVal::MAX_LEN];
+        let mut other_buf = [0; CfgVal::MAX_LEN];
+        let len = self.write_to(&mut buf);
+        let other_len = other.write_to(&mut other_buf);
+        buf[..len] == other_buf[..other_len]
+      }
+    }
+
+    impl Eq for CfgVal {}
+
     $(
       struct $cfg_item(pub $cfg_value_type);
 
@@ -1103,9 +1223,118 @@ cfg_val! {
   TpAlignToTowTp1,       0x1005000a, bool,
   TpPolTp1,              0x1005000b, bool,
   TpTimegridTp1,         0x2005000c, AlignmentToReferenceTime,
+
+  // CFG-TMODE-*
+  TmodeMode,             0x20030001, TmodeRcvrMode,
+  TmodePosType,          0x20030002, TmodePositionType,
+  /// ECEF X coordinate of the ARP position in cm
+  TmodeEcefX,            0x40030003, i32,
+  /// ECEF Y coordinate of the ARP position in cm
+  TmodeEcefY,            0x40030004, i32,
+  /// ECEF Z coordinate of the ARP position in cm
+  TmodeEcefZ,            0x40030005, i32,
+  /// High precision ECEF X coordinate of the ARP position in 0.1 mm
+  TmodeEcefXHp,          0x20030006, i8,
+  /// High precision ECEF Y coordinate of the ARP position in 0.1 mm
+  TmodeEcefYHp,          0x20030007, i8,
+  /// High precision ECEF Z coordinate of the ARP position in 0.1 mm
+  TmodeEcefZHp,          0x20030008, i8,
+  /// Latitude of the ARP position in 1e-7 deg
+  TmodeLat,              0x40030009, i32,
+  /// Longitude of the ARP position in 1e-7 deg
+  TmodeLon,              0x4003000a, i32,
+  /// Height of the ARP position in cm
+  TmodeHeight,           0x4003000b, i32,
+  /// High precision latitude of the ARP position in 1e-9 deg
+  TmodeLatHp,            0x2003000c, i8,
+  /// High precision longitude of the ARP position in 1e-9 deg
+  TmodeLonHp,            0x2003000d, i8,
+  /// High precision height of the ARP position in 0.1 mm
+  TmodeHeightHp,         0x2003000e, i8,
+  /// Fixed position 3D accuracy in 0.1 mm
+  TmodeFixedPosAcc,      0x4003000f, u32,
+  /// Survey-in minimum duration in s
+  TmodeSvinMinDur,       0x40030010, u32,
+  /// Survey-in position accuracy limit in 0.1 mm
+  TmodeSvinAccLimit,     0x40030011, u32,
+}
+
+/// Differences between two collections of configuration items,
+/// for example an intended profile and a CFG-VALGET readback
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CfgValDiff {
+    /// Items whose key is only present in the new collection
+    pub added: Vec<CfgVal>,
+    /// Items whose key is only present in the old collection
+    pub removed: Vec<CfgVal>,
+    /// Items present in both collections with different values, as (old, new)
+    pub changed: Vec<(CfgVal, CfgVal)>,
+}
+
+#[cfg(feature = "alloc")]
+impl CfgValDiff {
+    /// Compares the items by key. If a key appears more than once
+    /// in a collection, its last value is used.
+    pub fn new(old: &[CfgVal], new: &[CfgVal]) -> Self {
+        let old_by_key = Self::by_key(old);
+        let new_by_key = Self::by_key(new);
+
+        let mut diff = Self::default();
+        for (key, new_val) in &new_by_key {
+            match old_by_key.get(key) {
+                None => diff.added.push((*new_val).clone()),
+                Some(old_val) if old_val != new_val => {
+                    diff.changed.push(((*old_val).clone(), (*new_val).clone()))
+                },
+                Some(_) => {},
+            }
+        }
+        for (key, old_val) in &old_by_key {
+            if !new_by_key.contains_key(key) {
+                diff.removed.push((*old_val).clone());
+            }
+        }
+        diff
+    }
+
+    fn by_key(values: &[CfgVal]) -> BTreeMap<u32, &CfgVal> {
+        values.iter().map(|val| (val.key().raw(), val)).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// The minimal set of items to write with CFG-VALSET to turn the old collection
+    /// into the new one. Removed items cannot be reverted by CFG-VALSET and are not included.
+    pub fn val_set_items(&self) -> Vec<CfgVal> {
+        self.added
+            .iter()
+            .chain(self.changed.iter().map(|(_, new)| new))
+            .cloned()
+            .collect()
+    }
+
+    /// The CFG-VALSET packets writing [CfgValDiff::val_set_items] to the given layers
+    pub fn val_set_packets(&self, layers: CfgLayer) -> Vec<Vec<u8>> {
+        self.val_set_items()
+            .chunks(CfgValSetBuilder::MAX_ITEMS)
+            .map(|chunk| {
+                CfgValSetBuilder {
+                    version: 0,
+                    layers,
+                    reserved1: 0,
+                    cfg_data: chunk,
+                }
+                .into_packet_vec()
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TpPulse {
     /// Time pulse period
     Period = 0,
@@ -1114,9 +1343,87 @@ pub enum TpPulse {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TpPulseLength {
     /// Time pulse ratio
     Ratio = 0,
     /// Time pulse length
     Length = 1,
 }
+
+/// Receiver mode of CFG-TMODE-MODE
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TmodeRcvrMode {
+    Disabled = 0,
+    SurveyIn = 1,
+    /// Fixed ARP position given by the other CFG-TMODE keys
+    Fixed = 2,
+}
+
+/// Format of the fixed ARP position of CFG-TMODE-POS_TYPE
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TmodePositionType {
+    Ecef = 0,
+    Llh = 1,
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cfg_val_eq_by_key_and_value() {
+        assert_eq!(CfgVal::Uart1Baudrate(9600), CfgVal::Uart1Baudrate(9600));
+        assert_ne!(CfgVal::Uart1Baudrate(9600), CfgVal::Uart1Baudrate(115200));
+        assert_ne!(CfgVal::Uart1Baudrate(9600), CfgVal::Uart2Baudrate(9600));
+        assert_eq!(
+            CfgVal::Uart1Parity(Parity::Even).key(),
+            KeyId::new(0x20520004)
+        );
+    }
+
+    #[test]
+    fn cfg_val_try_parse_rejects_invalid_values() {
+        // CFG-TP-TIMEGRID_TP1 = NavIC, unknown to AlignmentToReferenceTime
+        assert_eq!(CfgVal::try_parse(&[0x0c, 0x00, 0x05, 0x20, 5]), None);
+        assert_eq!(
+            CfgVal::try_parse(&[0x0c, 0x00, 0x05, 0x20, 1]),
+            Some(CfgVal::TpTimegridTp1(AlignmentToReferenceTime::Gps))
+        );
+        assert_eq!(CfgVal::try_parse(&[0x05, 0x00, 0x52, 0x10, 2]), None);
+        // Truncated value
+        assert_eq!(CfgVal::try_parse(&[0x01, 0x00, 0x52, 0x40, 0x80]), None);
+        assert_eq!(CfgVal::try_parse(&[0x01, 0x00]), None);
+    }
+
+    #[test]
+    fn cfg_val_diff() {
+        let old = [
+            CfgVal::Uart1Baudrate(9600),
+            CfgVal::Uart1Enabled(true),
+            CfgVal::Uart2InProtUbx(true),
+        ];
+        let new = [
+            CfgVal::Uart1Enabled(true),
+            CfgVal::Uart1Baudrate(115200),
+            CfgVal::SignalGalEna(false),
+        ];
+
+        let diff = CfgValDiff::new(&old, &new);
+        assert_eq!(diff.added, [CfgVal::SignalGalEna(false)]);
+        assert_eq!(diff.removed, [CfgVal::Uart2InProtUbx(true)]);
+        assert_eq!(
+            diff.changed,
+            [(CfgVal::Uart1Baudrate(9600), CfgVal::Uart1Baudrate(115200))]
+        );
+        assert_eq!(
+            diff.val_set_items(),
+            [CfgVal::SignalGalEna(false), CfgVal::Uart1Baudrate(115200)]
+        );
+        assert_eq!(diff.val_set_packets(CfgLayer::RAM).len(), 1);
+
+        assert!(CfgValDiff::new(&new, &new).is_empty());
+    }
+}
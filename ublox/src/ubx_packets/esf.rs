@@ -0,0 +1,456 @@
+//! IMU and odometer samples of ESF-RAW and ESF-MEAS in SI units, and the
+//! health of the sensor fusion from ESF-STATUS and ESF-ALG
+//!
+//! The sensor time tags of ESF-RAW wrap around after a few minutes to hours,
+//! depending on the receiver, and the ESF-MEAS time tags at the end of the
+//! GPS week. [EsfTimeline] unwraps both into continuous timelines and relates
+//! the sensor clock to GNSS time.
+//!
+//! [FusionMonitor] follows the initialization of the IMU, the mount alignment
+//! and the wheel tick calibration, and reports each change as a [FusionEvent].
+
+use core::f64::consts::PI;
+
+use super::packets::*;
+use crate::PacketRef;
+
+/// Milliseconds in a GPS week
+const WEEK_MS: u64 = 604_800_000;
+
+/// A sensor measurement in SI units
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SensorValue {
+    /// Angular rate in rad/s
+    AngularRate(f64),
+    /// Specific force in m/s^2
+    Acceleration(f64),
+    /// Gyroscope temperature in degrees Celsius
+    Temperature(f64),
+    /// Vehicle speed in m/s, negative when driving backwards
+    Speed(f64),
+    /// Wheel or speed tick counter, wrapping at 23 bits
+    Ticks { count: u32, backward: bool },
+}
+
+impl SensorValue {
+    /// Decodes the 24 bit data field of a `sensor` measurement, `None` for
+    /// unknown sensors
+    pub fn decode(sensor: EsfSensorType, field: u32) -> Option<Self> {
+        if sensor.is_tick() {
+            let (count, backward) = EsfSensorType::field_ticks(field);
+            return Some(SensorValue::Ticks { count, backward });
+        }
+        // In the units of EsfSensorType
+        let value = f64::from(EsfSensorType::field_value(field)) * sensor.scale()?;
+        let value = match sensor {
+            EsfSensorType::Speed => SensorValue::Speed(value),
+            EsfSensorType::GyroX | EsfSensorType::GyroY | EsfSensorType::GyroZ => {
+                SensorValue::AngularRate(value * PI / 180.0)
+            },
+            EsfSensorType::AccX | EsfSensorType::AccY | EsfSensorType::AccZ => {
+                SensorValue::Acceleration(value)
+            },
+            EsfSensorType::GyroTemp => SensorValue::Temperature(value),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+/// A sensor measurement placed on the timelines of [EsfTimeline]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SensorSample {
+    pub sensor: EsfSensorType,
+    pub value: SensorValue,
+    /// Seconds on the unwrapped sensor clock
+    pub sensor_time: Option<f64>,
+    /// GPS time of week in seconds, continuing past the end of the week
+    pub gnss_time: Option<f64>,
+}
+
+/// Turns a time tag wrapping at `modulus` into a continuous count.
+/// Samples arriving slightly out of order are placed before the latest one.
+#[derive(Debug, Clone, Copy)]
+pub struct TimeTagUnwrapper {
+    modulus: u64,
+    latest: Option<u64>,
+}
+
+impl TimeTagUnwrapper {
+    /// Panics if `modulus` is 0
+    pub fn new(modulus: u64) -> Self {
+        assert!(modulus > 0, "time tag modulus must not be 0");
+        Self {
+            modulus,
+            latest: None,
+        }
+    }
+
+    /// Time tags of `bits` bits, panics if `bits` is 64 or more
+    pub fn with_bits(bits: u32) -> Self {
+        assert!(bits < 64, "time tags cannot have more than 63 bits");
+        Self::new(1 << bits)
+    }
+
+    pub fn unwrap(&mut self, tag: u32) -> u64 {
+        let tag = u64::from(tag) % self.modulus;
+        let Some(latest) = self.latest else {
+            self.latest = Some(tag);
+            return tag;
+        };
+        let delta = (tag + self.modulus - latest % self.modulus) % self.modulus;
+        let unwrapped = if delta < self.modulus / 2 {
+            latest + delta
+        } else {
+            latest.saturating_sub(self.modulus - delta)
+        };
+        self.latest = Some(latest.max(unwrapped));
+        unwrapped
+    }
+}
+
+/// Continuous timelines of the ESF sensor clock and of GNSS time
+///
+/// ESF-RAW samples are placed on the sensor clock, ESF-MEAS samples on GNSS
+/// time through their time tag. An ESF-MEAS carrying a calibration time tag,
+/// on the sensor clock, relates both timelines, as does [EsfTimeline::align].
+#[derive(Debug, Clone, Copy)]
+pub struct EsfTimeline {
+    sensor: TimeTagUnwrapper,
+    resolution: f64,
+    itow: TimeTagUnwrapper,
+    /// GNSS time minus sensor time in seconds
+    offset: Option<f64>,
+}
+
+impl EsfTimeline {
+    /// `resolution` in seconds and `bits` of the sensor time tags, as given
+    /// in the interface description of the receiver. Panics if `bits` is 64 or more.
+    pub fn new(resolution: f64, bits: u32) -> Self {
+        Self {
+            sensor: TimeTagUnwrapper::with_bits(bits),
+            resolution,
+            itow: TimeTagUnwrapper::new(WEEK_MS),
+            offset: None,
+        }
+    }
+
+    /// Relates a sensor time tag with the GPS time of week in ms of the same instant
+    pub fn align(&mut self, sensor_tag: u32, itow: u32) {
+        let sensor_time = self.sensor_time(sensor_tag);
+        self.offset = Some(self.gnss_time(itow) - sensor_time);
+    }
+
+    pub fn is_aligned(&self) -> bool {
+        self.offset.is_some()
+    }
+
+    /// Samples of an ESF-RAW message
+    pub fn raw<'a>(
+        &'a mut self,
+        raw: &'a EsfRawRef<'_>,
+    ) -> impl Iterator<Item = SensorSample> + 'a {
+        raw.data().filter_map(move |data| {
+            let sensor = data.sensor_type();
+            let value = SensorValue::decode(sensor, data.data_field)?;
+            let sensor_time = self.sensor_time(data.sensor_time_tag);
+            Some(SensorSample {
+                sensor,
+                value,
+                sensor_time: Some(sensor_time),
+                gnss_time: self.offset.map(|offset| sensor_time + offset),
+            })
+        })
+    }
+
+    /// Samples of an ESF-MEAS message
+    pub fn meas<'a>(
+        &'a mut self,
+        meas: &'a EsfMeasRef<'_>,
+    ) -> impl Iterator<Item = SensorSample> + 'a {
+        if let Some(calib_tag) = meas.calib_tag() {
+            self.align(calib_tag, meas.itow());
+        }
+        let gnss_time = self.gnss_time(meas.itow());
+        let sensor_time = self.offset.map(|offset| gnss_time - offset);
+        meas.data().filter_map(move |data| {
+            Some(SensorSample {
+                sensor: data.data_type,
+                value: SensorValue::decode(data.data_type, data.field())?,
+                sensor_time,
+                gnss_time: Some(gnss_time),
+            })
+        })
+    }
+
+    fn sensor_time(&mut self, tag: u32) -> f64 {
+        self.sensor.unwrap(tag) as f64 * self.resolution
+    }
+
+    fn gnss_time(&mut self, itow: u32) -> f64 {
+        self.itow.unwrap(itow) as f64 * 1e-3
+    }
+}
+
+/// Initialization of the sensor fusion, from ESF-STATUS and ESF-ALG
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FusionState {
+    pub fusion_mode: EsfStatusFusionMode,
+    pub imu: EsfStatusImuInit,
+    pub ins: EsfStatusInsInit,
+    pub mount_alignment: EsfStatusMountAngle,
+    pub wheel_ticks: EsfStatusWheelTickInit,
+    /// From ESF-ALG, `None` until the first one
+    pub alignment: Option<EsfAlgStatus>,
+    pub alignment_errors: EsfAlgError,
+}
+
+impl FusionState {
+    /// Fusing with an initialized IMU and mount alignment, and with
+    /// calibrated wheel ticks when they are used
+    pub fn is_calibrated(&self) -> bool {
+        self.fusion_mode == EsfStatusFusionMode::Fusion
+            && self.imu == EsfStatusImuInit::Initialized
+            && self.ins == EsfStatusInsInit::Initialized
+            && self.mount_alignment == EsfStatusMountAngle::Initialized
+            && self.wheel_ticks != EsfStatusWheelTickInit::Initializing
+            && self.alignment_errors.is_empty()
+    }
+}
+
+/// A change reported by [FusionMonitor]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FusionEvent {
+    FusionMode(EsfStatusFusionMode),
+    ImuInit(EsfStatusImuInit),
+    InsInit(EsfStatusInsInit),
+    MountAlignment(EsfStatusMountAngle),
+    WheelTickCalibration(EsfStatusWheelTickInit),
+    AlignmentStatus(EsfAlgStatus),
+    /// Newly raised alignment errors
+    AlignmentError(EsfAlgError),
+    SensorCalibration {
+        sensor: EsfSensorType,
+        status: EsfSensorStatusCalibration,
+    },
+    /// Newly raised sensor faults
+    SensorFault {
+        sensor: EsfSensorType,
+        faults: EsfSensorFaults,
+    },
+    /// The fusion is calibrated, `after` seconds since the monitor started or
+    /// the calibration was lost
+    Calibrated {
+        after: f64,
+    },
+    CalibrationLost,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SensorHealth {
+    calibration: EsfSensorStatusCalibration,
+    faults: EsfSensorFaults,
+}
+
+/// Number of [EsfSensorType] values
+const SENSOR_TYPES: usize = EsfSensorType::Unknown as usize + 1;
+
+/// Follows the sensor fusion through ESF-STATUS and ESF-ALG messages
+#[derive(Debug, Clone)]
+pub struct FusionMonitor {
+    state: Option<FusionState>,
+    sensors: [Option<SensorHealth>; SENSOR_TYPES],
+    itow: TimeTagUnwrapper,
+    /// Start of the current calibration in ms
+    start: Option<u64>,
+    time_to_calibrated: Option<f64>,
+}
+
+impl Default for FusionMonitor {
+    fn default() -> Self {
+        Self {
+            state: None,
+            sensors: [None; SENSOR_TYPES],
+            itow: TimeTagUnwrapper::new(WEEK_MS),
+            start: None,
+            time_to_calibrated: None,
+        }
+    }
+}
+
+impl FusionMonitor {
+    pub fn state(&self) -> Option<&FusionState> {
+        self.state.as_ref()
+    }
+
+    /// Seconds the last calibration took
+    pub fn time_to_calibrated(&self) -> Option<f64> {
+        self.time_to_calibrated
+    }
+
+    /// Calibration status and faults of a sensor reported by ESF-STATUS
+    pub fn sensor(
+        &self,
+        sensor: EsfSensorType,
+    ) -> Option<(EsfSensorStatusCalibration, EsfSensorFaults)> {
+        self.sensors[sensor as usize].map(|health| (health.calibration, health.faults))
+    }
+
+    /// Handles ESF-STATUS and ESF-ALG, ignoring other packets
+    pub fn handle(&mut self, packet: &PacketRef<'_>, on_event: impl FnMut(FusionEvent)) {
+        match packet {
+            PacketRef::EsfStatus(status) => self.status(status, on_event),
+            PacketRef::EsfAlg(alg) => self.alg(alg, on_event),
+            _ => {},
+        }
+    }
+
+    pub fn status(&mut self, status: &EsfStatusRef<'_>, mut on_event: impl FnMut(FusionEvent)) {
+        let init1 = status.init_status1();
+        let init2 = status.init_status2();
+        let state = FusionState {
+            fusion_mode: status.fusion_mode(),
+            imu: init2.imu_init_status(),
+            ins: init1.ins_initialization_status(),
+            mount_alignment: init1.mounting_angle_status(),
+            wheel_ticks: init1.wheel_tick_init_status(),
+            alignment: self.state.and_then(|state| state.alignment),
+            alignment_errors: self
+                .state
+                .map(|state| state.alignment_errors)
+                .unwrap_or_default(),
+        };
+        // The initial state is reported as a change
+        let fields: [fn(&FusionState) -> FusionEvent; 5] = [
+            |state| FusionEvent::FusionMode(state.fusion_mode),
+            |state| FusionEvent::ImuInit(state.imu),
+            |state| FusionEvent::InsInit(state.ins),
+            |state| FusionEvent::MountAlignment(state.mount_alignment),
+            |state| FusionEvent::WheelTickCalibration(state.wheel_ticks),
+        ];
+        for field in fields {
+            if self.state.map_or(true, |old| field(&old) != field(&state)) {
+                on_event(field(&state));
+            }
+        }
+
+        for sensor in status.data() {
+            let slot = &mut self.sensors[sensor.sensor_type() as usize];
+            let calibration = sensor.calibration_status();
+            let faults = sensor.faults();
+            let old = slot.replace(SensorHealth {
+                calibration,
+                faults,
+            });
+            if old.map(|old| old.calibration) != Some(calibration) {
+                on_event(FusionEvent::SensorCalibration {
+                    sensor: sensor.sensor_type(),
+                    status: calibration,
+                });
+            }
+            let raised = faults - old.map(|old| old.faults).unwrap_or_default();
+            if !raised.is_empty() {
+                on_event(FusionEvent::SensorFault {
+                    sensor: sensor.sensor_type(),
+                    faults: raised,
+                });
+            }
+        }
+
+        self.update(state, status.itow(), on_event);
+    }
+
+    /// ESF-ALG only refines the state after the first ESF-STATUS
+    pub fn alg(&mut self, alg: &EsfAlgRef<'_>, mut on_event: impl FnMut(FusionEvent)) {
+        let Some(mut state) = self.state else {
+            return;
+        };
+        let status = alg.flags().status();
+        if state.alignment != Some(status) {
+            state.alignment = Some(status);
+            on_event(FusionEvent::AlignmentStatus(status));
+        }
+        let raised = alg.error() - state.alignment_errors;
+        if !raised.is_empty() {
+            on_event(FusionEvent::AlignmentError(raised));
+        }
+        state.alignment_errors = alg.error();
+        self.update(state, alg.itow(), on_event);
+    }
+
+    fn update(&mut self, state: FusionState, itow: u32, mut on_event: impl FnMut(FusionEvent)) {
+        let now = self.itow.unwrap(itow);
+        let was_calibrated = self.state.is_some_and(|state| state.is_calibrated());
+        let start = *self.start.get_or_insert(now);
+        match (was_calibrated, state.is_calibrated()) {
+            (false, true) => {
+                let after = now.saturating_sub(start) as f64 * 1e-3;
+                self.time_to_calibrated = Some(after);
+                on_event(FusionEvent::Calibrated { after });
+            },
+            (true, false) => {
+                self.start = Some(now);
+                on_event(FusionEvent::CalibrationLost);
+            },
+            _ => {},
+        }
+        self.state = Some(state);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decodes_si_units() {
+        assert_eq!(
+            SensorValue::decode(EsfSensorType::GyroZ, 0xFFF000),
+            Some(SensorValue::AngularRate(-PI / 180.0))
+        );
+        assert_eq!(
+            SensorValue::decode(EsfSensorType::AccX, 0x002800),
+            Some(SensorValue::Acceleration(10.0))
+        );
+        assert_eq!(
+            SensorValue::decode(EsfSensorType::GyroTemp, 0xFFFF9C),
+            Some(SensorValue::Temperature(-1.0))
+        );
+        assert_eq!(
+            SensorValue::decode(EsfSensorType::RearLeftWheelTicks, 0x800005),
+            Some(SensorValue::Ticks {
+                count: 5,
+                backward: true
+            })
+        );
+        assert_eq!(SensorValue::decode(EsfSensorType::Unknown, 1), None);
+    }
+
+    #[test]
+    fn unwraps_time_tags() {
+        let mut tags = TimeTagUnwrapper::with_bits(24);
+        assert_eq!(tags.unwrap(0xFFFF00), 0xFFFF00);
+        assert_eq!(tags.unwrap(0x000010), 0x1000010);
+        // Out of order, before the wrap
+        assert_eq!(tags.unwrap(0xFFFFF0), 0xFFFFF0);
+        assert_eq!(tags.unwrap(0x000020), 0x1000020);
+        assert_eq!(tags.unwrap(0x700000), 0x1700000);
+        assert_eq!(tags.unwrap(0xE00000), 0x1E00000);
+        assert_eq!(tags.unwrap(0x000010), 0x2000010);
+
+        let mut itow = TimeTagUnwrapper::new(WEEK_MS);
+        itow.unwrap(604_799_900);
+        assert_eq!(itow.unwrap(100), WEEK_MS + 100);
+
+        let mut wide = TimeTagUnwrapper::with_bits(63);
+        assert_eq!(wide.unwrap(u32::MAX), u64::from(u32::MAX));
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_time_tags_of_64_bits() {
+        TimeTagUnwrapper::with_bits(64);
+    }
+}
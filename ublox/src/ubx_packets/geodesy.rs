@@ -0,0 +1,351 @@
+use super::packets::*;
+use super::types::{Position, PositionECEF};
+
+/// WGS84 semi-major axis in meters
+pub const WGS84_A: f64 = 6_378_137.0;
+/// WGS84 flattening
+pub const WGS84_F: f64 = 1.0 / 298.257_223_563;
+/// WGS84 first eccentricity squared
+pub const WGS84_E2: f64 = WGS84_F * (2.0 - WGS84_F);
+/// Mean earth radius in meters, used for great-circle distances
+pub const EARTH_MEAN_RADIUS: f64 = 6_371_008.8;
+
+/// Position in a local East-North-Up frame, in meters
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionENU {
+    pub east: f64,
+    pub north: f64,
+    pub up: f64,
+}
+
+/// Position in a local North-East-Down frame, in meters
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionNED {
+    pub north: f64,
+    pub east: f64,
+    pub down: f64,
+}
+
+impl From<PositionENU> for PositionNED {
+    fn from(enu: PositionENU) -> Self {
+        Self {
+            north: enu.north,
+            east: enu.east,
+            down: -enu.up,
+        }
+    }
+}
+
+impl From<PositionNED> for PositionENU {
+    fn from(ned: PositionNED) -> Self {
+        Self {
+            east: ned.east,
+            north: ned.north,
+            up: -ned.down,
+        }
+    }
+}
+
+impl Position {
+    /// Converts to ECEF, `alt` is taken as the height above the WGS84 ellipsoid
+    pub fn to_ecef(&self) -> PositionECEF {
+        let (sin_lat, cos_lat) = libm::sincos(self.lat.to_radians());
+        let (sin_lon, cos_lon) = libm::sincos(self.lon.to_radians());
+        let n = WGS84_A / libm::sqrt(1.0 - WGS84_E2 * sin_lat * sin_lat);
+        PositionECEF {
+            x: (n + self.alt) * cos_lat * cos_lon,
+            y: (n + self.alt) * cos_lat * sin_lon,
+            z: (n * (1.0 - WGS84_E2) + self.alt) * sin_lat,
+        }
+    }
+
+    /// Position relative to `reference` in its local East-North-Up frame
+    pub fn to_enu(&self, reference: &Position) -> PositionENU {
+        self.to_ecef().to_enu(reference)
+    }
+
+    /// Position relative to `reference` in its local North-East-Down frame
+    pub fn to_ned(&self, reference: &Position) -> PositionNED {
+        self.to_enu(reference).into()
+    }
+
+    /// Great-circle distance in meters to `other`, ignoring altitude.
+    /// Uses a spherical earth, so the error can reach about 0.5%.
+    pub fn distance(&self, other: &Position) -> f64 {
+        let lat1 = self.lat.to_radians();
+        let lat2 = other.lat.to_radians();
+        let dlat = lat2 - lat1;
+        let dlon = (other.lon - self.lon).to_radians();
+        let a = sq(libm::sin(dlat / 2.0))
+            + libm::cos(lat1) * libm::cos(lat2) * sq(libm::sin(dlon / 2.0));
+        2.0 * EARTH_MEAN_RADIUS * libm::asin(libm::sqrt(a).min(1.0))
+    }
+
+    /// Initial bearing to `other` in degrees, clockwise from north in 0..360
+    pub fn bearing(&self, other: &Position) -> f64 {
+        let lat1 = self.lat.to_radians();
+        let lat2 = other.lat.to_radians();
+        let dlon = (other.lon - self.lon).to_radians();
+        let y = libm::sin(dlon) * libm::cos(lat2);
+        let x =
+            libm::cos(lat1) * libm::sin(lat2) - libm::sin(lat1) * libm::cos(lat2) * libm::cos(dlon);
+        let bearing = libm::atan2(y, x).to_degrees() % 360.0;
+        if bearing < 0.0 {
+            bearing + 360.0
+        } else {
+            bearing
+        }
+    }
+}
+
+impl PositionECEF {
+    /// Converts to WGS84 latitude, longitude and height above the ellipsoid
+    pub fn to_llh(&self) -> Position {
+        let p = libm::hypot(self.x, self.y);
+        let lon = libm::atan2(self.y, self.x);
+        let mut lat = libm::atan2(self.z, p * (1.0 - WGS84_E2));
+        let mut alt = 0.0;
+        for _ in 0..10 {
+            let (sin_lat, cos_lat) = libm::sincos(lat);
+            let n = WGS84_A / libm::sqrt(1.0 - WGS84_E2 * sin_lat * sin_lat);
+            alt = p * cos_lat + self.z * sin_lat - WGS84_A * WGS84_A / n;
+            let next = libm::atan2(self.z, p * (1.0 - WGS84_E2 * n / (n + alt)));
+            let done = libm::fabs(next - lat) < 1e-14;
+            lat = next;
+            if done {
+                break;
+            }
+        }
+        Position {
+            lon: lon.to_degrees(),
+            lat: lat.to_degrees(),
+            alt,
+        }
+    }
+
+    /// Position relative to `reference` in its local East-North-Up frame
+    pub fn to_enu(&self, reference: &Position) -> PositionENU {
+        let origin = reference.to_ecef();
+        let (dx, dy, dz) = (self.x - origin.x, self.y - origin.y, self.z - origin.z);
+        let (sin_lat, cos_lat) = libm::sincos(reference.lat.to_radians());
+        let (sin_lon, cos_lon) = libm::sincos(reference.lon.to_radians());
+        PositionENU {
+            east: -sin_lon * dx + cos_lon * dy,
+            north: -sin_lat * cos_lon * dx - sin_lat * sin_lon * dy + cos_lat * dz,
+            up: cos_lat * cos_lon * dx + cos_lat * sin_lon * dy + sin_lat * dz,
+        }
+    }
+
+    /// Position relative to `reference` in its local North-East-Down frame
+    pub fn to_ned(&self, reference: &Position) -> PositionNED {
+        self.to_enu(reference).into()
+    }
+
+    /// Straight line distance in meters to `other`
+    pub fn distance(&self, other: &PositionECEF) -> f64 {
+        libm::sqrt(sq(self.x - other.x) + sq(self.y - other.y) + sq(self.z - other.z))
+    }
+
+    /// Azimuth (clockwise from north, 0..360) and elevation in degrees
+    /// as seen from `observer`, like the satellite angles of NAV-SAT
+    pub fn azimuth_elevation(&self, observer: &Position) -> (f64, f64) {
+        let enu = self.to_enu(observer);
+        let azimuth = libm::atan2(enu.east, enu.north).to_degrees();
+        let elevation = libm::atan2(enu.up, enu.horizontal_distance()).to_degrees();
+        let azimuth = if azimuth < 0.0 {
+            azimuth + 360.0
+        } else {
+            azimuth
+        };
+        (azimuth, elevation)
+    }
+}
+
+impl PositionENU {
+    /// Converts back to ECEF, given the origin of the local frame
+    pub fn to_ecef(&self, reference: &Position) -> PositionECEF {
+        let origin = reference.to_ecef();
+        let (sin_lat, cos_lat) = libm::sincos(reference.lat.to_radians());
+        let (sin_lon, cos_lon) = libm::sincos(reference.lon.to_radians());
+        PositionECEF {
+            x: origin.x - sin_lon * self.east - sin_lat * cos_lon * self.north
+                + cos_lat * cos_lon * self.up,
+            y: origin.y + cos_lon * self.east - sin_lat * sin_lon * self.north
+                + cos_lat * sin_lon * self.up,
+            z: origin.z + cos_lat * self.north + sin_lat * self.up,
+        }
+    }
+
+    /// Horizontal distance from the origin in meters
+    pub fn horizontal_distance(&self) -> f64 {
+        libm::hypot(self.east, self.north)
+    }
+}
+
+impl PositionNED {
+    /// Converts back to ECEF, given the origin of the local frame
+    pub fn to_ecef(&self, reference: &Position) -> PositionECEF {
+        PositionENU::from(*self).to_ecef(reference)
+    }
+}
+
+/// Uses the height above the ellipsoid, unlike `From<&NavHpPosLlhRef> for Position`
+impl<'a> From<&NavHpPosLlhRef<'a>> for PositionECEF {
+    fn from(packet: &NavHpPosLlhRef<'a>) -> Self {
+        Position {
+            lon: packet.lon_degrees() + packet.lon_hp_degrees(),
+            lat: packet.lat_degrees() + packet.lat_hp_degrees(),
+            alt: packet.height_meters() + packet.height_hp_meters() * 1e-3,
+        }
+        .to_ecef()
+    }
+}
+
+/// `alt` is the height above the ellipsoid
+impl<'a> From<&NavHpPosEcefRef<'a>> for Position {
+    fn from(packet: &NavHpPosEcefRef<'a>) -> Self {
+        PositionECEF::from(packet).to_llh()
+    }
+}
+
+/// Fixed position of a base station, `alt` being the height above the ellipsoid in LLA mode
+impl<'a> From<&CfgTmode3Ref<'a>> for PositionECEF {
+    fn from(cfg: &CfgTmode3Ref<'a>) -> Self {
+        let hp = |value: f32| f64::from(value) * 1e-4;
+        if cfg.flags().contains(CfgTmode3Flags::LLA) {
+            // Latitude and longitude are in 1e-7 degrees, the getters scale by 1e-2
+            Position {
+                lat: cfg.ecef_x_or_lat() * 1e-5 + f64::from(cfg.ecef_x_or_lat_hp()) * 1e-9,
+                lon: cfg.ecef_y_or_lon() * 1e-5 + f64::from(cfg.ecef_y_or_lon_hp()) * 1e-9,
+                alt: cfg.ecef_z_or_alt() + hp(cfg.ecef_z_or_alt_hp()),
+            }
+            .to_ecef()
+        } else {
+            PositionECEF {
+                x: cfg.ecef_x_or_lat() + hp(cfg.ecef_x_or_lat_hp()),
+                y: cfg.ecef_y_or_lon() + hp(cfg.ecef_y_or_lon_hp()),
+                z: cfg.ecef_z_or_alt() + hp(cfg.ecef_z_or_alt_hp()),
+            }
+        }
+    }
+}
+
+/// Current survey-in mean position
+impl<'a> From<&TimSvinRef<'a>> for PositionECEF {
+    fn from(svin: &TimSvinRef<'a>) -> Self {
+        PositionECEF {
+            x: f64::from(svin.mean_x()) * 1e-2,
+            y: f64::from(svin.mean_y()) * 1e-2,
+            z: f64::from(svin.mean_z()) * 1e-2,
+        }
+    }
+}
+
+/// Current survey-in mean position, including the high precision components
+impl<'a> From<&NavSvinRef<'a>> for PositionECEF {
+    fn from(svin: &NavSvinRef<'a>) -> Self {
+        PositionECEF {
+            x: svin.mean_x_m() + svin.mean_x_hp_m(),
+            y: svin.mean_y_m() + svin.mean_y_hp_m(),
+            z: svin.mean_z_m() + svin.mean_z_hp_m(),
+        }
+    }
+}
+
+fn sq(x: f64) -> f64 {
+    x * x
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64, tolerance: f64) {
+        assert!(libm::fabs(a - b) < tolerance, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn ecef_llh_round_trip() {
+        for (lat, lon, alt) in [
+            (48.137_154, 11.576_124, 519.0),
+            (-33.868_82, 151.209_29, -20.0),
+            (89.999_999, -120.0, 3_000.0),
+            (0.0, 180.0, 0.0),
+        ] {
+            let pos = Position { lat, lon, alt };
+            let back = pos.to_ecef().to_llh();
+            assert_close(back.lat, lat, 1e-9);
+            assert_close(back.lon, lon, 1e-9);
+            assert_close(back.alt, alt, 1e-4);
+        }
+
+        let equator = Position {
+            lat: 0.0,
+            lon: 90.0,
+            alt: 0.0,
+        }
+        .to_ecef();
+        assert_close(equator.x, 0.0, 1e-6);
+        assert_close(equator.y, WGS84_A, 1e-6);
+        assert_close(equator.z, 0.0, 1e-6);
+    }
+
+    #[test]
+    fn local_frames() {
+        let reference = Position {
+            lat: 52.0,
+            lon: 5.0,
+            alt: 50.0,
+        };
+        let up = Position {
+            alt: 60.0,
+            ..reference
+        };
+        let enu = up.to_enu(&reference);
+        assert_close(enu.east, 0.0, 1e-6);
+        assert_close(enu.north, 0.0, 1e-6);
+        assert_close(enu.up, 10.0, 1e-6);
+        assert_close(up.to_ned(&reference).down, -10.0, 1e-6);
+
+        let north = Position {
+            lat: 52.001,
+            ..reference
+        };
+        let enu = north.to_enu(&reference);
+        assert!(enu.north > 111.0 && enu.north < 112.0);
+        assert_close(enu.east, 0.0, 1e-6);
+
+        let back = enu.to_ecef(&reference);
+        assert_close(back.distance(&north.to_ecef()), 0.0, 1e-6);
+
+        let (_, elevation) = up.to_ecef().azimuth_elevation(&reference);
+        assert_close(elevation, 90.0, 1e-6);
+        let west = PositionENU {
+            east: -1000.0,
+            north: 0.0,
+            up: 1000.0,
+        };
+        let (azimuth, elevation) = west.to_ecef(&reference).azimuth_elevation(&reference);
+        assert_close(azimuth, 270.0, 1e-6);
+        assert_close(elevation, 45.0, 1e-6);
+    }
+
+    #[test]
+    fn distance_and_bearing() {
+        let a = Position {
+            lat: 0.0,
+            lon: 0.0,
+            alt: 0.0,
+        };
+        let b = Position {
+            lat: 0.0,
+            lon: 1.0,
+            alt: 0.0,
+        };
+        assert_close(a.distance(&b), 111_195.08, 0.01);
+        assert_close(a.bearing(&b), 90.0, 1e-9);
+        assert_close(b.bearing(&a), 270.0, 1e-9);
+    }
+}
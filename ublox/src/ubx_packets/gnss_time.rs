@@ -0,0 +1,505 @@
+use super::packets::*;
+use crate::error::DateTimeError;
+use chrono::prelude::*;
+use core::convert::TryFrom;
+
+const NANOS_PER_SEC: i64 = 1_000_000_000;
+const NANOS_PER_WEEK: i64 = 7 * 86_400 * NANOS_PER_SEC;
+
+/// Galileo System Time week 0 starts at GPS week 1024 (1999-08-22)
+const GAL_WEEK_OFFSET: i64 = 1024;
+/// BeiDou Time week 0 starts at GPS week 1356 (2006-01-01)
+const BDS_WEEK_OFFSET: i64 = 1356;
+/// BDT = GPST - 14 s
+const BDS_GPS_OFFSET: i64 = -14 * NANOS_PER_SEC;
+/// TAI = GPST + 19 s
+const TAI_GPS_OFFSET: i64 = 19 * NANOS_PER_SEC;
+/// GLONASS time = UTC(SU) + 3 h
+const GLONASS_UTC_OFFSET: i64 = 3 * 3600 * NANOS_PER_SEC;
+
+fn gps_epoch() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(1980, 1, 6)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+}
+
+/// Time scale of a [GnssTime]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimeScale {
+    Gps,
+    Galileo,
+    BeiDou,
+    Glonass,
+    Utc,
+    Tai,
+}
+
+/// A point in time as read on the clock of a [TimeScale].
+///
+/// Internally the time is kept as nanoseconds since 1980-01-06 00:00:00 in
+/// the calendar of its time scale, so that e.g. a GPS time and a UTC time
+/// of the same instant differ by the number of leap seconds.
+/// Week numbers and time of week follow the native numbering of each
+/// system: Galileo and BeiDou weeks start at their own epoch, while UTC,
+/// TAI and GLONASS weeks are counted from the GPS epoch, as u-blox reports them.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GnssTime {
+    scale: TimeScale,
+    nanos: i64,
+}
+
+impl GnssTime {
+    /// Time from nanoseconds since 1980-01-06 00:00:00 in the calendar of `scale`
+    pub const fn new(scale: TimeScale, nanos: i64) -> Self {
+        Self { scale, nanos }
+    }
+
+    /// Time from a week number and time of week in nanoseconds
+    pub fn from_week_tow(scale: TimeScale, week: u32, tow_nanos: u64) -> Self {
+        let week = i64::from(week) + week_offset(scale);
+        Self::new(scale, week * NANOS_PER_WEEK + tow_nanos as i64)
+    }
+
+    /// Time from a week number and time of week in seconds
+    pub fn from_week_tow_secs(scale: TimeScale, week: u32, tow: f64) -> Self {
+        let tow_nanos = (tow * 1e9 + 0.5) as u64;
+        Self::from_week_tow(scale, week, tow_nanos)
+    }
+
+    /// Time from a calendar date and time read on the clock of `scale`
+    pub fn from_naive_datetime(scale: TimeScale, datetime: NaiveDateTime) -> Option<Self> {
+        let nanos = (datetime - gps_epoch()).num_nanoseconds()?;
+        Some(Self::new(scale, nanos))
+    }
+
+    /// Time of the last rising edge of a TIM-TM2 message, with `scale` the time
+    /// scale of the GNSS time base as configured with CFG-TP5 or CFG-TP-TIMEGRID_TP1
+    pub fn from_tim_tm2(packet: &TimTm2Ref, scale: TimeScale) -> Result<Self, DateTimeError> {
+        let flags = packet.flags();
+        if !flags.time_valid() {
+            return Err(DateTimeError::NotValid);
+        }
+        let scale = match flags.time_base() {
+            TimTm2TimeBase::Gnss => scale,
+            TimTm2TimeBase::Utc => TimeScale::Utc,
+            TimTm2TimeBase::Receiver => return Err(DateTimeError::UnsupportedTimeBase),
+        };
+        let tow_nanos = u64::from(packet.tow_ms_r()) * 1_000_000 + u64::from(packet.tow_sub_ms_r());
+        Ok(GnssTime::from_week_tow(
+            scale,
+            u32::from(packet.wn_r()),
+            tow_nanos,
+        ))
+    }
+
+    pub fn scale(&self) -> TimeScale {
+        self.scale
+    }
+
+    /// Nanoseconds since 1980-01-06 00:00:00 in the calendar of the time scale
+    pub fn nanos(&self) -> i64 {
+        self.nanos
+    }
+
+    /// Week number in the native numbering of the time scale
+    pub fn week(&self) -> i64 {
+        self.nanos.div_euclid(NANOS_PER_WEEK) - week_offset(self.scale)
+    }
+
+    /// Time of week in nanoseconds
+    pub fn tow_nanos(&self) -> u64 {
+        self.nanos.rem_euclid(NANOS_PER_WEEK) as u64
+    }
+
+    /// Time of week in seconds
+    pub fn tow(&self) -> f64 {
+        self.tow_nanos() as f64 * 1e-9
+    }
+
+    /// Calendar date and time as read on the clock of the time scale
+    pub fn to_naive_datetime(&self) -> NaiveDateTime {
+        gps_epoch() + chrono::Duration::nanoseconds(self.nanos)
+    }
+
+    /// Same instant expressed in another time scale.
+    /// Leap seconds are only needed when converting from or to UTC or GLONASS.
+    pub fn to_scale(&self, scale: TimeScale, leap_seconds: &LeapSeconds) -> Self {
+        let gps = match self.scale {
+            TimeScale::Gps | TimeScale::Galileo => self.nanos,
+            TimeScale::BeiDou => self.nanos - BDS_GPS_OFFSET,
+            TimeScale::Tai => self.nanos - TAI_GPS_OFFSET,
+            TimeScale::Utc => leap_seconds.utc_to_gps(self.nanos),
+            TimeScale::Glonass => leap_seconds.utc_to_gps(self.nanos - GLONASS_UTC_OFFSET),
+        };
+        let nanos = match scale {
+            TimeScale::Gps | TimeScale::Galileo => gps,
+            TimeScale::BeiDou => gps + BDS_GPS_OFFSET,
+            TimeScale::Tai => gps + TAI_GPS_OFFSET,
+            TimeScale::Utc => leap_seconds.gps_to_utc(gps),
+            TimeScale::Glonass => leap_seconds.gps_to_utc(gps) + GLONASS_UTC_OFFSET,
+        };
+        Self::new(scale, nanos)
+    }
+
+    pub fn to_utc(&self, leap_seconds: &LeapSeconds) -> Self {
+        self.to_scale(TimeScale::Utc, leap_seconds)
+    }
+
+    pub fn to_tai(&self, leap_seconds: &LeapSeconds) -> Self {
+        self.to_scale(TimeScale::Tai, leap_seconds)
+    }
+
+    pub fn to_gps(&self, leap_seconds: &LeapSeconds) -> Self {
+        self.to_scale(TimeScale::Gps, leap_seconds)
+    }
+
+    /// UTC date and time of this instant
+    pub fn to_datetime_utc(&self, leap_seconds: &LeapSeconds) -> DateTime<Utc> {
+        let utc = self.to_utc(leap_seconds).to_naive_datetime();
+        DateTime::from_naive_utc_and_offset(utc, Utc)
+    }
+}
+
+fn week_offset(scale: TimeScale) -> i64 {
+    match scale {
+        TimeScale::Galileo => GAL_WEEK_OFFSET,
+        TimeScale::BeiDou => BDS_WEEK_OFFSET,
+        _ => 0,
+    }
+}
+
+impl From<DateTime<Utc>> for GnssTime {
+    fn from(datetime: DateTime<Utc>) -> Self {
+        let nanos = (datetime.naive_utc() - gps_epoch())
+            .num_nanoseconds()
+            .unwrap_or(i64::MAX);
+        Self::new(TimeScale::Utc, nanos)
+    }
+}
+
+/// GPS - UTC offset in seconds, optionally with a leap second event
+/// at which the offset changes.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LeapSeconds {
+    before: i8,
+    after: i8,
+    /// GPS time (ns since the GPS epoch) from which `after` applies
+    event: i64,
+}
+
+impl LeapSeconds {
+    /// Constant GPS - UTC offset
+    pub const fn new(gps_utc: i8) -> Self {
+        Self {
+            before: gps_utc,
+            after: gps_utc,
+            event: i64::MIN,
+        }
+    }
+
+    /// Offset `before` the leap second event at GPS time `event`, `after` from then on
+    pub fn with_event(before: i8, after: i8, event: &GnssTime) -> Self {
+        let event = event.to_gps(&Self::new(before)).nanos;
+        Self {
+            before,
+            after,
+            event,
+        }
+    }
+
+    /// GPS - UTC offset in seconds at the given time
+    pub fn gps_utc_at(&self, time: &GnssTime) -> i8 {
+        self.gps_utc_at_gps(time.to_gps(self).nanos)
+    }
+
+    fn gps_utc_at_gps(&self, gps: i64) -> i8 {
+        if gps >= self.event {
+            self.after
+        } else {
+            self.before
+        }
+    }
+
+    fn gps_to_utc(&self, gps: i64) -> i64 {
+        gps - i64::from(self.gps_utc_at_gps(gps)) * NANOS_PER_SEC
+    }
+
+    fn utc_to_gps(&self, utc: i64) -> i64 {
+        let before = utc + i64::from(self.before) * NANOS_PER_SEC;
+        if before >= self.event {
+            utc + i64::from(self.after) * NANOS_PER_SEC
+        } else {
+            before
+        }
+    }
+}
+
+impl<'a> TryFrom<&NavTimeLsRef<'a>> for LeapSeconds {
+    type Error = DateTimeError;
+    fn try_from(packet: &NavTimeLsRef<'a>) -> Result<Self, Self::Error> {
+        let valid = packet.valid();
+        if !valid.contains(NavTimeLsFlags::VALID_CURR_LS) {
+            return Err(DateTimeError::NotValid);
+        }
+        let current = packet.current_ls();
+        if !valid.contains(NavTimeLsFlags::VALID_TIME_TO_LS_EVENT) {
+            return Ok(Self::new(current));
+        }
+
+        // iTOW carries no week number, so place the event next to the end of
+        // day DN, which for DN 7 already falls into the following week.
+        // UTC midnight is within seconds of the GPS time of the event.
+        let tow = i64::from(packet.itow()) * 1_000_000
+            + i64::from(packet.time_to_ls_event()) * NANOS_PER_SEC;
+        let end_of_day = GnssTime::try_from(packet)?.nanos();
+        let mut offset = (tow - end_of_day).rem_euclid(NANOS_PER_WEEK);
+        if offset >= NANOS_PER_WEEK / 2 {
+            offset -= NANOS_PER_WEEK;
+        }
+        let event = GnssTime::new(TimeScale::Gps, end_of_day + offset);
+
+        if packet.time_to_ls_event() > 0 {
+            Ok(Self::with_event(
+                current,
+                current.saturating_add(packet.ls_change()),
+                &event,
+            ))
+        } else {
+            Ok(Self::with_event(
+                current.saturating_sub(packet.ls_change()),
+                current,
+                &event,
+            ))
+        }
+    }
+}
+
+impl<'a> TryFrom<&RxmRawxRef<'a>> for LeapSeconds {
+    type Error = DateTimeError;
+    fn try_from(packet: &RxmRawxRef<'a>) -> Result<Self, Self::Error> {
+        if !packet.rec_stat().contains(RecStatFlags::LEAP_SEC) {
+            return Err(DateTimeError::NotValid);
+        }
+        Ok(Self::new(packet.leap_s()))
+    }
+}
+
+impl<'a> TryFrom<&NavPvtRef<'a>> for GnssTime {
+    type Error = DateTimeError;
+    fn try_from(sol: &NavPvtRef<'a>) -> Result<Self, Self::Error> {
+        Ok(DateTime::<Utc>::try_from(sol)?.into())
+    }
+}
+
+impl<'a> TryFrom<&NavTimeUTCRef<'a>> for GnssTime {
+    type Error = DateTimeError;
+    fn try_from(packet: &NavTimeUTCRef<'a>) -> Result<Self, Self::Error> {
+        if !packet.valid().contains(NavTimeUtcFlags::VALID_UTC) {
+            return Err(DateTimeError::NotValid);
+        }
+        let datetime = naive_datetime(
+            packet.year(),
+            packet.month(),
+            packet.day(),
+            packet.hour(),
+            packet.min(),
+            packet.sec(),
+        )?;
+        if packet.nanos().unsigned_abs() >= NANOS_PER_SEC as u32 {
+            return Err(DateTimeError::InvalidNanoseconds);
+        }
+        let datetime = datetime + chrono::Duration::nanoseconds(i64::from(packet.nanos()));
+        GnssTime::from_naive_datetime(TimeScale::Utc, datetime).ok_or(DateTimeError::InvalidDate)
+    }
+}
+
+/// Time of the next time pulse
+impl<'a> TryFrom<&TimTpRef<'a>> for GnssTime {
+    type Error = DateTimeError;
+    fn try_from(packet: &TimTpRef<'a>) -> Result<Self, Self::Error> {
+        let scale = match packet.flags().time_base() {
+            TimTpTimeBase::Utc => TimeScale::Utc,
+            TimTpTimeBase::Gnss => match packet.ref_info().time_ref_gnss() {
+                Some(TimTpRefInfoTimeRefGnss::Gps) => TimeScale::Gps,
+                Some(TimTpRefInfoTimeRefGnss::Glo) => TimeScale::Glonass,
+                Some(TimTpRefInfoTimeRefGnss::Bds) => TimeScale::BeiDou,
+                Some(TimTpRefInfoTimeRefGnss::Gal) => TimeScale::Galileo,
+                _ => return Err(DateTimeError::UnsupportedTimeBase),
+            },
+        };
+        // tow_sub_ms is in units of 2^-32 ms
+        let sub_ms_nanos = (u64::from(packet.tow_sub_ms()) * 1_000_000) >> 32;
+        let tow_nanos = u64::from(packet.tow_ms()) * 1_000_000 + sub_ms_nanos;
+        Ok(GnssTime::from_week_tow(
+            scale,
+            u32::from(packet.week()),
+            tow_nanos,
+        ))
+    }
+}
+
+/// Time of the last rising edge, for the UTC time base only, as the GNSS
+/// time base does not tell which system it follows: see [GnssTime::from_tim_tm2]
+impl<'a> TryFrom<&TimTm2Ref<'a>> for GnssTime {
+    type Error = DateTimeError;
+    fn try_from(packet: &TimTm2Ref<'a>) -> Result<Self, Self::Error> {
+        match packet.flags().time_base() {
+            TimTm2TimeBase::Utc => GnssTime::from_tim_tm2(packet, TimeScale::Utc),
+            _ => Err(DateTimeError::UnsupportedTimeBase),
+        }
+    }
+}
+
+/// GNSS top of second preceding the last time pulse
+#[cfg(feature = "ubx_proto23")]
+impl<'a> TryFrom<&TimTosRef<'a>> for GnssTime {
+    type Error = DateTimeError;
+    fn try_from(packet: &TimTosRef<'a>) -> Result<Self, Self::Error> {
+        if !packet.flags().contains(TimTosFlags::GNSS_TIME_IS_VALID) {
+            return Err(DateTimeError::NotValid);
+        }
+        let scale = match packet.gnss_id() {
+            0 => TimeScale::Gps,
+            2 => TimeScale::Galileo,
+            3 => TimeScale::BeiDou,
+            6 => TimeScale::Glonass,
+            _ => return Err(DateTimeError::UnsupportedTimeBase),
+        };
+        let tow_nanos = u64::from(packet.tow()) * NANOS_PER_SEC as u64;
+        Ok(GnssTime::from_week_tow(scale, packet.week(), tow_nanos))
+    }
+}
+
+/// Measurement time in receiver local time, aligned to GPS time
+impl<'a> TryFrom<&RxmRawxRef<'a>> for GnssTime {
+    type Error = DateTimeError;
+    fn try_from(packet: &RxmRawxRef<'a>) -> Result<Self, Self::Error> {
+        let tow = packet.rcv_tow();
+        if !(0.0..604_800.0).contains(&tow) {
+            return Err(DateTimeError::InvalidTime);
+        }
+        Ok(GnssTime::from_week_tow_secs(
+            TimeScale::Gps,
+            u32::from(packet.week()),
+            tow,
+        ))
+    }
+}
+
+/// UTC time of the leap second event, or of the last event if none is scheduled:
+/// midnight at the end of day DN (IS-GPS-200, 20.3.3.5.2.4), the first UTC second
+/// with the new offset. The inserted second 23:59:60 itself has no [GnssTime].
+impl<'a> TryFrom<&NavTimeLsRef<'a>> for GnssTime {
+    type Error = DateTimeError;
+    fn try_from(packet: &NavTimeLsRef<'a>) -> Result<Self, Self::Error> {
+        if !packet
+            .valid()
+            .contains(NavTimeLsFlags::VALID_TIME_TO_LS_EVENT)
+        {
+            return Err(DateTimeError::NotValid);
+        }
+        let day = match packet.date_of_ls_gps_dn() {
+            day @ 1..=7 => u64::from(day),
+            _ => return Err(DateTimeError::InvalidDate),
+        };
+        Ok(GnssTime::from_week_tow(
+            TimeScale::Utc,
+            u32::from(packet.date_of_ls_gps_wn()),
+            day * 86_400 * NANOS_PER_SEC as u64,
+        ))
+    }
+}
+
+fn naive_datetime(
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    min: u8,
+    sec: u8,
+) -> Result<NaiveDateTime, DateTimeError> {
+    let date = NaiveDate::from_ymd_opt(i32::from(year), u32::from(month), u32::from(day))
+        .ok_or(DateTimeError::InvalidDate)?;
+    let time = NaiveTime::from_hms_opt(u32::from(hour), u32::from(min), u32::from(sec))
+        .ok_or(DateTimeError::InvalidTime)?;
+    Ok(NaiveDateTime::new(date, time))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, min: u32, sec: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, min, sec)
+            .unwrap()
+    }
+
+    #[test]
+    fn week_numbering() {
+        // GPS week 2000 started on 2018-05-06
+        let gps = GnssTime::from_week_tow(TimeScale::Gps, 2000, 0);
+        assert_eq!(gps.to_naive_datetime(), utc(2018, 5, 6, 0, 0, 0));
+        assert_eq!(gps.week(), 2000);
+        assert_eq!(gps.tow_nanos(), 0);
+
+        let leap = LeapSeconds::new(18);
+        let gal = gps.to_scale(TimeScale::Galileo, &leap);
+        assert_eq!(gal.week(), 976);
+        assert_eq!(gal.tow_nanos(), 0);
+
+        let bds = gps.to_scale(TimeScale::BeiDou, &leap);
+        assert_eq!(bds.week(), 643);
+        assert_eq!(bds.tow(), 604_786.0);
+        assert_eq!(bds.to_gps(&leap), gps);
+    }
+
+    #[test]
+    fn utc_and_tai() {
+        let leap = LeapSeconds::new(18);
+        let gps = GnssTime::from_week_tow_secs(TimeScale::Gps, 2000, 18.5);
+        assert_eq!(
+            gps.to_utc(&leap).to_naive_datetime(),
+            utc(2018, 5, 6, 0, 0, 0) + chrono::Duration::milliseconds(500)
+        );
+        assert_eq!(
+            gps.to_tai(&leap).to_naive_datetime(),
+            utc(2018, 5, 6, 0, 0, 37) + chrono::Duration::milliseconds(500)
+        );
+        let glo = gps.to_scale(TimeScale::Glonass, &leap);
+        assert_eq!(glo.to_naive_datetime().hour(), 3);
+        assert_eq!(glo.to_gps(&leap), gps);
+    }
+
+    #[test]
+    fn leap_second_event() {
+        // 2017-01-01 00:00:00 UTC, GPS - UTC changed from 17 to 18 s
+        let event = GnssTime::from_naive_datetime(TimeScale::Utc, utc(2017, 1, 1, 0, 0, 0))
+            .unwrap()
+            .to_gps(&LeapSeconds::new(17));
+        let leap = LeapSeconds::with_event(17, 18, &event);
+
+        let before = GnssTime::new(TimeScale::Gps, event.nanos() - NANOS_PER_SEC);
+        assert_eq!(leap.gps_utc_at(&before), 17);
+        assert_eq!(
+            before.to_utc(&leap).to_naive_datetime(),
+            utc(2016, 12, 31, 23, 59, 59)
+        );
+        assert_eq!(leap.gps_utc_at(&event), 18);
+        assert_eq!(
+            event.to_utc(&leap).to_naive_datetime(),
+            utc(2016, 12, 31, 23, 59, 59)
+        );
+        let after = GnssTime::new(TimeScale::Gps, event.nanos() + NANOS_PER_SEC);
+        assert_eq!(
+            after.to_utc(&leap).to_naive_datetime(),
+            utc(2017, 1, 1, 0, 0, 0)
+        );
+        assert_eq!(after.to_utc(&leap).to_gps(&leap), after);
+    }
+}
@@ -0,0 +1,359 @@
+//! Jamming, interference and spoofing monitoring
+//!
+//! [InterferenceMonitor] combines the jamming and spoofing detection of the
+//! receiver, from MON-HW, MON-RF, NAV-STATUS and SEC-SIG, with thresholds on
+//! the RF front end and on the C/N0 of the tracked satellites, and assesses
+//! each navigation epoch.
+
+use bitflags::bitflags;
+
+use super::packets::*;
+use crate::PacketRef;
+
+/// Severity of a jamming or spoofing assessment
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ThreatLevel {
+    #[default]
+    None,
+    Warning,
+    Critical,
+}
+
+bitflags! {
+    /// Reasons of an [InterferenceAssessment]
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct InterferenceIndications: u16 {
+        /// The receiver reports jamming in MON-HW, MON-RF or SEC-SIG
+        const RECEIVER_JAMMING = 0x01;
+        /// The CW jamming indicator exceeds its threshold
+        const JAM_INDICATOR = 0x02;
+        /// The AGC count is below its threshold, the front end reducing its
+        /// gain for a strong in-band signal
+        const AGC = 0x04;
+        /// The noise level exceeds its threshold
+        const NOISE = 0x08;
+        /// The mean C/N0 is below its threshold
+        const LOW_CNO = 0x10;
+        /// The receiver reports spoofing in NAV-STATUS or SEC-SIG
+        const RECEIVER_SPOOFING = 0x20;
+        /// The C/N0 of the satellites is uniform, as if sent by a single transmitter
+        const UNIFORM_CNO = 0x40;
+        /// The antenna is shorted or open
+        const ANTENNA = 0x80;
+    }
+}
+
+/// Thresholds of [InterferenceMonitor]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InterferenceThresholds {
+    /// CW jamming indicator of MON-HW and MON-RF, 0 to 255, above which
+    /// jamming is indicated
+    pub jam_indicator: u8,
+    /// AGC count, 0 to 8191, below which jamming is indicated
+    pub agc_count: u16,
+    /// Noise level per ms above which jamming is indicated
+    pub noise_per_ms: u16,
+    /// Mean C/N0 in dBHz below which jamming is indicated
+    pub cno_mean: f64,
+    /// Standard deviation of the C/N0 in dB below which spoofing is indicated
+    pub cno_std_dev: f64,
+    /// Tracked satellites needed to evaluate the C/N0
+    pub min_satellites: usize,
+}
+
+impl Default for InterferenceThresholds {
+    fn default() -> Self {
+        Self {
+            jam_indicator: 50,
+            agc_count: 1000,
+            noise_per_ms: 150,
+            cno_mean: 30.0,
+            cno_std_dev: 2.0,
+            min_satellites: 6,
+        }
+    }
+}
+
+/// C/N0 statistics of the tracked satellites of NAV-SAT
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CnoStats {
+    pub satellites: usize,
+    /// Mean C/N0 in dBHz
+    pub mean: f64,
+    /// Standard deviation in dB
+    pub std_dev: f64,
+    /// Highest C/N0 in dBHz
+    pub max: u8,
+}
+
+impl CnoStats {
+    /// Statistics of the satellites with a signal, `None` without any
+    pub fn new(nav_sat: &NavSatRef<'_>) -> Option<Self> {
+        let cno = || nav_sat.svs().map(|sv| sv.cno()).filter(|&cno| cno > 0);
+        let satellites = cno().count();
+        if satellites == 0 {
+            return None;
+        }
+        let mean = cno().map(f64::from).sum::<f64>() / satellites as f64;
+        let variance = cno()
+            .map(|cno| (f64::from(cno) - mean) * (f64::from(cno) - mean))
+            .sum::<f64>()
+            / satellites as f64;
+        Some(Self {
+            satellites,
+            mean,
+            std_dev: libm::sqrt(variance),
+            max: cno().max().unwrap_or(0),
+        })
+    }
+}
+
+/// Assessment of a navigation epoch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InterferenceAssessment {
+    /// GPS time of week of the epoch in ms
+    pub itow: u32,
+    pub jamming: ThreatLevel,
+    pub spoofing: ThreatLevel,
+    pub indications: InterferenceIndications,
+    pub cno: Option<CnoStats>,
+}
+
+/// A change reported by [InterferenceMonitor]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InterferenceEvent {
+    Jamming(ThreatLevel),
+    Spoofing(ThreatLevel),
+    Antenna(AntennaStatus),
+    /// The assessment of each epoch
+    Epoch(InterferenceAssessment),
+}
+
+/// Front end state of MON-HW or of an RF block of MON-RF
+#[derive(Debug, Clone, Copy)]
+struct FrontEnd {
+    jamming: JammingState,
+    jam_ind: u8,
+    agc_cnt: u16,
+    noise_per_ms: u16,
+    antenna: AntennaStatus,
+}
+
+/// Assesses jamming and spoofing at each navigation epoch
+///
+/// The latest MON-HW, MON-RF, NAV-STATUS, SEC-SIG and NAV-SAT are used for
+/// each epoch, ended by NAV-EOE or [InterferenceMonitor::end_epoch].
+#[derive(Debug, Clone, Default)]
+pub struct InterferenceMonitor {
+    thresholds: InterferenceThresholds,
+    /// Interference monitor of the receiver, from CFG-ITFM
+    detection_enabled: Option<bool>,
+    hw: Option<FrontEnd>,
+    rf: Option<FrontEnd>,
+    nav_spoofing: Option<SpoofingState>,
+    /// SEC-SIG states of the enabled detections
+    sec_jamming: Option<JammingState>,
+    sec_spoofing: Option<SpoofingState>,
+    cno: Option<CnoStats>,
+    antenna: Option<AntennaStatus>,
+    latest: Option<InterferenceAssessment>,
+}
+
+impl InterferenceMonitor {
+    pub fn new(thresholds: InterferenceThresholds) -> Self {
+        Self {
+            thresholds,
+            ..Default::default()
+        }
+    }
+
+    /// Assessment of the latest epoch
+    pub fn latest(&self) -> Option<&InterferenceAssessment> {
+        self.latest.as_ref()
+    }
+
+    /// Whether the interference monitor of the receiver is enabled, for
+    /// receivers configured with the CFG-ITFM keys instead of UBX-CFG-ITFM.
+    /// The jamming states of MON-HW and MON-RF are ignored when disabled.
+    pub fn set_detection_enabled(&mut self, enabled: bool) {
+        self.detection_enabled = Some(enabled);
+    }
+
+    /// Handles the monitored packets, ignoring others
+    pub fn handle(&mut self, packet: &PacketRef<'_>, mut on_event: impl FnMut(InterferenceEvent)) {
+        match packet {
+            PacketRef::CfgItfm(cfg) => self.set_detection_enabled(cfg.config().enabled()),
+            PacketRef::MonHw(hw) => {
+                self.hw = Some(FrontEnd {
+                    jamming: hw.flags().jamming_state(),
+                    jam_ind: hw.jam_ind(),
+                    agc_cnt: hw.agc_cnt(),
+                    noise_per_ms: hw.noise_per_ms(),
+                    antenna: hw.a_status(),
+                });
+                // MON-RF reports the antenna per RF band once available
+                if self.rf.is_none() {
+                    self.antenna(hw.a_status(), &mut on_event);
+                }
+            },
+            #[cfg(any(feature = "ubx_proto27", feature = "ubx_proto31"))]
+            PacketRef::MonRf(rf) => {
+                // The worst of the RF bands
+                self.rf = rf
+                    .blocks()
+                    .map(|block| FrontEnd {
+                        jamming: block.jamming_state(),
+                        jam_ind: block.jam_ind(),
+                        agc_cnt: block.agc_cnt(),
+                        noise_per_ms: block.noise_per_ms(),
+                        antenna: block.ant_status(),
+                    })
+                    .reduce(|a, b| FrontEnd {
+                        jamming: a.jamming.max(b.jamming),
+                        jam_ind: a.jam_ind.max(b.jam_ind),
+                        agc_cnt: a.agc_cnt.min(b.agc_cnt),
+                        noise_per_ms: a.noise_per_ms.max(b.noise_per_ms),
+                        antenna: if is_antenna_fault(a.antenna) {
+                            a.antenna
+                        } else {
+                            b.antenna
+                        },
+                    });
+                if let Some(rf) = self.rf {
+                    self.antenna(rf.antenna, &mut on_event);
+                }
+            },
+            #[cfg(any(feature = "ubx_proto27", feature = "ubx_proto31"))]
+            PacketRef::SecSig(sig) => {
+                self.sec_jamming = sig.jamming_detection_enabled().then(|| sig.jamming_state());
+                self.sec_spoofing = sig
+                    .spoofing_detection_enabled()
+                    .then(|| sig.spoofing_state());
+            },
+            PacketRef::NavStatus(status) => {
+                self.nav_spoofing = Some(status.flags2().spoofing_detection());
+            },
+            PacketRef::NavSat(sat) => self.cno = CnoStats::new(sat),
+            PacketRef::NavEoe(eoe) => {
+                self.end_epoch(eoe.itow(), on_event);
+            },
+            _ => {},
+        }
+    }
+
+    /// Assesses the epoch at `itow` from the latest packets
+    pub fn end_epoch(
+        &mut self,
+        itow: u32,
+        mut on_event: impl FnMut(InterferenceEvent),
+    ) -> InterferenceAssessment {
+        let thresholds = &self.thresholds;
+        let mut indications = InterferenceIndications::empty();
+        let mut receiver_jamming = JammingState::Unknown;
+        for front_end in [self.hw, self.rf].into_iter().flatten() {
+            if self.detection_enabled != Some(false) {
+                receiver_jamming = receiver_jamming.max(front_end.jamming);
+            }
+            // The worst front end wins
+            if front_end.jam_ind > thresholds.jam_indicator {
+                indications |= InterferenceIndications::JAM_INDICATOR;
+            }
+            if front_end.agc_cnt < thresholds.agc_count {
+                indications |= InterferenceIndications::AGC;
+            }
+            if front_end.noise_per_ms > thresholds.noise_per_ms {
+                indications |= InterferenceIndications::NOISE;
+            }
+            if is_antenna_fault(front_end.antenna) {
+                indications |= InterferenceIndications::ANTENNA;
+            }
+        }
+        receiver_jamming = receiver_jamming.max(self.sec_jamming.unwrap_or(JammingState::Unknown));
+
+        let mut uniform_cno = false;
+        if let Some(cno) = self
+            .cno
+            .filter(|cno| cno.satellites >= thresholds.min_satellites)
+        {
+            indications.set(
+                InterferenceIndications::LOW_CNO,
+                cno.mean < thresholds.cno_mean,
+            );
+            uniform_cno = cno.std_dev < thresholds.cno_std_dev;
+        }
+
+        let mut jamming = match receiver_jamming {
+            JammingState::Warning => ThreatLevel::Warning,
+            JammingState::Critical => ThreatLevel::Critical,
+            JammingState::Unknown | JammingState::Ok => ThreatLevel::None,
+        };
+        if jamming > ThreatLevel::None {
+            indications |= InterferenceIndications::RECEIVER_JAMMING;
+        }
+        let measured = (indications
+            & (InterferenceIndications::JAM_INDICATOR
+                | InterferenceIndications::AGC
+                | InterferenceIndications::NOISE
+                | InterferenceIndications::LOW_CNO))
+            .bits()
+            .count_ones();
+        jamming = jamming.max(match measured {
+            0 => ThreatLevel::None,
+            1 => ThreatLevel::Warning,
+            _ => ThreatLevel::Critical,
+        });
+
+        let mut spoofing = [self.nav_spoofing, self.sec_spoofing]
+            .into_iter()
+            .flatten()
+            .map(|state| match state {
+                SpoofingState::Indicated => ThreatLevel::Warning,
+                SpoofingState::Affirmed => ThreatLevel::Critical,
+                SpoofingState::Unknown | SpoofingState::NoSpoofing => ThreatLevel::None,
+            })
+            .max()
+            .unwrap_or_default();
+        if spoofing > ThreatLevel::None {
+            indications |= InterferenceIndications::RECEIVER_SPOOFING;
+        }
+        if uniform_cno {
+            indications |= InterferenceIndications::UNIFORM_CNO;
+            spoofing = if spoofing > ThreatLevel::None {
+                ThreatLevel::Critical
+            } else {
+                ThreatLevel::Warning
+            };
+        }
+
+        let assessment = InterferenceAssessment {
+            itow,
+            jamming,
+            spoofing,
+            indications,
+            cno: self.cno,
+        };
+        let previous = self.latest.replace(assessment);
+        if previous.map(|previous| previous.jamming) != Some(jamming) {
+            on_event(InterferenceEvent::Jamming(jamming));
+        }
+        if previous.map(|previous| previous.spoofing) != Some(spoofing) {
+            on_event(InterferenceEvent::Spoofing(spoofing));
+        }
+        on_event(InterferenceEvent::Epoch(assessment));
+        assessment
+    }
+
+    fn antenna(&mut self, status: AntennaStatus, on_event: &mut impl FnMut(InterferenceEvent)) {
+        if self.antenna != Some(status) {
+            self.antenna = Some(status);
+            on_event(InterferenceEvent::Antenna(status));
+        }
+    }
+}
+
+fn is_antenna_fault(status: AntennaStatus) -> bool {
+    matches!(status, AntennaStatus::Short | AntennaStatus::Open)
+}
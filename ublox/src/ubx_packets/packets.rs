@@ -1,4 +1,4 @@
-use crate::cfg_val::CfgVal;
+use crate::cfg_val::{CfgVal, KeyId};
 use core::convert::TryInto;
 use core::fmt;
 
@@ -29,6 +29,11 @@ use super::{
 pub use packetref::PacketRef;
 pub mod packetref;
 
+#[cfg(feature = "alloc")]
+mod nav_epoch;
+#[cfg(feature = "alloc")]
+pub use nav_epoch::{NavEpoch, NavEpochAssembler};
+
 /// Used to help serialize the packet's fields flattened within a struct containing the msg_id and class fields, but
 /// without using the serde FlatMapSerializer which requires alloc.
 #[cfg(feature = "serde")]
@@ -529,6 +534,8 @@ pub enum CarrierPhaseRangeSolutionStatus {
     SolutionWithFloatingAmbiguities,
     /// Carrier phase range solution with fixed ambiguities
     SolutionWithFixedAmbiguities,
+    /// Reserved value 3 of the 2-bit field
+    Reserved,
 }
 
 #[repr(transparent)]
@@ -554,7 +561,7 @@ impl NavRelPosNedFlags {
             0 => CarrierPhaseRangeSolutionStatus::NoSolution,
             1 => CarrierPhaseRangeSolutionStatus::SolutionWithFloatingAmbiguities,
             2 => CarrierPhaseRangeSolutionStatus::SolutionWithFixedAmbiguities,
-            unknown => panic!("Unexpected 2-bit bitfield value {}!", unknown),
+            _ => CarrierPhaseRangeSolutionStatus::Reserved,
         }
     }
 
@@ -570,12 +577,12 @@ impl NavRelPosNedFlags {
         (self.0 >> 7) & 0x1 != 0
     }
 
-    #[cfg(feature = "ubx_proto27")]
+    #[cfg(any(feature = "ubx_proto27", feature = "ubx_proto31"))]
     pub fn rel_pos_heading_valid(&self) -> bool {
         (self.0 >> 8) & 0x1 != 0
     }
 
-    #[cfg(feature = "ubx_proto27")]
+    #[cfg(any(feature = "ubx_proto27", feature = "ubx_proto31"))]
     pub fn rel_pos_normalized(&self) -> bool {
         (self.0 >> 9) & 0x1 != 0
     }
@@ -597,7 +604,7 @@ impl fmt::Debug for NavRelPosNedFlags {
             .field("ref_pos_miss", &self.ref_pos_miss())
             .field("ref_obs_miss", &self.ref_obs_miss());
 
-        #[cfg(feature = "ubx_proto27")]
+        #[cfg(any(feature = "ubx_proto27", feature = "ubx_proto31"))]
         dbg_struct
             .field("rel_pos_heading_valid", &self.rel_pos_heading_valid())
             .field("rel_pos_normalized", &self.rel_pos_normalized());
@@ -628,6 +635,53 @@ struct NavDop {
     easting_dop: u16,
 }
 
+/// Covariance matrices of the position and velocity solutions
+#[ubx_packet_recv]
+#[ubx(class = 0x01, id = 0x36, fixed_payload_len = 64)]
+struct NavCov {
+    /// GPS Millisecond Time of Week
+    itow: u32,
+    /// Message version, should be 0
+    version: u8,
+    /// Position covariance matrix validity flag
+    #[ubx(map_type = bool, from = nav_cov::is_valid_flag)]
+    pos_cov_valid: u8,
+    /// Velocity covariance matrix validity flag
+    #[ubx(map_type = bool, from = nav_cov::is_valid_flag)]
+    vel_cov_valid: u8,
+    reserved: [u8; 9],
+    /// Position covariance matrix value p_NN in [m^2]
+    pos_cov_nn: f32,
+    /// Position covariance matrix value p_NE in [m^2]
+    pos_cov_ne: f32,
+    /// Position covariance matrix value p_ND in [m^2]
+    pos_cov_nd: f32,
+    /// Position covariance matrix value p_EE in [m^2]
+    pos_cov_ee: f32,
+    /// Position covariance matrix value p_ED in [m^2]
+    pos_cov_ed: f32,
+    /// Position covariance matrix value p_DD in [m^2]
+    pos_cov_dd: f32,
+    /// Velocity covariance matrix value v_NN in [m^2/s^2]
+    vel_cov_nn: f32,
+    /// Velocity covariance matrix value v_NE in [m^2/s^2]
+    vel_cov_ne: f32,
+    /// Velocity covariance matrix value v_ND in [m^2/s^2]
+    vel_cov_nd: f32,
+    /// Velocity covariance matrix value v_EE in [m^2/s^2]
+    vel_cov_ee: f32,
+    /// Velocity covariance matrix value v_ED in [m^2/s^2]
+    vel_cov_ed: f32,
+    /// Velocity covariance matrix value v_DD in [m^2/s^2]
+    vel_cov_dd: f32,
+}
+
+mod nav_cov {
+    pub(crate) fn is_valid_flag(flag: u8) -> bool {
+        flag != 0
+    }
+}
+
 /// End of Epoch Marker
 #[ubx_packet_recv]
 #[ubx(class = 0x01, id = 0x61, fixed_payload_len = 4)]
@@ -779,17 +833,95 @@ pub enum MapMatchingStatus {
 
 /// Further information about navigation output
 /// Only for FW version >= 7.01; undefined otherwise
-#[ubx_extend]
-#[ubx(from, rest_reserved)]
-#[repr(u8)]
-#[derive(Debug, Copy, Clone)]
-enum NavStatusFlags2 {
+#[repr(transparent)]
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct NavStatusFlags2(u8);
+
+impl NavStatusFlags2 {
+    pub fn power_save_mode(self) -> NavStatusPsmState {
+        match self.0 & 0x3 {
+            0 => NavStatusPsmState::Acquisition,
+            1 => NavStatusPsmState::Tracking,
+            2 => NavStatusPsmState::PowerOptimizedTracking,
+            _ => NavStatusPsmState::Inactive,
+        }
+    }
+
+    /// Spoofing detection state, from protocol version 18
+    pub fn spoofing_detection(self) -> SpoofingState {
+        ((self.0 >> 3) & 0x3).into()
+    }
+
+    pub const fn from(x: u8) -> Self {
+        Self(x)
+    }
+}
+
+impl fmt::Debug for NavStatusFlags2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NavStatusFlags2")
+            .field("psm_state", &self.power_save_mode())
+            .field("spoof_det_state", &self.spoofing_detection())
+            .finish()
+    }
+}
+
+/// Power save mode state
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum NavStatusPsmState {
     Acquisition = 0,
     Tracking = 1,
     PowerOptimizedTracking = 2,
     Inactive = 3,
 }
 
+/// Spoofing detection state of NAV-STATUS and SEC-SIG
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum SpoofingState {
+    /// Unknown or deactivated
+    Unknown = 0,
+    NoSpoofing = 1,
+    Indicated = 2,
+    /// Multiple spoofing indications in NAV-STATUS, spoofing affirmed in SEC-SIG
+    Affirmed = 3,
+}
+
+impl From<u8> for SpoofingState {
+    fn from(bits: u8) -> Self {
+        match bits & 0x7 {
+            1 => SpoofingState::NoSpoofing,
+            2 => SpoofingState::Indicated,
+            3 => SpoofingState::Affirmed,
+            _ => SpoofingState::Unknown,
+        }
+    }
+}
+
+/// Jamming and interference state of MON-HW, MON-RF and SEC-SIG
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum JammingState {
+    /// Unknown or monitoring disabled
+    Unknown = 0,
+    Ok = 1,
+    Warning = 2,
+    Critical = 3,
+}
+
+impl From<u8> for JammingState {
+    fn from(bits: u8) -> Self {
+        match bits & 0x3 {
+            1 => JammingState::Ok,
+            2 => JammingState::Warning,
+            3 => JammingState::Critical,
+            _ => JammingState::Unknown,
+        }
+    }
+}
+
 #[repr(transparent)]
 #[derive(Copy, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
@@ -1270,6 +1402,20 @@ impl CfgItfmConfig {
         }
     }
 
+    pub fn enabled(&self) -> bool {
+        self.enable
+    }
+
+    /// Broadband jamming detection threshold in dB
+    pub fn bb_threshold(&self) -> u32 {
+        self.bb_threshold.0
+    }
+
+    /// CW jamming detection threshold in dB
+    pub fn cw_threshold(&self) -> u32 {
+        self.cw_threshold.0
+    }
+
     const fn into_raw(self) -> u32 {
         ((self.enable as u32) << 31)
             | self.cw_threshold.into_raw()
@@ -1281,9 +1427,14 @@ impl CfgItfmConfig {
 impl From<u32> for CfgItfmConfig {
     fn from(cfg: u32) -> Self {
         let enable = (cfg & 0x80000000) > 0;
-        let bb_threshold = CfgItfmBbThreshold::from(cfg);
-        let cw_threshold = CfgItfmCwThreshold::from(cfg);
-        let algorithm_bits = CfgItfmAlgoBits::from(cfg);
+        let bb_threshold = CfgItfmBbThreshold::from(
+            (cfg >> CfgItfmBbThreshold::POSITION) & CfgItfmBbThreshold::MASK,
+        );
+        let cw_threshold = CfgItfmCwThreshold::from(
+            (cfg >> CfgItfmCwThreshold::POSITION) & CfgItfmCwThreshold::MASK,
+        );
+        let algorithm_bits =
+            CfgItfmAlgoBits::from((cfg >> CfgItfmAlgoBits::POSITION) & CfgItfmAlgoBits::MASK);
         Self {
             enable,
             bb_threshold,
@@ -1466,7 +1617,7 @@ impl From<u32> for CfgItfmAntennaSettings {
 
 /// Information message conifg
 #[repr(u8)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum GnssId {
     GPS = 0,
@@ -2018,6 +2169,142 @@ struct CfgRst {
     reserved1: u8,
 }
 
+/// Clear, Save and Load configurations
+///
+/// Clearing is done first, then saving, then loading.
+/// The legacy way of persisting configuration on pre-Gen9 (M8) devices.
+#[ubx_packet_send]
+#[ubx(
+    class = 0x06,
+    id = 0x09,
+    fixed_payload_len = 13,
+    flags = "default_for_builder"
+)]
+struct CfgCfg {
+    /// Configuration sections to clear from non-volatile memory
+    #[ubx(map_type = CfgCfgMask)]
+    clear_mask: u32,
+
+    /// Configuration sections to save to non-volatile memory
+    #[ubx(map_type = CfgCfgMask)]
+    save_mask: u32,
+
+    /// Configuration sections to load from non-volatile memory
+    #[ubx(map_type = CfgCfgMask)]
+    load_mask: u32,
+
+    /// Devices to which the clear/save/load operations apply
+    #[ubx(map_type = CfgCfgDeviceMask)]
+    device_mask: u8,
+}
+
+impl CfgCfgBuilder {
+    /// Save the given configuration sections of the current configuration
+    pub fn save(mask: CfgCfgMask, devices: CfgCfgDeviceMask) -> Self {
+        Self {
+            save_mask: mask,
+            device_mask: devices,
+            ..Default::default()
+        }
+    }
+
+    /// Load the given configuration sections from non-volatile memory
+    /// into the current configuration
+    pub fn load(mask: CfgCfgMask, devices: CfgCfgDeviceMask) -> Self {
+        Self {
+            load_mask: mask,
+            device_mask: devices,
+            ..Default::default()
+        }
+    }
+
+    /// Clear the given configuration sections from non-volatile memory
+    pub fn clear(mask: CfgCfgMask, devices: CfgCfgDeviceMask) -> Self {
+        Self {
+            clear_mask: mask,
+            device_mask: devices,
+            ..Default::default()
+        }
+    }
+
+    /// Clear all permanent configuration and load the default
+    /// configuration into the current configuration
+    pub fn restore_defaults(devices: CfgCfgDeviceMask) -> Self {
+        Self {
+            clear_mask: CfgCfgMask::ALL_SECTIONS,
+            load_mask: CfgCfgMask::ALL_SECTIONS,
+            device_mask: devices,
+            ..Default::default()
+        }
+    }
+}
+
+#[ubx_extend_bitflags]
+#[ubx(from, into_raw, rest_reserved)]
+bitflags! {
+    /// Configuration sections used by [CfgCfg]
+    #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct CfgCfgMask: u32 {
+        /// Communications port settings
+        const IO_PORT = 0x0001;
+        /// Message configuration
+        const MSG_CONF = 0x0002;
+        /// INF message configuration
+        const INF_MSG = 0x0004;
+        /// Navigation configuration
+        const NAV_CONF = 0x0008;
+        /// Receiver Manager configuration
+        const RXM_CONF = 0x0010;
+        /// Sensor interface configuration
+        const SEN_CONF = 0x0100;
+        /// Remote inventory configuration
+        const RINV_CONF = 0x0200;
+        /// Antenna configuration
+        const ANT_CONF = 0x0400;
+        /// Logging configuration
+        const LOG_CONF = 0x0800;
+        /// FTS configuration (FTS products only)
+        const FTS_CONF = 0x1000;
+    }
+}
+
+impl CfgCfgMask {
+    /// All known configuration sections, without the reserved bits
+    pub const ALL_SECTIONS: Self = Self::IO_PORT
+        .union(Self::MSG_CONF)
+        .union(Self::INF_MSG)
+        .union(Self::NAV_CONF)
+        .union(Self::RXM_CONF)
+        .union(Self::SEN_CONF)
+        .union(Self::RINV_CONF)
+        .union(Self::ANT_CONF)
+        .union(Self::LOG_CONF)
+        .union(Self::FTS_CONF);
+}
+
+#[ubx_extend_bitflags]
+#[ubx(from, into_raw, rest_reserved)]
+bitflags! {
+    /// Non-volatile storage devices used by [CfgCfg]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct CfgCfgDeviceMask: u8 {
+        /// Battery backed RAM
+        const BBR = 0x01;
+        /// Flash
+        const FLASH = 0x02;
+        /// EEPROM
+        const EEPROM = 0x04;
+        /// SPI Flash
+        const SPI_FLASH = 0x10;
+    }
+}
+
+impl Default for CfgCfgDeviceMask {
+    fn default() -> Self {
+        Self::BBR | Self::FLASH | Self::EEPROM | Self::SPI_FLASH
+    }
+}
+
 /// Reset Receiver / Clear Backup Data Structures
 #[ubx_packet_recv_send]
 #[ubx(class = 6, id = 0x13, fixed_payload_len = 4)]
@@ -2194,6 +2481,58 @@ struct TimSvin {
     reserved: [u8; 2],
 }
 
+/// Survey-in data
+#[ubx_packet_recv]
+#[ubx(class = 0x01, id = 0x3b, fixed_payload_len = 40)]
+struct NavSvin {
+    /// Message version (0x00 for this version)
+    version: u8,
+    reserved1: [u8; 3],
+    /// GPS time of week of the navigation epoch in ms
+    itow: u32,
+    /// Passed survey-in observation time in [s]
+    dur: u32,
+    /// Current survey-in mean position ECEF X coordinate in [m]
+    #[ubx(map_type = f64, scale = 1e-2, alias = mean_x_m)]
+    mean_x: i32,
+    /// Current survey-in mean position ECEF Y coordinate in [m]
+    #[ubx(map_type = f64, scale = 1e-2, alias = mean_y_m)]
+    mean_y: i32,
+    /// Current survey-in mean position ECEF Z coordinate in [m]
+    #[ubx(map_type = f64, scale = 1e-2, alias = mean_z_m)]
+    mean_z: i32,
+    /// High precision component of the mean ECEF X coordinate in [m],
+    /// in the range -0.0099..+0.0099.
+    /// Precise coordinate = mean_x + mean_x_hp.
+    #[ubx(map_type = f64, scale = 1e-4, alias = mean_x_hp_m)]
+    mean_x_hp: i8,
+    /// High precision component of the mean ECEF Y coordinate in [m]
+    #[ubx(map_type = f64, scale = 1e-4, alias = mean_y_hp_m)]
+    mean_y_hp: i8,
+    /// High precision component of the mean ECEF Z coordinate in [m]
+    #[ubx(map_type = f64, scale = 1e-4, alias = mean_z_hp_m)]
+    mean_z_hp: i8,
+    reserved2: u8,
+    /// Current survey-in mean position accuracy in [m]
+    #[ubx(map_type = f64, scale = 1e-4)]
+    mean_acc: u32,
+    /// Number of position observations used during survey-in
+    obs: u32,
+    /// Survey-in position validity flag
+    #[ubx(map_type = bool, from = nav_svin::is_set)]
+    valid: u8,
+    /// Survey-in in progress flag
+    #[ubx(map_type = bool, from = nav_svin::is_set)]
+    active: u8,
+    reserved3: [u8; 2],
+}
+
+mod nav_svin {
+    pub(crate) fn is_set(flag: u8) -> bool {
+        flag == 1
+    }
+}
+
 /// Leap second event information
 #[ubx_packet_recv]
 #[ubx(class = 0x01, id = 0x26, fixed_payload_len = 24)]
@@ -2272,7 +2611,7 @@ struct CfgTmode3 {
     version: u8,
     reserved1: u8,
     /// Receiver mode, see [CfgTmode3RcvrMode] enum
-    #[ubx(map_type = CfgTmode3RcvrMode)]
+    #[ubx(map_type = CfgTmode3RcvrMode, may_fail)]
     rcvr_mode: u8,
     #[ubx(map_type = CfgTmode3Flags)]
     flags: u8,
@@ -2315,16 +2654,18 @@ struct CfgTmode3 {
     reserved3: [u8; 8],
 }
 
-#[ubx_extend_bitflags]
-#[ubx(from, into_raw, rest_reserved)]
-bitflags! {
-    #[derive(Default, Debug)]
-    pub struct CfgTmode3RcvrMode: u8 {
-        const DISABLED = 0x01;
-        const SURVEY_IN = 0x02;
-        /// True ARP position is required in `FixedMode`
-        const FIXED_MODE = 0x04;
-    }
+/// Receiver mode of CFG-TMODE3
+#[derive(Default)]
+#[ubx_extend]
+#[ubx(from_unchecked, into_raw, rest_error)]
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CfgTmode3RcvrMode {
+    #[default]
+    Disabled = 0,
+    SurveyIn = 1,
+    /// True ARP position is required in `FixedMode`
+    FixedMode = 2,
 }
 
 #[ubx_extend_bitflags]
@@ -2464,6 +2805,12 @@ struct CfgValSet<'a> {
     cfg_data: &'a [CfgVal],
 }
 
+impl CfgValSetBuilder<'_> {
+    /// Maximum number of items in a single CFG-VALSET message,
+    /// also the maximum number of items in a CFG-VALGET response
+    pub const MAX_ITEMS: usize = 64;
+}
+
 #[derive(Debug, Clone)]
 pub struct CfgValIter<'a> {
     pub(crate) data: &'a [u8],
@@ -2485,26 +2832,132 @@ impl<'a> CfgValIter<'a> {
     }
 }
 
+impl<'a> CfgValIter<'a> {
+    /// Iterate over the raw key/value pairs of a CFG-VALSET or CFG-VALGET payload
+    pub fn from_raw(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    /// The remaining encoded key/value pairs, including those skipped by the
+    /// iterator. Stops at the first malformed pair.
+    pub fn raw_items(&self) -> impl Iterator<Item = &'a [u8]> {
+        let mut data = &self.data[self.offset..];
+        core::iter::from_fn(move || {
+            let len = Self::item_len(data)?;
+            let (item, rest) = data.split_at(len);
+            data = rest;
+            Some(item)
+        })
+    }
+
+    fn is_valid(bytes: &[u8]) -> bool {
+        let mut offset = 0;
+        while offset < bytes.len() {
+            match Self::item_len(&bytes[offset..]) {
+                Some(len) => offset += len,
+                None => return false,
+            }
+        }
+        true
+    }
+
+    /// Length of the key/value pair at the start of `bytes`,
+    /// if it is complete and its key has a valid size
+    fn item_len(bytes: &[u8]) -> Option<usize> {
+        if bytes.len() < KeyId::SIZE {
+            return None;
+        }
+        let key_id = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        if !matches!((key_id >> 28) & 0b111, 1..=5) {
+            return None;
+        }
+        let len = KeyId::SIZE + KeyId::new(key_id).value_size().to_usize();
+        (len <= bytes.len()).then_some(len)
+    }
+}
+
 impl core::iter::Iterator for CfgValIter<'_> {
     type Item = CfgVal;
 
+    /// Yields the configuration values, skipping items rejected by
+    /// [CfgVal::try_parse]. Stops at the first malformed pair.
     fn next(&mut self) -> Option<Self::Item> {
-        if self.offset < self.data.len() {
-            let cfg_val = CfgVal::parse(&self.data[self.offset..]);
-
-            self.offset += cfg_val.len();
-
-            Some(cfg_val)
-        } else {
-            None
+        while self.offset < self.data.len() {
+            let buf = &self.data[self.offset..];
+            let len = Self::item_len(buf)?;
+            self.offset += len;
+            if let Some(cfg_val) = CfgVal::try_parse(&buf[..len]) {
+                return Some(cfg_val);
+            }
         }
+        None
     }
 }
 
+/// Poll configuration items from the given layer
+///
+/// Up to 64 keys can be requested, [KeyId::group_wildcard] keys are
+/// answered with up to 64 items starting at `position`.
+#[ubx_packet_send]
+#[ubx(
+  class = 0x06,
+  id = 0x8b,
+  max_payload_len = 260, // 4 + 4 * 64
+)]
+struct CfgValGetRequest<'a> {
+    /// Message version, 0 for a poll request
+    version: u8,
+    /// The layer from which the configuration items should be retrieved
+    #[ubx(map_type = CfgValGetLayer)]
+    layer: u8,
+    /// Number of items to skip before returning results
+    position: u16,
+    keys: &'a [KeyId],
+}
+
+/// Configuration items returned for a [CfgValGetRequest] poll
+#[ubx_packet_recv]
+#[ubx(
+  class = 0x06,
+  id = 0x8b,
+  max_payload_len = 772, // 4 + (4 + 8) * 64
+)]
+struct CfgValGet {
+    /// Message version, 1 for a poll response
+    version: u8,
+    /// The layer from which the configuration items were retrieved
+    #[ubx(map_type = CfgValGetLayer, may_fail)]
+    layer: u8,
+    /// Number of items skipped before the returned results
+    position: u16,
+    #[ubx(
+        map_type = CfgValIter,
+        from = CfgValIter::from_raw,
+        is_valid = CfgValIter::is_valid,
+        may_fail,
+        get_as_ref,
+    )]
+    cfg_data: [u8; 0],
+}
+
+/// Configuration layer used by [CfgValGetRequest]
+#[ubx_extend]
+#[ubx(from_unchecked, into_raw, rest_error)]
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CfgValGetLayer {
+    Ram = 0,
+    Bbr = 1,
+    Flash = 2,
+    /// The receiver default configuration
+    Default = 7,
+}
+
 #[ubx_extend_bitflags]
 #[ubx(from, into_raw, rest_reserved)]
 bitflags! {
     /// A mask describing where configuration is applied.
+    #[derive(Debug, Clone, Copy)]
     pub struct CfgLayer: u8 {
         const RAM = 0b001;
         const BBR = 0b010;
@@ -2859,6 +3312,7 @@ struct CfgRate {
 /// Alignment to reference time
 #[repr(u16)]
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AlignmentToReferenceTime {
     Utc = 0,
     Gps = 1,
@@ -3284,6 +3738,27 @@ struct MgaAck {
     msg_payload_start: [u8; 4],
 }
 
+impl MgaAckRef<'_> {
+    /// Checks whether this acknowledgment refers to the MGA `packet`,
+    /// given as a complete UBX frame
+    pub fn is_ack_for(&self, packet: &[u8]) -> bool {
+        packet.len() >= 10
+            && packet[2] == 0x13
+            && self.msg_id() == packet[3]
+            && self.msg_payload_start() == packet[6..10]
+    }
+
+    /// `true` if the receiver accepted the acknowledged message
+    pub fn is_accepted(&self) -> bool {
+        self.ack_type() == 1
+    }
+
+    /// Number of MGA-DBD entries, if this acknowledges the end of a database dump
+    pub fn dbd_count(&self) -> Option<u32> {
+        (self.msg_id() == MgaDbd::ID).then(|| u32::from_le_bytes(self.msg_payload_start()))
+    }
+}
+
 /// Get/set IMU-mount misalignment configuration
 /// Only available for ADR products
 #[ubx_packet_recv_send]
@@ -3454,8 +3929,14 @@ pub enum MsgAckInfoCode {
     RejectedUnknownType = 6,
 }
 
-#[ubx_packet_recv]
-#[ubx(class = 0x13, id = 0x06, fixed_payload_len = 48)]
+/// GLONASS ephemeris assistance UBX-MGA-GLO-EPH
+#[ubx_packet_recv_send]
+#[ubx(
+    class = 0x13,
+    id = 0x06,
+    fixed_payload_len = 48,
+    flags = "default_for_builder"
+)]
 struct MgaGloEph {
     msg_type: u8,
     version: u8,
@@ -3482,8 +3963,14 @@ struct MgaGloEph {
     reserved2: [u8; 4],
 }
 
-#[ubx_packet_recv]
-#[ubx(class = 0x13, id = 0x00, fixed_payload_len = 16)]
+/// GPS ionosphere assistance UBX-MGA-GPS-IONO
+#[ubx_packet_recv_send]
+#[ubx(
+    class = 0x13,
+    id = 0x00,
+    fixed_payload_len = 16,
+    flags = "default_for_builder"
+)]
 struct MgaGpsIono {
     /// Message type: 0x06 for this type
     msg_type: u8,
@@ -3491,73 +3978,230 @@ struct MgaGpsIono {
     version: u8,
     reserved1: [u8; 2],
     /// Ionospheric parameter alpha0 [s]
-    #[ubx(map_type = f64, scale = 1.0)] // 2^-30
+    #[ubx(map_type = f64, scale = 9.313225746154785e-10)] // 2^-30
     alpha0: i8,
     /// Ionospheric parameter alpha1 [s/semi-circle]
-    #[ubx(map_type = f64, scale = 1.0)] // 2^-27
+    #[ubx(map_type = f64, scale = 7.450580596923828e-09)] // 2^-27
     alpha1: i8,
     /// Ionospheric parameter alpha1 [s/semi-circle^2]
-    #[ubx(map_type = f64, scale = 1.0)] // 2^-24
+    #[ubx(map_type = f64, scale = 5.960464477539063e-08)] // 2^-24
     alpha2: i8,
     /// Ionospheric parameter alpha1 [s/semi-circle^3]
-    #[ubx(map_type = f64, scale = 1.0)] // 2^-24
+    #[ubx(map_type = f64, scale = 5.960464477539063e-08)] // 2^-24
     alpha3: i8,
     /// Ionospheric parameter beta0 [s]
-    #[ubx(map_type = f64, scale = 1.0)] // 2^-11
+    #[ubx(map_type = f64, scale = 2048.0)] // 2^11
     beta0: i8,
     /// Ionospheric parameter beta0 [s/semi-circle]
-    #[ubx(map_type = f64, scale = 1.0)] // 2^-14
+    #[ubx(map_type = f64, scale = 16384.0)] // 2^14
     beta1: i8,
     /// Ionospheric parameter beta0 [s/semi-circle^2]
-    #[ubx(map_type = f64, scale = 1.0)] // 2^-16
+    #[ubx(map_type = f64, scale = 65536.0)] // 2^16
     beta2: i8,
     /// Ionospheric parameter beta0 [s/semi-circle^3]
-    #[ubx(map_type = f64, scale = 1.0)] // 2^-16
+    #[ubx(map_type = f64, scale = 65536.0)] // 2^16
     beta3: i8,
     reserved2: [u8; 4],
 }
 
-/// Time pulse time data
+/// AssistNow Offline data of one satellite and day UBX-MGA-ANO
 #[ubx_packet_recv]
-#[ubx(class = 0x0d, id = 0x01, fixed_payload_len = 16)]
-struct TimTp {
-    /// Time pulse time of week according to time base
-    tow_ms: u32,
-    /// Submillisecond part of towMS (scaling: 2^(-32))
-    tow_sub_ms: u32,
-    /// Quantization error of time pulse
-    q_err: i32,
-    /// Time pulse week number according to time base
-    week: u16,
-    /// Flags
-    #[ubx(map_type = TimTpFlags, from = TimTpFlags)]
-    flags: u8,
-    /// Time reference information
-    #[ubx(map_type = TimTpRefInfo, from = TimTpRefInfo)]
-    ref_info: u8,
+#[ubx(class = 0x13, id = 0x20, fixed_payload_len = 76)]
+struct MgaAno {
+    /// Message type: 0x00 for this type
+    msg_type: u8,
+    /// Message version: 0x00 for this version
+    version: u8,
+    sv_id: u8,
+    gnss_id: u8,
+    /// Years since 2000
+    year: u8,
+    month: u8,
+    day: u8,
+    reserved1: u8,
+    /// Opaque assistance data
+    #[ubx(map_type = &[u8], from = core::convert::identity, get_as_ref)]
+    data: [u8; 64],
+    reserved2: [u8; 4],
 }
 
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize))]
-pub struct TimTpFlags(u8);
-
-impl TimTpFlags {
-    /// Time base
-    pub fn time_base(&self) -> TimTpTimeBase {
-        if self.0 & 0b1 == 0 {
-            TimTpTimeBase::Gnss
-        } else {
-            TimTpTimeBase::Utc
-        }
+impl MgaAnoRef<'_> {
+    /// Day the data is valid for
+    pub fn date(&self) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(
+            2000 + i32::from(self.year()),
+            u32::from(self.month()),
+            u32::from(self.day()),
+        )
     }
+}
 
-    /// UTC availability
-    pub fn utc_available(&self) -> bool {
-        self.0 & 0b10 != 0
+/// Poll the navigation database UBX-MGA-DBD
+#[ubx_packet_send]
+#[ubx(class = 0x13, id = 0x80, fixed_payload_len = 0)]
+struct MgaDbdPoll {}
+
+/// Navigation database dump entry UBX-MGA-DBD
+///
+/// The receiver answers a [MgaDbdPoll] with a sequence of these, followed by
+/// a [MgaAck] whose payload start is the number of entries sent.
+#[ubx_packet_recv]
+#[ubx(class = 0x13, id = 0x80, max_payload_len = 176)]
+struct MgaDbd {
+    reserved1: [u8; 12],
+    /// Opaque navigation database contents
+    #[ubx(map_type = core::slice::Iter<'a, u8>, from = byte_iter)]
+    data: [u8; 0],
+}
+
+fn byte_iter(bytes: &[u8]) -> core::slice::Iter<'_, u8> {
+    bytes.iter()
+}
+
+impl MgaDbdRef<'_> {
+    /// Frame the entry to send it back to the receiver as-is
+    #[cfg(feature = "alloc")]
+    pub fn into_packet_vec(self) -> Vec<u8> {
+        crate::UbxUnknownPacketRef {
+            payload: self.0,
+            class: MgaDbd::CLASS,
+            msg_id: MgaDbd::ID,
+        }
+        .into_packet_vec()
     }
+}
 
-    /// (T)RAIM state
-    ///
+/// Initial time assistance in UTC UBX-MGA-INI-TIME_UTC
+#[ubx_packet_send]
+#[ubx(
+    class = 0x13,
+    id = 0x40,
+    fixed_payload_len = 24,
+    flags = "default_for_builder"
+)]
+struct MgaIniTimeUtc {
+    /// Message type: 0x10 for this type
+    msg_type: u8,
+    /// Message version: 0x00 for this version
+    version: u8,
+    /// Time reference: 0 on receipt of the message, 1 on the PPS pulse,
+    /// bit 4 falling edge, bit 5 last pulse
+    reference: u8,
+    /// Number of leap seconds since 1980, -128 if unknown
+    leap_secs: i8,
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+    reserved1: u8,
+    /// Nanoseconds
+    ns: u32,
+    /// Seconds part of the time accuracy
+    t_acc_s: u16,
+    reserved2: [u8; 2],
+    /// Nanoseconds part of the time accuracy
+    t_acc_ns: u32,
+}
+
+impl MgaIniTimeUtcBuilder {
+    pub fn set_time(mut self, tm: DateTime<Utc>, accuracy_ns: u64) -> Self {
+        self.msg_type = 0x10;
+        self.leap_secs = -128;
+        self.year = tm.year() as u16;
+        self.month = tm.month() as u8;
+        self.day = tm.day() as u8;
+        self.hour = tm.hour() as u8;
+        self.minute = tm.minute() as u8;
+        self.second = tm.second() as u8;
+        self.ns = tm.nanosecond();
+        self.t_acc_s = (accuracy_ns / 1_000_000_000).min(u64::from(u16::MAX)) as u16;
+        self.t_acc_ns = (accuracy_ns % 1_000_000_000) as u32;
+        self
+    }
+}
+
+/// Initial position assistance in WGS84 coordinates UBX-MGA-INI-POS_LLH
+#[ubx_packet_send]
+#[ubx(
+    class = 0x13,
+    id = 0x40,
+    fixed_payload_len = 20,
+    flags = "default_for_builder"
+)]
+struct MgaIniPosLlh {
+    /// Message type: 0x01 for this type
+    msg_type: u8,
+    /// Message version: 0x00 for this version
+    version: u8,
+    reserved1: [u8; 2],
+    /// Latitude [deg]
+    #[ubx(map_type = f64, scale = 1e-7)]
+    lat: i32,
+    /// Longitude [deg]
+    #[ubx(map_type = f64, scale = 1e-7)]
+    lon: i32,
+    /// Height above the WGS84 ellipsoid [m]
+    #[ubx(map_type = f64, scale = 1e-2)]
+    alt: i32,
+    /// Position accuracy, standard deviation [m]
+    #[ubx(map_type = f64, scale = 1e-2)]
+    pos_acc: u32,
+}
+
+impl MgaIniPosLlhBuilder {
+    pub fn set_position(mut self, pos: Position, accuracy: f64) -> Self {
+        self.msg_type = 0x01;
+        self.lat = pos.lat;
+        self.lon = pos.lon;
+        self.alt = pos.alt;
+        self.pos_acc = accuracy;
+        self
+    }
+}
+
+/// Time pulse time data
+#[ubx_packet_recv]
+#[ubx(class = 0x0d, id = 0x01, fixed_payload_len = 16)]
+struct TimTp {
+    /// Time pulse time of week according to time base
+    tow_ms: u32,
+    /// Submillisecond part of towMS (scaling: 2^(-32))
+    tow_sub_ms: u32,
+    /// Quantization error of time pulse
+    q_err: i32,
+    /// Time pulse week number according to time base
+    week: u16,
+    /// Flags
+    #[ubx(map_type = TimTpFlags, from = TimTpFlags)]
+    flags: u8,
+    /// Time reference information
+    #[ubx(map_type = TimTpRefInfo, from = TimTpRefInfo)]
+    ref_info: u8,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TimTpFlags(u8);
+
+impl TimTpFlags {
+    /// Time base
+    pub fn time_base(&self) -> TimTpTimeBase {
+        if self.0 & 0b1 == 0 {
+            TimTpTimeBase::Gnss
+        } else {
+            TimTpTimeBase::Utc
+        }
+    }
+
+    /// UTC availability
+    pub fn utc_available(&self) -> bool {
+        self.0 & 0b10 != 0
+    }
+
+    /// (T)RAIM state
+    ///
     /// Returns `None` if unavailale.
     pub fn raim_active(&self) -> Option<bool> {
         match (self.0 >> 2) & 0b11 {
@@ -3693,11 +4337,10 @@ impl TimTm2Flags {
     }
 
     pub fn time_base(&self) -> TimTm2TimeBase {
-        match self.0 & 0b11000 {
+        match (self.0 >> 3) & 0b11 {
             0 => TimTm2TimeBase::Receiver,
             1 => TimTm2TimeBase::Gnss,
-            2 => TimTm2TimeBase::Utc,
-            _ => unreachable!(),
+            _ => TimTm2TimeBase::Utc,
         }
     }
 
@@ -3721,6 +4364,7 @@ pub enum TimTm2Run {
     Stopped,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TimTm2TimeBase {
     Receiver,
     Gnss,
@@ -3867,6 +4511,7 @@ struct MonHw {
     a_status: u8,
     #[ubx(map_type = AntennaPower)]
     a_power: u8,
+    #[ubx(map_type = MonHwFlags)]
     flags: u8,
     reserved1: u8,
     used_mask: u32,
@@ -3878,6 +4523,123 @@ struct MonHw {
     pull_l: u32,
 }
 
+/// UBX-MON-HW flags
+#[repr(transparent)]
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MonHwFlags(u8);
+
+impl MonHwFlags {
+    pub fn rtc_calibrated(self) -> bool {
+        self.0 & 0x01 != 0
+    }
+
+    pub fn safe_boot(self) -> bool {
+        (self.0 >> 1) & 0x01 != 0
+    }
+
+    /// Output of the interference monitor, unknown when it is disabled
+    pub fn jamming_state(self) -> JammingState {
+        (self.0 >> 2).into()
+    }
+
+    pub fn xtal_absent(self) -> bool {
+        (self.0 >> 4) & 0x01 != 0
+    }
+
+    pub const fn from(x: u8) -> Self {
+        Self(x)
+    }
+}
+
+impl fmt::Debug for MonHwFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("flags")
+            .field("rtcCalib", &self.rtc_calibrated())
+            .field("safeBoot", &self.safe_boot())
+            .field("jammingState", &self.jamming_state())
+            .field("xtalAbsent", &self.xtal_absent())
+            .finish()
+    }
+}
+
+/// RF information, one block per RF band
+#[cfg(any(feature = "ubx_proto27", feature = "ubx_proto31"))]
+#[ubx_packet_recv]
+#[ubx(class = 0x0a, id = 0x38, max_payload_len = 1240)]
+struct MonRf {
+    /// Message version, 0x00 for this version
+    version: u8,
+    n_blocks: u8,
+    reserved0: [u8; 2],
+    #[ubx(
+        map_type = MonRfBlockIter,
+        from = MonRfBlockIter::new,
+        is_valid = MonRfBlockIter::is_valid,
+        may_fail,
+    )]
+    blocks: [u8; 0],
+}
+
+#[cfg(any(feature = "ubx_proto27", feature = "ubx_proto31"))]
+#[ubx_packet_recv]
+#[ubx(class = 0x0a, id = 0x38, fixed_payload_len = 24)]
+struct MonRfBlock {
+    /// RF band, 0 for L1 and 1 for L2 or L5
+    block_id: u8,
+    flags: u8,
+    #[ubx(map_type = AntennaStatus)]
+    ant_status: u8,
+    #[ubx(map_type = AntennaPower)]
+    ant_power: u8,
+    post_status: u32,
+    reserved1: [u8; 4],
+    noise_per_ms: u16,
+    /// AGC monitor, 0 to 8191
+    agc_cnt: u16,
+    /// CW jamming indicator, 0 for no and 255 for strong CW jamming
+    jam_ind: u8,
+    ofs_i: i8,
+    mag_i: u8,
+    ofs_q: i8,
+    mag_q: u8,
+    reserved2: [u8; 3],
+}
+
+#[cfg(any(feature = "ubx_proto27", feature = "ubx_proto31"))]
+impl MonRfBlockRef<'_> {
+    /// Output of the interference monitor, unknown when it is disabled
+    pub fn jamming_state(&self) -> JammingState {
+        self.flags().into()
+    }
+}
+
+#[cfg(any(feature = "ubx_proto27", feature = "ubx_proto31"))]
+#[derive(Debug, Clone)]
+pub struct MonRfBlockIter<'a>(core::slice::ChunksExact<'a, u8>);
+
+#[cfg(any(feature = "ubx_proto27", feature = "ubx_proto31"))]
+impl<'a> MonRfBlockIter<'a> {
+    const BLOCK_SIZE: usize = 24;
+
+    fn new(bytes: &'a [u8]) -> Self {
+        Self(bytes.chunks_exact(Self::BLOCK_SIZE))
+    }
+
+    fn is_valid(bytes: &'a [u8]) -> bool {
+        bytes.len() % Self::BLOCK_SIZE == 0
+    }
+}
+
+#[cfg(any(feature = "ubx_proto27", feature = "ubx_proto31"))]
+impl<'a> core::iter::Iterator for MonRfBlockIter<'a> {
+    type Item = MonRfBlockRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(MonRfBlockRef)
+    }
+}
+
 #[ubx_extend]
 #[ubx(from, rest_reserved)]
 #[repr(u8)]
@@ -4008,16 +4770,33 @@ mod mon_ver {
     }
 }
 
+/// RTCM input status, one per RTCM message received by the receiver
 #[ubx_packet_recv]
 #[ubx(class = 0x02, id = 0x32, fixed_payload_len = 8)]
 struct RxmRtcm {
     version: u8,
+    #[ubx(map_type = RxmRtcmFlags)]
     flags: u8,
     sub_type: u16,
     ref_station: u16,
     msg_type: u16,
 }
 
+#[ubx_extend_bitflags]
+#[ubx(from, rest_reserved)]
+bitflags! {
+    /// `RxmRtcm` flags, at most one of `NOT_USED` and `USED` is set
+    #[derive(Default, Debug)]
+    pub struct RxmRtcmFlags: u8 {
+        /// The message failed the CRC check
+        const CRC_FAILED = 0x01;
+        /// The message was not used in the solution
+        const NOT_USED = 0x02;
+        /// The message was used in the solution
+        const USED = 0x04;
+    }
+}
+
 #[ubx_packet_recv]
 #[ubx(class = 0x10, id = 0x02, max_payload_len = 1240)]
 struct EsfMeas {
@@ -4068,12 +4847,29 @@ pub struct EsfMeasData {
     pub data_field: i32,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SensorData {
     Tick(i32),
     Value(f32),
 }
 
+impl SensorData {
+    /// Decodes a 24 bit data field in the units of [EsfSensorType]
+    fn decode(data_type: EsfSensorType, field: u32) -> Self {
+        if data_type.is_tick() {
+            let (count, backward) = EsfSensorType::field_ticks(field);
+            let tick = count as i32;
+            return SensorData::Tick(if backward { -tick } else { tick });
+        }
+        match data_type.scale() {
+            Some(scale) => {
+                SensorData::Value((f64::from(EsfSensorType::field_value(field)) * scale) as f32)
+            },
+            None => SensorData::Value(0f32),
+        }
+    }
+}
+
 impl EsfMeasData {
     pub fn direction(&self) -> i8 {
         if self.data_field.is_negative() {
@@ -4083,34 +4879,13 @@ impl EsfMeasData {
         }
     }
 
+    /// The 24 bit data field as sent by the receiver
+    pub fn field(&self) -> u32 {
+        self.data_field.unsigned_abs() & 0xFFFFFF
+    }
+
     pub fn value(&self) -> SensorData {
-        match self.data_type {
-            EsfSensorType::FrontLeftWheelTicks
-            | EsfSensorType::FrontRightWheelTicks
-            | EsfSensorType::RearLeftWheelTicks
-            | EsfSensorType::RearRightWheelTicks
-            | EsfSensorType::SpeedTick => {
-                let tick = (self.data_field & 0x7FFFFF) * (self.direction() as i32);
-                SensorData::Tick(tick)
-            },
-            EsfSensorType::Speed => {
-                let value = (self.data_field & 0x7FFFFF) as f32 * (self.direction() as f32) * 1e-3;
-                SensorData::Value(value)
-            },
-            EsfSensorType::GyroX | EsfSensorType::GyroY | EsfSensorType::GyroZ => {
-                let value = (self.data_field & 0x7FFFFF) as f32 * 2_f32.powi(-12);
-                SensorData::Value(value)
-            },
-            EsfSensorType::AccX | EsfSensorType::AccY | EsfSensorType::AccZ => {
-                let value = (self.data_field & 0x7FFFFF) as f32 * 2_f32.powi(-10);
-                SensorData::Value(value)
-            },
-            EsfSensorType::GyroTemp => {
-                let value = (self.data_field & 0x7FFFFF) as f32 * 1e-2;
-                SensorData::Value(value)
-            },
-            _ => SensorData::Value(0f32),
-        }
+        SensorData::decode(self.data_type, self.field())
     }
 }
 
@@ -4201,6 +4976,175 @@ struct EsfMeasSpeed {
     data: [u8; 4],
 }
 
+/// UBX-ESF-MEAS sent by the host, with external sensor data such as
+/// wheel ticks or vehicle speed. Use [EsfMeasInputBuilder::new] to set the
+/// number of measurements in `flags`.
+#[ubx_packet_send]
+#[ubx(
+  class = 0x10,
+  id = 0x02,
+  max_payload_len = 132, // 8 + 4 * 31
+)]
+struct EsfMeasInput<'a> {
+    /// Time tag of the measurements in ms, on the host's time base
+    time_tag: u32,
+    flags: u16,
+    /// Identification number of the data provider
+    id: u16,
+    data: &'a [EsfMeasInputData],
+}
+
+impl<'a> EsfMeasInputBuilder<'a> {
+    /// Maximum number of measurements in a single message
+    pub const MAX_MEAS: usize = 31;
+
+    /// Panics if there are more than [Self::MAX_MEAS] measurements
+    pub fn new(time_tag: u32, data: &'a [EsfMeasInputData]) -> Self {
+        assert!(
+            data.len() <= Self::MAX_MEAS,
+            "too many ESF-MEAS measurements"
+        );
+        Self {
+            time_tag,
+            flags: (data.len() as u16) << 11,
+            id: 0,
+            data,
+        }
+    }
+}
+
+/// A measurement of [EsfMeasInput]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EsfMeasInputData {
+    pub data_type: EsfSensorType,
+    /// Tick counts for the tick types, negative when moving backward.
+    /// Values in the units of [EsfSensorType] for the others.
+    pub value: SensorData,
+}
+
+impl EsfMeasInputData {
+    /// The 24 bit data field: ticks with a direction bit, scaled values in two's complement
+    fn data_field(&self) -> u32 {
+        match self.value {
+            SensorData::Tick(ticks) => {
+                let backward = if ticks < 0 {
+                    EsfSensorType::TICK_BACKWARD
+                } else {
+                    0
+                };
+                (ticks.unsigned_abs() & EsfSensorType::TICK_MASK) | backward
+            },
+            SensorData::Value(value) => {
+                let scale = self.data_type.scale().unwrap_or(1.0);
+                (libm::round(f64::from(value) / scale) as i32) as u32 & 0xFFFFFF
+            },
+        }
+    }
+
+    pub fn extend_to<T>(&self, buf: &mut T) -> usize
+    where
+        T: core::iter::Extend<u8>,
+    {
+        let data = self.data_field() | (u32::from(self.data_type as u8) << 24);
+        let bytes = data.to_le_bytes();
+        buf.extend(bytes);
+        bytes.len()
+    }
+}
+
+/// Converts the host's odometer counts and vehicle speed into
+/// [EsfMeasInputData] at a fixed rate.
+///
+/// Time tags are the host's milliseconds, wrapping at `u32::MAX`. A measurement
+/// is returned when the time tag reaches the next sampling time, otherwise `None`.
+/// Wheel ticks, speed ticks and speed each follow their own schedule.
+#[derive(Debug, Clone)]
+pub struct OdometerSampler {
+    period: u32,
+    /// Next sampling time of the wheel ticks, speed ticks and speed
+    next: [Option<u32>; 3],
+}
+
+impl OdometerSampler {
+    /// Samples at `rate` Hz, at most 1000
+    pub fn new(rate: u32) -> Self {
+        Self {
+            period: 1000 / rate.clamp(1, 1000),
+            next: [None; 3],
+        }
+    }
+
+    const WHEEL_TICKS: usize = 0;
+    const SPEED_TICKS: usize = 1;
+    const SPEED: usize = 2;
+
+    fn due(&mut self, schedule: usize, time_tag: u32) -> bool {
+        let next = &mut self.next[schedule];
+        match *next {
+            Some(at) if (time_tag.wrapping_sub(at) as i32) < 0 => false,
+            // Keep the schedule unless the host fell behind by a whole period
+            Some(at) if time_tag.wrapping_sub(at) < self.period => {
+                *next = Some(at.wrapping_add(self.period));
+                true
+            },
+            _ => {
+                *next = Some(time_tag.wrapping_add(self.period));
+                true
+            },
+        }
+    }
+
+    /// Cumulative tick counts of the front left, front right, rear left
+    /// and rear right wheels
+    pub fn wheel_ticks(
+        &mut self,
+        time_tag: u32,
+        counts: [u64; 4],
+        backward: bool,
+    ) -> Option<[EsfMeasInputData; 4]> {
+        if !self.due(Self::WHEEL_TICKS, time_tag) {
+            return None;
+        }
+        let types = [
+            EsfSensorType::FrontLeftWheelTicks,
+            EsfSensorType::FrontRightWheelTicks,
+            EsfSensorType::RearLeftWheelTicks,
+            EsfSensorType::RearRightWheelTicks,
+        ];
+        Some(core::array::from_fn(|i| {
+            Self::tick(types[i], counts[i], backward)
+        }))
+    }
+
+    /// Cumulative count of a single tick sensor, e.g. on the gearbox
+    pub fn speed_ticks(
+        &mut self,
+        time_tag: u32,
+        count: u64,
+        backward: bool,
+    ) -> Option<EsfMeasInputData> {
+        self.due(Self::SPEED_TICKS, time_tag)
+            .then(|| Self::tick(EsfSensorType::SpeedTick, count, backward))
+    }
+
+    /// Vehicle speed in m/s, negative when moving backward
+    pub fn speed(&mut self, time_tag: u32, speed: f32) -> Option<EsfMeasInputData> {
+        self.due(Self::SPEED, time_tag).then_some(EsfMeasInputData {
+            data_type: EsfSensorType::Speed,
+            value: SensorData::Value(speed),
+        })
+    }
+
+    /// The receiver expects counts wrapping at 23 bits
+    fn tick(data_type: EsfSensorType, count: u64, backward: bool) -> EsfMeasInputData {
+        let ticks = (count & u64::from(EsfSensorType::TICK_MASK)) as i32;
+        EsfMeasInputData {
+            data_type,
+            value: SensorData::Tick(if backward { -ticks } else { ticks }),
+        }
+    }
+}
+
 #[ubx_packet_recv]
 #[ubx(class = 0x10, id = 0x03, max_payload_len = 1240)]
 struct EsfRaw {
@@ -4222,6 +5166,16 @@ pub struct EsfRawData {
     pub sensor_time_tag: u32,
 }
 
+impl EsfRawData {
+    pub fn sensor_type(&self) -> EsfSensorType {
+        self.data_type.into()
+    }
+
+    pub fn value(&self) -> SensorData {
+        SensorData::decode(self.sensor_type(), self.data_field)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct EsfRawDataIter<'a>(core::slice::ChunksExact<'a, u8>);
 
@@ -4254,7 +5208,7 @@ impl core::iter::Iterator for EsfRawDataIter<'_> {
 }
 
 #[repr(u8)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EsfAlgStatus {
     UserDefinedAngles = 0,
@@ -4333,7 +5287,7 @@ impl fmt::Debug for EsfAlgFlags {
 #[ubx_extend_bitflags]
 #[ubx(from, rest_reserved)]
 bitflags! {
-    #[derive(Debug)]
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
     pub struct EsfAlgError: u8 {
         const TILT_ALG_ERROR = 0x01;
         const YAW_ALG_ERROR = 0x02;
@@ -4436,15 +5390,7 @@ impl EsfInitStatus1 {
     const INS_STATUS_MASK: u8 = 0x03;
 
     pub fn wheel_tick_init_status(self) -> EsfStatusWheelTickInit {
-        let bits = (self.0) & Self::WHEEL_TICK_MASK;
-        match bits {
-            0 => EsfStatusWheelTickInit::Off,
-            1 => EsfStatusWheelTickInit::Initializing,
-            2 => EsfStatusWheelTickInit::Initialized,
-            _ => {
-                panic!("Unexpected 2-bit value {}!", bits);
-            },
-        }
+        EsfStatusWheelTickInit::from((self.0) & Self::WHEEL_TICK_MASK)
     }
 
     pub fn wheel_tick_init_status_raw(self) -> u8 {
@@ -4454,13 +5400,8 @@ impl EsfInitStatus1 {
     pub fn mounting_angle_status(self) -> EsfStatusMountAngle {
         let bits = (self.0 >> 2) & Self::MOUNTING_ANGLE_STATUS_MASK;
         match bits {
-            0 => EsfStatusMountAngle::Off,
-            1 => EsfStatusMountAngle::Initializing,
-            2 => EsfStatusMountAngle::Initialized,
             3 => EsfStatusMountAngle::Initialized,
-            _ => {
-                panic!("Unexpected 3-bit value {}!", bits);
-            },
+            _ => EsfStatusMountAngle::from(bits),
         }
     }
 
@@ -4469,15 +5410,7 @@ impl EsfInitStatus1 {
     }
 
     pub fn ins_initialization_status(self) -> EsfStatusInsInit {
-        let bits = (self.0 >> 5) & Self::INS_STATUS_MASK;
-        match bits {
-            0 => EsfStatusInsInit::Off,
-            1 => EsfStatusInsInit::Initializing,
-            2 => EsfStatusInsInit::Initialized,
-            _ => {
-                panic!("Unexpected 2-bit value {}!", bits);
-            },
-        }
+        EsfStatusInsInit::from((self.0 >> 5) & Self::INS_STATUS_MASK)
     }
 
     pub fn ins_initialization_status_raw(self) -> u8 {
@@ -4495,27 +5428,33 @@ impl fmt::Debug for EsfInitStatus1 {
     }
 }
 
+#[ubx_extend]
+#[ubx(from, rest_reserved)]
 #[repr(u8)]
-#[derive(Copy, Clone, Debug)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub enum EsfStatusWheelTickInit {
     Off = 0,
     Initializing = 1,
     Initialized = 2,
 }
 
+#[ubx_extend]
+#[ubx(from, rest_reserved)]
 #[repr(u8)]
-#[derive(Copy, Clone, Debug)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub enum EsfStatusMountAngle {
     Off = 0,
     Initializing = 1,
     Initialized = 2,
 }
 
+#[ubx_extend]
+#[ubx(from, rest_reserved)]
 #[repr(u8)]
-#[derive(Copy, Clone, Debug)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub enum EsfStatusInsInit {
     Off = 0,
     Initializing = 1,
@@ -4533,15 +5472,7 @@ impl EsfInitStatus2 {
     }
 
     pub fn imu_init_status(self) -> EsfStatusImuInit {
-        let bits = (self.0) & 0x02;
-        match bits {
-            0 => EsfStatusImuInit::Off,
-            1 => EsfStatusImuInit::Initializing,
-            2 => EsfStatusImuInit::Initialized,
-            _ => {
-                panic!("Unexpected 2-bit value {}!", bits);
-            },
-        }
+        EsfStatusImuInit::from((self.0) & 0x03)
     }
 }
 
@@ -4553,9 +5484,11 @@ impl fmt::Debug for EsfInitStatus2 {
     }
 }
 
+#[ubx_extend]
+#[ubx(from, rest_reserved)]
 #[repr(u8)]
-#[derive(Copy, Clone, Debug)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub enum EsfStatusImuInit {
     Off = 0,
     Initializing = 1,
@@ -4659,14 +5592,14 @@ impl From<u8> for SensorStatus1 {
         let sensor_type: EsfSensorType = (s & 0x3F).into();
         Self {
             sensor_type,
-            used: (s >> 6) != 0,
-            ready: (s >> 7) != 0,
+            used: (s >> 6) & 0x01 != 0,
+            ready: (s >> 7) & 0x01 != 0,
         }
     }
 }
 
 #[repr(u8)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum EsfSensorType {
     None = 0,
@@ -4722,6 +5655,47 @@ impl From<u8> for EsfSensorType {
     }
 }
 
+impl EsfSensorType {
+    const TICK_MASK: u32 = 0x7FFFFF;
+    const TICK_BACKWARD: u32 = 0x800000;
+
+    /// Wheel and speed tick counters, their data field carries the direction in bit 23
+    pub(crate) fn is_tick(self) -> bool {
+        matches!(
+            self,
+            EsfSensorType::FrontLeftWheelTicks
+                | EsfSensorType::FrontRightWheelTicks
+                | EsfSensorType::RearLeftWheelTicks
+                | EsfSensorType::RearRightWheelTicks
+                | EsfSensorType::SpeedTick
+        )
+    }
+
+    /// Scale from the data field to the units of the sensor type,
+    /// `None` for tick counters and unknown sensors
+    pub(crate) fn scale(self) -> Option<f64> {
+        match self {
+            EsfSensorType::Speed => Some(1e-3),
+            EsfSensorType::GyroX | EsfSensorType::GyroY | EsfSensorType::GyroZ => {
+                Some(1.0 / 4096.0)
+            },
+            EsfSensorType::AccX | EsfSensorType::AccY | EsfSensorType::AccZ => Some(1.0 / 1024.0),
+            EsfSensorType::GyroTemp => Some(1e-2),
+            _ => None,
+        }
+    }
+
+    /// A 24 bit data field in two's complement
+    pub(crate) fn field_value(field: u32) -> i32 {
+        (field << 8) as i32 >> 8
+    }
+
+    /// Tick count and backward direction of a 24 bit data field
+    pub(crate) fn field_ticks(field: u32) -> (u32, bool) {
+        (field & Self::TICK_MASK, field & Self::TICK_BACKWARD != 0)
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Copy, Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
@@ -4742,7 +5716,7 @@ impl From<u8> for SensorStatus2 {
 }
 
 #[repr(u8)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum EsfSensorStatusCalibration {
     NotCalibrated = 0,
@@ -4762,7 +5736,7 @@ impl From<u8> for EsfSensorStatusCalibration {
 }
 
 #[repr(u8)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum EsfSensorStatusTime {
     NoData = 0,
@@ -4786,7 +5760,7 @@ impl From<u8> for EsfSensorStatusTime {
 #[ubx_extend_bitflags]
 #[ubx(from, into_raw, rest_reserved)]
 bitflags! {
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct EsfSensorFaults: u8 {
     const BAD_MEASUREMENT = 1;
     const BAD_TIME_TAG = 2;
@@ -5041,8 +6015,14 @@ struct NavVelECEF {
     s_acc: u32,
 }
 
-#[ubx_packet_recv]
-#[ubx(class = 0x13, id = 0x00, fixed_payload_len = 68)]
+/// GPS ephemeris assistance UBX-MGA-GPS-EPH
+#[ubx_packet_recv_send]
+#[ubx(
+    class = 0x13,
+    id = 0x00,
+    fixed_payload_len = 68,
+    flags = "default_for_builder"
+)]
 struct MgaGpsEph {
     msg_type: u8,
     version: u8,
@@ -5051,53 +6031,192 @@ struct MgaGpsEph {
     fit_interval: u8,
     ura_index: u8,
     sv_health: u8,
-    #[ubx(map_type = f64, scale = 2e-31)]
+    #[ubx(map_type = f64, scale = 4.656612873077393e-10)] // 2^-31
     tgd: i8,
     iodc: u16,
-    #[ubx(map_type = f64, scale = 2e+4)]
+    #[ubx(map_type = f64, scale = 16.0)] // 2^4
     toc: u16,
     reserved2: u8,
-    #[ubx(map_type = f64, scale = 2e-55)]
+    #[ubx(map_type = f64, scale = 2.7755575615628914e-17)] // 2^-55
     af2: i8,
-    #[ubx(map_type = f64, scale = 2e-43)]
+    #[ubx(map_type = f64, scale = 1.1368683772161603e-13)] // 2^-43
     afl: i16,
-    #[ubx(map_type = f64, scale = 2e-31)]
+    #[ubx(map_type = f64, scale = 4.656612873077393e-10)] // 2^-31
     af0: i32,
-    #[ubx(map_type = f64, scale = 2e-5)]
+    #[ubx(map_type = f64, scale = 0.03125)] // 2^-5
     crs: i16,
-    #[ubx(map_type = f64, scale = 2e-43)]
+    #[ubx(map_type = f64, scale = 1.1368683772161603e-13)] // 2^-43
     delta_n: i16,
-    #[ubx(map_type = f64, scale = 2e-31)]
+    #[ubx(map_type = f64, scale = 4.656612873077393e-10)] // 2^-31
     m0: i32,
-    #[ubx(map_type = f64, scale = 2e-29)]
+    #[ubx(map_type = f64, scale = 1.862645149230957e-09)] // 2^-29
     cuc: i16,
-    #[ubx(map_type = f64, scale = 2e-29)]
+    #[ubx(map_type = f64, scale = 1.862645149230957e-09)] // 2^-29
     cus: i16,
-    #[ubx(map_type = f64, scale = 2e-33)]
+    #[ubx(map_type = f64, scale = 1.1641532182693481e-10)] // 2^-33
     e: u32,
-    #[ubx(map_type = f64, scale = 2e-19)]
+    #[ubx(map_type = f64, scale = 1.9073486328125e-06)] // 2^-19
     sqrt_a: u32,
-    #[ubx(map_type = f64, scale = 2e+4)]
+    #[ubx(map_type = f64, scale = 16.0)] // 2^4
     toe: u16,
-    #[ubx(map_type = f64, scale = 2e-29)]
+    #[ubx(map_type = f64, scale = 1.862645149230957e-09)] // 2^-29
     cic: i16,
-    #[ubx(map_type = f64, scale = 2e-31)]
+    #[ubx(map_type = f64, scale = 4.656612873077393e-10)] // 2^-31
     omega0: i32,
-    #[ubx(map_type = f64, scale = 2e-29)]
+    #[ubx(map_type = f64, scale = 1.862645149230957e-09)] // 2^-29
     cis: i16,
-    #[ubx(map_type = f64, scale = 2e-5)]
+    #[ubx(map_type = f64, scale = 0.03125)] // 2^-5
     crc: i16,
-    #[ubx(map_type = f64, scale = 2e-31)]
+    #[ubx(map_type = f64, scale = 4.656612873077393e-10)] // 2^-31
     i0: i32,
-    #[ubx(map_type = f64, scale = 2e-31)]
+    #[ubx(map_type = f64, scale = 4.656612873077393e-10)] // 2^-31
     omega: i32,
-    #[ubx(map_type = f64, scale = 2e-43)]
+    #[ubx(map_type = f64, scale = 1.1368683772161603e-13)] // 2^-43
     omega_dot: i32,
-    #[ubx(map_type = f64, scale = 2e-43)]
+    #[ubx(map_type = f64, scale = 1.1368683772161603e-13)] // 2^-43
     idot: i16,
     reserved3: [u8; 2],
 }
 
+/// Galileo ephemeris assistance UBX-MGA-GAL-EPH
+#[ubx_packet_recv_send]
+#[ubx(
+    class = 0x13,
+    id = 0x02,
+    fixed_payload_len = 76,
+    flags = "default_for_builder"
+)]
+struct MgaGalEph {
+    /// Message type: 0x01 for this type
+    msg_type: u8,
+    /// Message version: 0x00 for this version
+    version: u8,
+    sv_id: u8,
+    reserved1: u8,
+    /// Ephemeris and clock correction issue of data
+    iod_nav: u16,
+    #[ubx(map_type = f64, scale = 1.1368683772161603e-13)] // 2^-43
+    delta_n: i16,
+    #[ubx(map_type = f64, scale = 4.656612873077393e-10)] // 2^-31
+    m0: i32,
+    #[ubx(map_type = f64, scale = 1.1641532182693481e-10)] // 2^-33
+    e: u32,
+    #[ubx(map_type = f64, scale = 1.9073486328125e-06)] // 2^-19
+    sqrt_a: u32,
+    #[ubx(map_type = f64, scale = 4.656612873077393e-10)] // 2^-31
+    omega0: i32,
+    #[ubx(map_type = f64, scale = 4.656612873077393e-10)] // 2^-31
+    i0: i32,
+    #[ubx(map_type = f64, scale = 4.656612873077393e-10)] // 2^-31
+    omega: i32,
+    #[ubx(map_type = f64, scale = 1.1368683772161603e-13)] // 2^-43
+    omega_dot: i32,
+    #[ubx(map_type = f64, scale = 1.1368683772161603e-13)] // 2^-43
+    idot: i16,
+    #[ubx(map_type = f64, scale = 1.862645149230957e-09)] // 2^-29
+    cuc: i16,
+    #[ubx(map_type = f64, scale = 1.862645149230957e-09)] // 2^-29
+    cus: i16,
+    #[ubx(map_type = f64, scale = 0.03125)] // 2^-5
+    crc: i16,
+    #[ubx(map_type = f64, scale = 0.03125)] // 2^-5
+    crs: i16,
+    #[ubx(map_type = f64, scale = 1.862645149230957e-09)] // 2^-29
+    cic: i16,
+    #[ubx(map_type = f64, scale = 1.862645149230957e-09)] // 2^-29
+    cis: i16,
+    #[ubx(map_type = f64, scale = 60.0)]
+    toe: u16,
+    #[ubx(map_type = f64, scale = 5.820766091346741e-11)] // 2^-34
+    af0: i32,
+    #[ubx(map_type = f64, scale = 1.4210854715202004e-14)] // 2^-46
+    af1: i32,
+    #[ubx(map_type = f64, scale = 1.734723475976807e-18)] // 2^-59
+    af2: i8,
+    /// Signal in space accuracy index for E1/E5b
+    sisa_index_e1_e5b: u8,
+    #[ubx(map_type = f64, scale = 60.0)]
+    toc: u16,
+    /// E1/E5b broadcast group delay [s]
+    #[ubx(map_type = f64, scale = 2.3283064365386963e-10)] // 2^-32
+    bgd_e1_e5b: i16,
+    reserved2: [u8; 2],
+    health_e1b: u8,
+    data_validity_e1b: u8,
+    health_e5b: u8,
+    data_validity_e5b: u8,
+    reserved3: [u8; 4],
+}
+
+/// BeiDou ephemeris assistance UBX-MGA-BDS-EPH
+#[ubx_packet_recv_send]
+#[ubx(
+    class = 0x13,
+    id = 0x03,
+    fixed_payload_len = 88,
+    flags = "default_for_builder"
+)]
+struct MgaBdsEph {
+    /// Message type: 0x01 for this type
+    msg_type: u8,
+    /// Message version: 0x00 for this version
+    version: u8,
+    sv_id: u8,
+    reserved1: u8,
+    /// Autonomous satellite health flag
+    sat_h1: u8,
+    /// Age of data, clock
+    iodc: u8,
+    #[ubx(map_type = f64, scale = 1.3552527156068805e-20)] // 2^-66
+    a2: i16,
+    #[ubx(map_type = f64, scale = 8.881784197001252e-16)] // 2^-50
+    a1: i32,
+    #[ubx(map_type = f64, scale = 1.1641532182693481e-10)] // 2^-33
+    a0: i32,
+    #[ubx(map_type = f64, scale = 8.0)] // 2^3
+    toc: u32,
+    /// Equipment group delay differential [s]
+    #[ubx(map_type = f64, scale = 1e-10)]
+    tgd1: i16,
+    /// User range accuracy index
+    urai: u8,
+    /// Age of data, ephemeris
+    iode: u8,
+    #[ubx(map_type = f64, scale = 8.0)] // 2^3
+    toe: u32,
+    #[ubx(map_type = f64, scale = 1.9073486328125e-06)] // 2^-19
+    sqrt_a: u32,
+    #[ubx(map_type = f64, scale = 1.1641532182693481e-10)] // 2^-33
+    e: u32,
+    #[ubx(map_type = f64, scale = 4.656612873077393e-10)] // 2^-31
+    omega: i32,
+    #[ubx(map_type = f64, scale = 1.1368683772161603e-13)] // 2^-43
+    delta_n: i16,
+    #[ubx(map_type = f64, scale = 1.1368683772161603e-13)] // 2^-43
+    idot: i16,
+    #[ubx(map_type = f64, scale = 4.656612873077393e-10)] // 2^-31
+    m0: i32,
+    #[ubx(map_type = f64, scale = 4.656612873077393e-10)] // 2^-31
+    omega0: i32,
+    #[ubx(map_type = f64, scale = 1.1368683772161603e-13)] // 2^-43
+    omega_dot: i32,
+    #[ubx(map_type = f64, scale = 4.656612873077393e-10)] // 2^-31
+    i0: i32,
+    #[ubx(map_type = f64, scale = 4.656612873077393e-10)] // 2^-31
+    cuc: i32,
+    #[ubx(map_type = f64, scale = 4.656612873077393e-10)] // 2^-31
+    cus: i32,
+    #[ubx(map_type = f64, scale = 0.015625)] // 2^-6
+    crc: i32,
+    #[ubx(map_type = f64, scale = 0.015625)] // 2^-6
+    crs: i32,
+    #[ubx(map_type = f64, scale = 4.656612873077393e-10)] // 2^-31
+    cic: i32,
+    #[ubx(map_type = f64, scale = 4.656612873077393e-10)] // 2^-31
+    cis: i32,
+    reserved2: [u8; 4],
+}
+
 #[ubx_packet_recv]
 #[ubx(class = 0x02, id = 0x15, fixed_payload_len = 32)]
 #[derive(Debug)]
@@ -5107,7 +6226,9 @@ pub struct RxmRawxInfo {
     do_mes: f32,
     gnss_id: u8,
     sv_id: u8,
-    reserved2: u8,
+    /// Signal identifier, see `NavSig`. Only reported from protocol
+    /// version 27 on, older receivers leave this byte reserved as zero.
+    sig_id: u8,
     freq_id: u8,
     lock_time: u16,
     cno: u8,
@@ -5176,6 +6297,63 @@ struct SecUniqId {
     unique_id: [u8; 5],
 }
 
+/// Signal security information, version 1 or 2
+#[cfg(any(feature = "ubx_proto27", feature = "ubx_proto31"))]
+#[ubx_packet_recv]
+#[ubx(class = 0x27, id = 0x09, max_payload_len = 1028)]
+struct SecSig {
+    version: u8,
+    #[ubx(map_type = core::slice::Iter<'a, u8>, from = byte_iter)]
+    data: [u8; 0],
+}
+
+#[cfg(any(feature = "ubx_proto27", feature = "ubx_proto31"))]
+impl SecSigRef<'_> {
+    /// Jamming and spoofing flags, each with the enable bit followed by the state
+    fn flags(&self) -> (u8, u8) {
+        let data = self.data().as_slice();
+        let byte = |i: usize| data.get(i).copied().unwrap_or(0);
+        match self.version() {
+            1 => (byte(3), byte(7)),
+            _ => (byte(0) & 0x07, byte(0) >> 3),
+        }
+    }
+
+    pub fn jamming_detection_enabled(&self) -> bool {
+        self.flags().0 & 0x01 != 0
+    }
+
+    pub fn jamming_state(&self) -> JammingState {
+        (self.flags().0 >> 1).into()
+    }
+
+    pub fn spoofing_detection_enabled(&self) -> bool {
+        self.flags().1 & 0x01 != 0
+    }
+
+    pub fn spoofing_state(&self) -> SpoofingState {
+        (self.flags().1 >> 1).into()
+    }
+
+    /// Center frequencies in kHz of the jamming detection with their
+    /// jamming indication, from version 2
+    pub fn jamming_frequencies(&self) -> impl Iterator<Item = (u32, bool)> + '_ {
+        let data = self.data().as_slice();
+        let count = match self.version() {
+            1 => 0,
+            _ => usize::from(data.get(2).copied().unwrap_or(0)),
+        };
+        data.get(3..)
+            .unwrap_or_default()
+            .chunks_exact(4)
+            .take(count)
+            .map(|chunk| {
+                let value = u32::from_le_bytes(chunk.try_into().unwrap());
+                (value & 0xFFFFFF, (value >> 24) & 0x01 != 0)
+            })
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -5231,4 +6409,32 @@ mod test {
         assert_eq!("SBAS;IMES;QZSS", it.next().unwrap());
         assert_eq!(None, it.next());
     }
+
+    #[test]
+    fn odometer_sampler_rate() {
+        let mut sampler = OdometerSampler::new(10);
+        assert!(sampler.speed(1000, 1.0).is_some());
+        assert!(sampler.speed(1050, 1.0).is_none());
+        assert!(sampler.speed(1102, 1.0).is_some());
+        // The schedule is kept despite the late sample
+        assert!(sampler.speed(1200, 1.0).is_some());
+        // and restarted after a gap
+        assert!(sampler.speed(5000, 1.0).is_some());
+        assert!(sampler.speed(5099, 1.0).is_none());
+
+        let ticks = sampler
+            .wheel_ticks(5100, [1, 2, 0x80_0003, 4], true)
+            .unwrap();
+        assert_eq!(ticks[0].data_type, EsfSensorType::FrontLeftWheelTicks);
+        assert_eq!(ticks[2].value, SensorData::Tick(-3));
+        assert_eq!(ticks[3].data_type, EsfSensorType::RearRightWheelTicks);
+        // Speed ticks sampled at the same time tag as the wheel ticks
+        assert!(sampler.speed_ticks(5100, 7, false).is_some());
+        assert!(sampler.wheel_ticks(5150, [0; 4], false).is_none());
+
+        let mut sampler = OdometerSampler::new(10);
+        assert!(sampler.speed(u32::MAX - 50, 1.0).is_some());
+        assert!(sampler.speed(10, 1.0).is_none());
+        assert!(sampler.speed(50, 1.0).is_some());
+    }
 }
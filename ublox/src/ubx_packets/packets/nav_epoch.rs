@@ -0,0 +1,181 @@
+use alloc::vec::Vec;
+
+use super::*;
+
+/// All navigation messages of one navigation epoch, sharing the same iTOW.
+///
+/// The messages are stored as owned copies of their payloads and
+/// accessed through the usual `Ref` types.
+#[derive(Debug, Clone, Default)]
+pub struct NavEpoch {
+    itow: u32,
+    complete: bool,
+    pvt: Option<Vec<u8>>,
+    dop: Option<Vec<u8>>,
+    sat: Option<Vec<u8>>,
+    #[cfg(any(feature = "ubx_proto27", feature = "ubx_proto31"))]
+    sig: Option<Vec<u8>>,
+    cov: Option<Vec<u8>>,
+    rel_pos_ned: Option<Vec<u8>>,
+}
+
+impl NavEpoch {
+    fn new(itow: u32) -> Self {
+        Self {
+            itow,
+            ..Default::default()
+        }
+    }
+
+    /// GPS Millisecond Time of Week of this epoch
+    pub fn itow(&self) -> u32 {
+        self.itow
+    }
+
+    /// `true` if the epoch was closed by a [NavEoe] message,
+    /// `false` if it was closed because a message of the next epoch arrived first
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    pub fn pvt(&self) -> Option<NavPvtRef<'_>> {
+        self.pvt.as_deref().map(NavPvtRef)
+    }
+
+    pub fn dop(&self) -> Option<NavDopRef<'_>> {
+        self.dop.as_deref().map(NavDopRef)
+    }
+
+    pub fn sat(&self) -> Option<NavSatRef<'_>> {
+        self.sat.as_deref().map(NavSatRef)
+    }
+
+    #[cfg(any(feature = "ubx_proto27", feature = "ubx_proto31"))]
+    pub fn sig(&self) -> Option<NavSigRef<'_>> {
+        self.sig.as_deref().map(NavSigRef)
+    }
+
+    pub fn cov(&self) -> Option<NavCovRef<'_>> {
+        self.cov.as_deref().map(NavCovRef)
+    }
+
+    pub fn rel_pos_ned(&self) -> Option<NavRelPosNedRef<'_>> {
+        self.rel_pos_ned.as_deref().map(NavRelPosNedRef)
+    }
+}
+
+/// Groups the navigation messages of an epoch into a single [NavEpoch].
+///
+/// An epoch is emitted when its [NavEoe] arrives or, if NAV-EOE is not enabled,
+/// when the first message of the next epoch (with a different iTOW) arrives.
+/// Messages not belonging to a navigation epoch are ignored.
+#[derive(Debug, Default)]
+pub struct NavEpochAssembler {
+    current: Option<NavEpoch>,
+}
+
+impl NavEpochAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a packet to the current epoch. Returns the previous epoch
+    /// if this packet finished it.
+    pub fn push(&mut self, packet: &PacketRef<'_>) -> Option<NavEpoch> {
+        let (itow, payload) = match packet {
+            PacketRef::NavPvt(pack) => (pack.itow(), pack.0),
+            PacketRef::NavDop(pack) => (pack.itow(), pack.0),
+            PacketRef::NavSat(pack) => (pack.itow(), pack.0),
+            #[cfg(any(feature = "ubx_proto27", feature = "ubx_proto31"))]
+            PacketRef::NavSig(pack) => (pack.itow(), pack.0),
+            PacketRef::NavCov(pack) => (pack.itow(), pack.0),
+            PacketRef::NavRelPosNed(pack) => (pack.itow(), pack.0),
+            PacketRef::NavEoe(pack) => {
+                let finished = self.take_if_other_epoch(pack.itow());
+                if finished.is_some() {
+                    return finished;
+                }
+                return self.current.take().map(|mut epoch| {
+                    epoch.complete = true;
+                    epoch
+                });
+            },
+            _ => return None,
+        };
+
+        let finished = self.take_if_other_epoch(itow);
+        let epoch = self.current.get_or_insert_with(|| NavEpoch::new(itow));
+        let slot = match packet {
+            PacketRef::NavPvt(_) => &mut epoch.pvt,
+            PacketRef::NavDop(_) => &mut epoch.dop,
+            PacketRef::NavSat(_) => &mut epoch.sat,
+            #[cfg(any(feature = "ubx_proto27", feature = "ubx_proto31"))]
+            PacketRef::NavSig(_) => &mut epoch.sig,
+            PacketRef::NavCov(_) => &mut epoch.cov,
+            PacketRef::NavRelPosNed(_) => &mut epoch.rel_pos_ned,
+            _ => unreachable!(),
+        };
+        *slot = Some(payload.to_vec());
+        finished
+    }
+
+    /// Returns the (incomplete) epoch assembled so far, for example at the end of a stream
+    pub fn flush(&mut self) -> Option<NavEpoch> {
+        self.current.take()
+    }
+
+    fn take_if_other_epoch(&mut self, itow: u32) -> Option<NavEpoch> {
+        match self.current {
+            Some(ref epoch) if epoch.itow != itow => self.current.take(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn dop(itow: u32) -> [u8; 18] {
+        let mut payload = [0; 18];
+        payload[..4].copy_from_slice(&itow.to_le_bytes());
+        payload
+    }
+
+    #[test]
+    fn emits_on_eoe() {
+        let mut assembler = NavEpochAssembler::new();
+        let payload = dop(1000);
+        assert!(assembler
+            .push(&PacketRef::NavDop(NavDopRef(&payload)))
+            .is_none());
+
+        let eoe = 1000u32.to_le_bytes();
+        let epoch = assembler.push(&PacketRef::NavEoe(NavEoeRef(&eoe))).unwrap();
+        assert_eq!(epoch.itow(), 1000);
+        assert!(epoch.is_complete());
+        assert!(epoch.dop().is_some());
+        assert!(epoch.pvt().is_none());
+        assert!(assembler.flush().is_none());
+    }
+
+    #[test]
+    fn emits_on_itow_change() {
+        let mut assembler = NavEpochAssembler::new();
+        let first = dop(1000);
+        let second = dop(2000);
+        assert!(assembler
+            .push(&PacketRef::NavDop(NavDopRef(&first)))
+            .is_none());
+
+        let epoch = assembler
+            .push(&PacketRef::NavDop(NavDopRef(&second)))
+            .unwrap();
+        assert_eq!(epoch.itow(), 1000);
+        assert!(!epoch.is_complete());
+
+        let epoch = assembler.flush().unwrap();
+        assert_eq!(epoch.itow(), 2000);
+        assert_eq!(epoch.dop().unwrap().itow(), 2000);
+    }
+}
@@ -12,6 +12,7 @@ define_recv_packets!(
         CfgAnt,
         CfgEsfAlg,
         CfgEsfWt,
+        CfgValGet,
         CfgItfm,
         CfgGnss,
         CfgNav5,
@@ -43,8 +44,13 @@ define_recv_packets!(
         MgaGpsIono,
         MgaGpsEph,
         MgaGloEph,
+        MgaAno,
+        MgaDbd,
+        MgaGalEph,
+        MgaBdsEph,
         NavAtt,
         NavClock,
+        NavCov,
         NavDop,
         NavEoe,
         NavHpPosLlh,
@@ -56,6 +62,7 @@ define_recv_packets!(
         NavSat,
         NavSolution,
         NavStatus,
+        NavSvin,
         NavVelNed,
         NavTimeUTC,
         NavTimeLs,
@@ -90,6 +97,7 @@ define_recv_packets!(
         CfgTp5,
         CfgEsfAlg,
         CfgEsfWt,
+        CfgValGet,
         EsfAlg,
         EsfIns,
         EsfMeas,
@@ -103,12 +111,18 @@ define_recv_packets!(
         MonVer,
         MonGnss,
         MonHw,
+        MonRf,
         MgaAck,
         MgaGpsIono,
         MgaGpsEph,
         MgaGloEph,
+        MgaAno,
+        MgaDbd,
+        MgaGalEph,
+        MgaBdsEph,
         NavAtt,
         NavClock,
+        NavCov,
         NavDop,
         NavEoe,
         NavHpPosLlh,
@@ -121,6 +135,7 @@ define_recv_packets!(
         NavSat,
         NavSolution,
         NavStatus,
+        NavSvin,
         NavVelNed,
         NavTimeUTC,
         NavTimeLs,
@@ -128,6 +143,7 @@ define_recv_packets!(
         RxmRawx,
         RxmRtcm,
         RxmSfrbx,
+        SecSig,
         SecUniqId,
         TimSvin,
         TimTp,
@@ -154,6 +170,7 @@ define_recv_packets!(
         CfgTp5,
         CfgEsfAlg,
         CfgEsfWt,
+        CfgValGet,
         EsfAlg,
         EsfIns,
         EsfMeas,
@@ -167,12 +184,18 @@ define_recv_packets!(
         MonVer,
         MonGnss,
         MonHw,
+        MonRf,
         MgaAck,
         MgaGpsIono,
         MgaGpsEph,
         MgaGloEph,
+        MgaAno,
+        MgaDbd,
+        MgaGalEph,
+        MgaBdsEph,
         NavAtt,
         NavClock,
+        NavCov,
         NavDop,
         NavEoe,
         NavHpPosLlh,
@@ -185,6 +208,7 @@ define_recv_packets!(
         NavSig,
         NavSolution,
         NavStatus,
+        NavSvin,
         NavVelNed,
         NavTimeUTC,
         NavTimeLs,
@@ -192,6 +216,7 @@ define_recv_packets!(
         RxmRawx,
         RxmRtcm,
         RxmSfrbx,
+        SecSig,
         SecUniqId,
         TimSvin,
         TimTp,
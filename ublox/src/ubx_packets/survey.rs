@@ -0,0 +1,288 @@
+//! Survey-in and fixed position configuration of base stations
+
+use super::cfg_val::{CfgVal, TmodePositionType, TmodeRcvrMode};
+use super::packets::*;
+use super::types::{Position, PositionECEF};
+
+/// Requirements a survey-in has to meet before its mean position is valid
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurveyIn {
+    /// Minimum observation time in seconds
+    pub min_duration: u32,
+    /// Required 3D accuracy of the mean position in meters
+    pub accuracy_limit: f64,
+}
+
+impl SurveyIn {
+    /// CFG-TMODE3 starting the survey-in
+    pub fn tmode3(&self) -> CfgTmode3Builder {
+        CfgTmode3Builder {
+            rcvr_mode: CfgTmode3RcvrMode::SurveyIn,
+            sv_in_min_duration: self.min_duration,
+            sv_in_accur_limit: self.accuracy_limit,
+            ..Default::default()
+        }
+    }
+
+    /// Configuration items starting the survey-in, for CFG-VALSET
+    pub fn cfg_vals(&self) -> [CfgVal; 3] {
+        [
+            CfgVal::TmodeMode(TmodeRcvrMode::SurveyIn),
+            CfgVal::TmodeSvinMinDur(self.min_duration),
+            CfgVal::TmodeSvinAccLimit(tenth_mm(self.accuracy_limit)),
+        ]
+    }
+}
+
+/// Position of the antenna reference point of a base station
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy)]
+pub enum FixedPosition {
+    Ecef(PositionECEF),
+    /// `alt` being the height above the ellipsoid
+    Llh(Position),
+}
+
+/// Fixed base station mode
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy)]
+pub struct FixedBase {
+    pub position: FixedPosition,
+    /// 3D accuracy of the position in meters
+    pub accuracy: f64,
+}
+
+impl FixedBase {
+    /// CFG-TMODE3 switching to fixed mode, using the high precision fields
+    pub fn tmode3(&self) -> CfgTmode3Builder {
+        let (flags, [x, y, z]) = match self.position {
+            FixedPosition::Ecef(pos) => (CfgTmode3Flags::empty(), ecef_parts(&pos)),
+            FixedPosition::Llh(pos) => (CfgTmode3Flags::LLA, llh_parts(&pos)),
+        };
+        // The getters scale the standard precision fields by 1e-2
+        CfgTmode3Builder {
+            rcvr_mode: CfgTmode3RcvrMode::FixedMode,
+            flags,
+            ecef_x_or_lat: f64::from(x.0) * 1e-2,
+            ecef_y_or_lon: f64::from(y.0) * 1e-2,
+            ecef_z_or_alt: f64::from(z.0) * 1e-2,
+            ecef_x_or_lat_hp: f32::from(x.1),
+            ecef_y_or_lon_hp: f32::from(y.1),
+            ecef_z_or_alt_hp: f32::from(z.1),
+            fixed_pos_acc: self.accuracy,
+            ..Default::default()
+        }
+    }
+
+    /// Configuration items switching to fixed mode, for CFG-VALSET
+    pub fn cfg_vals(&self) -> [CfgVal; 9] {
+        let accuracy = CfgVal::TmodeFixedPosAcc(tenth_mm(self.accuracy));
+        let mode = CfgVal::TmodeMode(TmodeRcvrMode::Fixed);
+        match self.position {
+            FixedPosition::Ecef(pos) => {
+                let [x, y, z] = ecef_parts(&pos);
+                [
+                    mode,
+                    CfgVal::TmodePosType(TmodePositionType::Ecef),
+                    CfgVal::TmodeEcefX(x.0),
+                    CfgVal::TmodeEcefY(y.0),
+                    CfgVal::TmodeEcefZ(z.0),
+                    CfgVal::TmodeEcefXHp(x.1),
+                    CfgVal::TmodeEcefYHp(y.1),
+                    CfgVal::TmodeEcefZHp(z.1),
+                    accuracy,
+                ]
+            },
+            FixedPosition::Llh(pos) => {
+                let [lat, lon, height] = llh_parts(&pos);
+                [
+                    mode,
+                    CfgVal::TmodePosType(TmodePositionType::Llh),
+                    CfgVal::TmodeLat(lat.0),
+                    CfgVal::TmodeLon(lon.0),
+                    CfgVal::TmodeHeight(height.0),
+                    CfgVal::TmodeLatHp(lat.1),
+                    CfgVal::TmodeLonHp(lon.1),
+                    CfgVal::TmodeHeightHp(height.1),
+                    accuracy,
+                ]
+            },
+        }
+    }
+}
+
+/// Progress of a survey-in, from NAV-SVIN or TIM-SVIN
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurveyInStatus {
+    /// Observation time in seconds
+    pub duration: u32,
+    pub mean: PositionECEF,
+    /// 3D accuracy of the mean position in meters
+    pub accuracy: f64,
+    pub observations: u32,
+    /// The mean position meets the survey-in requirements
+    pub valid: bool,
+    pub active: bool,
+}
+
+impl SurveyInStatus {
+    /// The survey-in finished with a valid position
+    pub fn is_complete(&self) -> bool {
+        self.valid && !self.active
+    }
+
+    /// Fixed mode at the mean position
+    pub fn fixed_base(&self) -> FixedBase {
+        FixedBase {
+            position: FixedPosition::Ecef(self.mean),
+            accuracy: self.accuracy,
+        }
+    }
+}
+
+impl<'a> From<&NavSvinRef<'a>> for SurveyInStatus {
+    fn from(svin: &NavSvinRef<'a>) -> Self {
+        Self {
+            duration: svin.dur(),
+            mean: PositionECEF::from(svin),
+            accuracy: svin.mean_acc(),
+            observations: svin.obs(),
+            valid: svin.valid(),
+            active: svin.active(),
+        }
+    }
+}
+
+impl<'a> From<&TimSvinRef<'a>> for SurveyInStatus {
+    fn from(svin: &TimSvinRef<'a>) -> Self {
+        Self {
+            duration: svin.dur(),
+            mean: PositionECEF::from(svin),
+            // 3D variance in mm^2
+            accuracy: libm::sqrt(f64::from(svin.mean_v().max(0))) * 1e-3,
+            observations: svin.obs(),
+            valid: svin.valid() == 1,
+            active: svin.active() == 1,
+        }
+    }
+}
+
+fn tenth_mm(meters: f64) -> u32 {
+    libm::round(meters * 1e4) as u32
+}
+
+/// Splits `value` into a standard precision part in `unit` and a high
+/// precision part in `unit / 100`
+fn split(value: f64, unit: f64) -> (i32, i8) {
+    let fine = libm::round(value / unit * 100.0) as i64;
+    ((fine / 100) as i32, (fine % 100) as i8)
+}
+
+/// Coordinates in cm and 0.1 mm
+fn ecef_parts(pos: &PositionECEF) -> [(i32, i8); 3] {
+    [split(pos.x, 1e-2), split(pos.y, 1e-2), split(pos.z, 1e-2)]
+}
+
+/// Latitude and longitude in 1e-7 and 1e-9 degrees, height in cm and 0.1 mm
+fn llh_parts(pos: &Position) -> [(i32, i8); 3] {
+    [
+        split(pos.lat, 1e-7),
+        split(pos.lon, 1e-7),
+        split(pos.alt, 1e-2),
+    ]
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod test {
+    use super::*;
+    use crate::{PacketRef, Parser};
+
+    #[test]
+    fn fixed_base_high_precision() {
+        let base = FixedBase {
+            position: FixedPosition::Ecef(PositionECEF {
+                x: 4027881.3388,
+                y: 307045.6001,
+                z: -4919474.9114,
+            }),
+            accuracy: 0.0125,
+        };
+        let [_, _, x, y, z, x_hp, y_hp, z_hp, acc] = base.cfg_vals();
+        assert_eq!(x, CfgVal::TmodeEcefX(402788133));
+        assert_eq!(y, CfgVal::TmodeEcefY(30704560));
+        assert_eq!(z, CfgVal::TmodeEcefZ(-491947491));
+        assert_eq!(x_hp, CfgVal::TmodeEcefXHp(88));
+        assert_eq!(y_hp, CfgVal::TmodeEcefYHp(1));
+        assert_eq!(z_hp, CfgVal::TmodeEcefZHp(-14));
+        assert_eq!(acc, CfgVal::TmodeFixedPosAcc(125));
+
+        let bytes = base.tmode3().into_packet_bytes();
+        let mut parser = Parser::default();
+        let mut it = parser.consume(&bytes);
+        match it.next() {
+            Some(Ok(PacketRef::CfgTmode3(cfg))) => {
+                assert_eq!(cfg.rcvr_mode(), CfgTmode3RcvrMode::FixedMode);
+                assert_eq!(
+                    &bytes[10..22],
+                    &[0x25, 0x0f, 0x02, 0x18, 0xb0, 0x83, 0xd4, 0x01, 0x1d, 0x7a, 0xad, 0xe2]
+                );
+                assert_eq!(&bytes[22..25], &[88, 1, (-14i8) as u8]);
+                let pos = PositionECEF::from(&cfg);
+                assert!((pos.x - 4027881.3388).abs() < 1e-6);
+                assert!((pos.z + 4919474.9114).abs() < 1e-6);
+            },
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn fixed_base_llh() {
+        let base = FixedBase {
+            position: FixedPosition::Llh(Position {
+                lat: 47.285_220_166,
+                lon: -8.565_253_1,
+                alt: 546.5891,
+            }),
+            accuracy: 0.02,
+        };
+        let [mode, pos_type, lat, lon, height, lat_hp, lon_hp, height_hp, _] = base.cfg_vals();
+        assert_eq!(mode, CfgVal::TmodeMode(TmodeRcvrMode::Fixed));
+        assert_eq!(pos_type, CfgVal::TmodePosType(TmodePositionType::Llh));
+        assert_eq!(lat, CfgVal::TmodeLat(472852201));
+        assert_eq!(lat_hp, CfgVal::TmodeLatHp(66));
+        assert_eq!(lon, CfgVal::TmodeLon(-85652531));
+        assert_eq!(lon_hp, CfgVal::TmodeLonHp(0));
+        assert_eq!(height, CfgVal::TmodeHeight(54658));
+        assert_eq!(height_hp, CfgVal::TmodeHeightHp(91));
+
+        let cfg = base.tmode3();
+        assert!(cfg.flags.contains(CfgTmode3Flags::LLA));
+        let bytes = cfg.into_packet_bytes();
+        assert_eq!(
+            i32::from_le_bytes(bytes[10..14].try_into().unwrap()),
+            472852201
+        );
+    }
+
+    #[test]
+    fn survey_in_start() {
+        let survey = SurveyIn {
+            min_duration: 120,
+            accuracy_limit: 2.0,
+        };
+        assert_eq!(
+            survey.cfg_vals(),
+            [
+                CfgVal::TmodeMode(TmodeRcvrMode::SurveyIn),
+                CfgVal::TmodeSvinMinDur(120),
+                CfgVal::TmodeSvinAccLimit(20000),
+            ]
+        );
+        let bytes = survey.tmode3().into_packet_bytes();
+        assert_eq!(bytes[8], 1);
+        assert_eq!(&bytes[30..34], &120u32.to_le_bytes());
+        assert_eq!(&bytes[34..38], &20000u32.to_le_bytes());
+    }
+}
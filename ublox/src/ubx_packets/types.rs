@@ -1,3 +1,4 @@
+use super::geodesy::PositionNED;
 use super::packets::*;
 use crate::error::DateTimeError;
 use chrono::prelude::*;
@@ -13,13 +14,14 @@ pub struct Position {
     /// Latitude in degrees
     pub lat: f64,
 
-    /// Altitude in meters
+    /// Altitude in meters. Above mean sea level when converted from packets,
+    /// taken as height above the WGS84 ellipsoid by the geodetic conversions.
     pub alt: f64,
 }
 
 /// Represents a world position in the ECEF coordinate system
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct PositionECEF {
     /// x coordinates in meters
     pub x: f64,
@@ -41,6 +43,126 @@ pub struct Velocity {
     pub heading: f64, // degrees
 }
 
+/// Solution type of a relative position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RelativePositionQuality {
+    /// The relative position is not valid
+    Invalid,
+    /// Valid, without carrier phase solution
+    Differential,
+    /// Carrier phase solution with floating ambiguities
+    Float,
+    /// Carrier phase solution with fixed ambiguities
+    Fixed,
+}
+
+/// Baseline from the reference station (or moving base) to the rover,
+/// can be constructed from NavRelPosNed packets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RelativePosition {
+    pub ref_station_id: u16,
+    /// GPS Millisecond Time of Week
+    pub itow: u32,
+    /// Baseline in meters, including the high precision components
+    pub ned: PositionNED,
+    /// Accuracy of the baseline components in meters
+    pub accuracy: PositionNED,
+    /// Length of the baseline in meters
+    pub length: f64,
+    /// Accuracy of the length in meters, not reported by the 40 byte version
+    pub length_accuracy: Option<f64>,
+    /// Heading of the baseline in degrees, if valid.
+    /// Computed from the baseline by the 40 byte version.
+    pub heading: Option<f64>,
+    /// Accuracy of the heading in degrees, not reported by the 40 byte version
+    pub heading_accuracy: Option<f64>,
+    /// Elevation of the rover above the horizontal plane of the base, in degrees
+    pub pitch: f64,
+    pub quality: RelativePositionQuality,
+    /// The reference is a moving base
+    pub moving_base: bool,
+}
+
+impl<'a> From<&NavRelPosNedRef<'a>> for RelativePosition {
+    fn from(packet: &NavRelPosNedRef<'a>) -> Self {
+        // Standard precision in cm, high precision and accuracies in 0.1 mm
+        let meters = |cm: i32, hp: i8| 1e-2 * f64::from(cm) + 1e-4 * f64::from(hp);
+        let ned = PositionNED {
+            north: meters(packet.rel_pos_n(), packet.rel_pos_hpn()),
+            east: meters(packet.rel_pos_e(), packet.rel_pos_hpe()),
+            down: meters(packet.rel_pos_d(), packet.rel_pos_hpd()),
+        };
+        let accuracy = PositionNED {
+            north: 1e-4 * f64::from(packet.acc_n()),
+            east: 1e-4 * f64::from(packet.acc_e()),
+            down: 1e-4 * f64::from(packet.acc_d()),
+        };
+
+        let flags = packet.flags();
+        let quality = if !flags.rel_pos_valid() {
+            RelativePositionQuality::Invalid
+        } else {
+            match flags.carr_soln() {
+                CarrierPhaseRangeSolutionStatus::NoSolution => {
+                    RelativePositionQuality::Differential
+                },
+                CarrierPhaseRangeSolutionStatus::SolutionWithFloatingAmbiguities => {
+                    RelativePositionQuality::Float
+                },
+                CarrierPhaseRangeSolutionStatus::SolutionWithFixedAmbiguities => {
+                    RelativePositionQuality::Fixed
+                },
+                CarrierPhaseRangeSolutionStatus::Reserved => RelativePositionQuality::Invalid,
+            }
+        };
+
+        let horizontal = libm::hypot(ned.north, ned.east);
+        let pitch = libm::atan2(-ned.down, horizontal).to_degrees();
+
+        #[cfg(feature = "ubx_proto23")]
+        let (length, length_accuracy, heading, heading_accuracy) = {
+            let length = libm::sqrt(horizontal * horizontal + ned.down * ned.down);
+            let heading = flags.rel_pos_valid().then(|| {
+                let heading = libm::atan2(ned.east, ned.north).to_degrees();
+                if heading < 0.0 {
+                    heading + 360.0
+                } else {
+                    heading
+                }
+            });
+            (length, None, heading, None)
+        };
+
+        #[cfg(not(feature = "ubx_proto23"))]
+        let (length, length_accuracy, heading, heading_accuracy) = {
+            let length = meters(packet.rel_pos_length(), packet.rel_pos_hp_length());
+            let heading_valid = flags.rel_pos_heading_valid();
+            (
+                length,
+                Some(1e-4 * f64::from(packet.acc_length())),
+                heading_valid.then(|| 1e-5 * f64::from(packet.rel_pos_heading())),
+                heading_valid.then(|| 1e-5 * f64::from(packet.acc_heading())),
+            )
+        };
+
+        RelativePosition {
+            ref_station_id: packet.ref_station_id(),
+            itow: packet.itow(),
+            ned,
+            accuracy,
+            length,
+            length_accuracy,
+            heading,
+            heading_accuracy,
+            pitch,
+            quality,
+            moving_base: flags.is_moving(),
+        }
+    }
+}
+
 impl<'a> From<&NavPosLlhRef<'a>> for Position {
     fn from(packet: &NavPosLlhRef<'a>) -> Self {
         Position {
@@ -56,7 +178,7 @@ impl<'a> From<&NavHpPosLlhRef<'a>> for Position {
         Position {
             lon: packet.lon_degrees() + packet.lon_hp_degrees(),
             lat: packet.lat_degrees() + packet.lat_hp_degrees(),
-            alt: packet.height_msl() + packet.height_hp_msl(),
+            alt: packet.height_msl() + packet.height_hp_msl() * 1e-3,
         }
     }
 }
@@ -64,9 +186,9 @@ impl<'a> From<&NavHpPosLlhRef<'a>> for Position {
 impl<'a> From<&NavHpPosEcefRef<'a>> for PositionECEF {
     fn from(packet: &NavHpPosEcefRef<'a>) -> Self {
         PositionECEF {
-            x: 10e-2 * (packet.ecef_x_cm() + 0.1 * packet.ecef_x_hp_mm()),
-            y: 10e-2 * (packet.ecef_y_cm() + 0.1 * packet.ecef_y_hp_mm()),
-            z: 10e-2 * (packet.ecef_z_cm() + 0.1 * packet.ecef_z_hp_mm()),
+            x: 1e-2 * (packet.ecef_x_cm() + 0.1 * packet.ecef_x_hp_mm()),
+            y: 1e-2 * (packet.ecef_y_cm() + 0.1 * packet.ecef_y_hp_mm()),
+            z: 1e-2 * (packet.ecef_z_cm() + 0.1 * packet.ecef_z_hp_mm()),
         }
     }
 }
@@ -1,4 +1,6 @@
-use ublox::{CfgMsgSinglePortBuilder, NavPosLlh, NavStatus};
+use ublox::{
+    CfgCfgBuilder, CfgCfgDeviceMask, CfgCfgMask, CfgMsgSinglePortBuilder, NavPosLlh, NavStatus,
+};
 
 #[test]
 fn test_cfg_msg_simple() {
@@ -12,3 +14,43 @@ fn test_cfg_msg_simple() {
         CfgMsgSinglePortBuilder::set_rate_for::<NavStatus>(1).into_packet_bytes()
     );
 }
+
+#[test]
+fn test_cfg_cfg() {
+    assert_eq!(
+        [
+            0xB5, 0x62, 0x06, 0x09, 0x0D, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F, 0x1F, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x17, 0x71, 0xDF
+        ],
+        CfgCfgBuilder::save(CfgCfgMask::ALL_SECTIONS, CfgCfgDeviceMask::default())
+            .into_packet_bytes()
+    );
+
+    assert_eq!(
+        [
+            0xB5, 0x62, 0x06, 0x09, 0x0D, 0x00, 0x1F, 0x1F, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x1F, 0x1F, 0x00, 0x00, 0x01, 0x99, 0xD8
+        ],
+        CfgCfgBuilder::restore_defaults(CfgCfgDeviceMask::BBR).into_packet_bytes()
+    );
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn test_cfg_val_get_request() {
+    use ublox::{cfg_val::KeyId, CfgValGetLayer, CfgValGetRequestBuilder};
+
+    assert_eq!(
+        vec![
+            0xB5, 0x62, 0x06, 0x8B, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0x0F, 0x52, 0x00,
+            0xF9, 0x5E
+        ],
+        CfgValGetRequestBuilder {
+            version: 0,
+            layer: CfgValGetLayer::Ram,
+            position: 0,
+            keys: &[KeyId::group_wildcard(0x52)],
+        }
+        .into_packet_vec()
+    );
+}
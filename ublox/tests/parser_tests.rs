@@ -1,8 +1,15 @@
 #![cfg(feature = "alloc")]
 
+use ublox::navmsg::{Ephemeris, GlonassEphemeris, NavMessage};
 use ublox::{
-    CfgNav5Builder, CfgNav5DynModel, CfgNav5FixMode, CfgNav5Params, CfgNav5UtcStandard, PacketRef,
-    Parser, ParserError, ParserIter,
+    cfg_val::CfgVal, AntennaStatus, CfgNav5Builder, CfgNav5DynModel, CfgNav5FixMode, CfgNav5Params,
+    CfgNav5UtcStandard, CfgValGetLayer, EsfAlgStatus, EsfMeasInput, EsfMeasInputBuilder,
+    EsfMeasInputData, EsfSensorFaults, EsfSensorStatusCalibration, EsfSensorType,
+    EsfStatusFusionMode, EsfStatusImuInit, EsfStatusInsInit, EsfStatusMountAngle,
+    EsfStatusWheelTickInit, EsfTimeline, FusionEvent, FusionMonitor, GnssId, GnssTime,
+    InterferenceEvent, InterferenceIndications, InterferenceMonitor, PacketRef, Parser,
+    ParserError, ParserIter, PositionECEF, RelativePosition, RelativePositionQuality, SensorData,
+    SensorValue, SurveyInStatus, ThreatLevel, UbxPacketMeta, UbxUnknownPacketRef,
 };
 
 macro_rules! my_vec {
@@ -322,3 +329,960 @@ fn test_double_start_at_end() {
     }
     assert!(it.next().is_none());
 }
+
+#[test]
+fn test_cfg_val_get_skips_unknown_keys() {
+    #[rustfmt::skip]
+    let payload = [
+        0x01, 0x00, 0x00, 0x00, // version, layer, position
+        0x01, 0x00, 0x52, 0x40, 0x00, 0xc2, 0x01, 0x00, // CFG-UART1-BAUDRATE = 115200
+        0x01, 0x00, 0xff, 0x30, 0x34, 0x12, // unknown two-byte key
+        0x05, 0x00, 0x52, 0x10, 0x01, // CFG-UART1-ENABLED = true
+    ];
+    let bytes = UbxUnknownPacketRef {
+        payload: &payload,
+        class: 0x06,
+        msg_id: 0x8b,
+    }
+    .into_packet_vec();
+
+    let mut parser = Parser::default();
+    let mut it = parser.consume(&bytes);
+    match it.next() {
+        Some(Ok(PacketRef::CfgValGet(pack))) => {
+            assert_eq!(pack.layer(), CfgValGetLayer::Ram);
+            let items: Vec<CfgVal> = pack.cfg_data().collect();
+            assert_eq!(items.len(), 2);
+            assert!(matches!(items[0], CfgVal::Uart1Baudrate(115200)));
+            assert!(matches!(items[1], CfgVal::Uart1Enabled(true)));
+            let raw: Vec<&[u8]> = pack.cfg_data().raw_items().collect();
+            assert_eq!(raw.len(), 3);
+            assert_eq!(raw[1], &payload[12..18]);
+            assert_eq!(PacketRef::CfgValGet(pack).payload(), &payload);
+        },
+        _ => panic!(),
+    }
+    assert!(it.next().is_none());
+}
+
+#[test]
+fn test_tim_tm2_gnss_time() {
+    // time base UTC, time valid, rising edge in week 2000 at 0.5 s + 250 ns
+    let mut payload = [0u8; 28];
+    payload[1] = (2 << 3) | 0x40;
+    payload[4..6].copy_from_slice(&2000u16.to_le_bytes());
+    payload[8..12].copy_from_slice(&500u32.to_le_bytes());
+    payload[12..16].copy_from_slice(&250u32.to_le_bytes());
+    let bytes = UbxUnknownPacketRef {
+        payload: &payload,
+        class: 0x0d,
+        msg_id: 0x03,
+    }
+    .into_packet_vec();
+
+    let mut parser = Parser::default();
+    let mut it = parser.consume(&bytes);
+    match it.next() {
+        Some(Ok(PacketRef::TimTm2(pack))) => {
+            let time = GnssTime::try_from(&pack).unwrap();
+            assert_eq!(time.scale(), ublox::TimeScale::Utc);
+            assert_eq!(time.week(), 2000);
+            assert_eq!(time.tow_nanos(), 500_000_250);
+        },
+        _ => panic!(),
+    }
+    drop(it);
+
+    // time base GNSS, whose system is only known from the receiver configuration
+    payload[1] = (1 << 3) | 0x40;
+    let bytes = UbxUnknownPacketRef {
+        payload: &payload,
+        class: 0x0d,
+        msg_id: 0x03,
+    }
+    .into_packet_vec();
+    let mut it = parser.consume(&bytes);
+    match it.next() {
+        Some(Ok(PacketRef::TimTm2(pack))) => {
+            assert!(matches!(
+                GnssTime::try_from(&pack),
+                Err(ublox::DateTimeError::UnsupportedTimeBase)
+            ));
+            let time = GnssTime::from_tim_tm2(&pack, ublox::TimeScale::Galileo).unwrap();
+            assert_eq!(time.scale(), ublox::TimeScale::Galileo);
+            assert_eq!(time.week(), 2000);
+            assert_eq!(time.tow_nanos(), 500_000_250);
+        },
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn test_nav_timels_leap_second_event() {
+    // 2017-01-01 00:00:00 UTC leap second, announced for WN 1929 DN 7 one
+    // hour ahead, i.e. at GPS week 1930, 17 s
+    let mut payload = [0u8; 24];
+    payload[0..4].copy_from_slice(&601_217_000u32.to_le_bytes());
+    payload[9] = 17;
+    payload[10] = 2;
+    payload[11] = 1;
+    payload[12..16].copy_from_slice(&3600i32.to_le_bytes());
+    payload[16..18].copy_from_slice(&1929u16.to_le_bytes());
+    payload[18..20].copy_from_slice(&7u16.to_le_bytes());
+    payload[23] = 0x03;
+    let bytes = ubx_packet(0x01, 0x26, &payload);
+
+    let mut parser = Parser::default();
+    let mut it = parser.consume(&bytes);
+    match it.next() {
+        Some(Ok(PacketRef::NavTimeLs(pack))) => {
+            let time = GnssTime::try_from(&pack).unwrap();
+            assert_eq!(time.scale(), ublox::TimeScale::Utc);
+            assert_eq!(
+                time.to_naive_datetime(),
+                chrono::NaiveDate::from_ymd_opt(2017, 1, 1)
+                    .unwrap()
+                    .and_hms_opt(0, 0, 0)
+                    .unwrap()
+            );
+
+            let leap = ublox::LeapSeconds::try_from(&pack).unwrap();
+            let event = GnssTime::from_week_tow_secs(ublox::TimeScale::Gps, 1930, 17.0);
+            let before = GnssTime::from_week_tow_secs(ublox::TimeScale::Gps, 1930, 16.0);
+            assert_eq!(leap.gps_utc_at(&before), 17);
+            assert_eq!(leap.gps_utc_at(&event), 18);
+            // UTC midnight follows the inserted second 23:59:60 at GPS 17 s
+            assert_eq!(leap.gps_utc_at(&time), 18);
+            assert_eq!(
+                time.to_gps(&leap),
+                GnssTime::from_week_tow_secs(ublox::TimeScale::Gps, 1930, 18.0)
+            );
+        },
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn test_mga_gps_eph_scaling() {
+    let mut payload = [0u8; 68];
+    payload[0] = 0x01;
+    payload[2] = 12;
+    payload[10..12].copy_from_slice(&450u16.to_le_bytes());
+    payload[16..20].copy_from_slice(&(-2048i32).to_le_bytes());
+    payload[36..40].copy_from_slice(&2_702_000_000u32.to_le_bytes());
+    payload[40..42].copy_from_slice(&450u16.to_le_bytes());
+    let bytes = UbxUnknownPacketRef {
+        payload: &payload,
+        class: 0x13,
+        msg_id: 0x00,
+    }
+    .into_packet_vec();
+
+    let mut parser = Parser::default();
+    let mut it = parser.consume(&bytes);
+    match it.next() {
+        Some(Ok(PacketRef::MgaGpsEph(pack))) => {
+            let eph = Ephemeris::from(&pack);
+            assert_eq!(eph.sv_id, 12);
+            assert_eq!((eph.toc, eph.toe), (7200.0, 7200.0));
+            assert_eq!(eph.af0, -2048.0 / 2f64.powi(31));
+            assert_eq!(eph.sqrt_a, 2_702_000_000.0 / 2f64.powi(19));
+        },
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn test_mga_ephemeris_round_trip() {
+    let gps = Ephemeris {
+        gnss: GnssId::GPS,
+        sv_id: 7,
+        week: 0,
+        toe: 7200.0,
+        toc: 7200.0,
+        iode: 45,
+        iodc: 45,
+        accuracy: 2,
+        health: 0,
+        sqrt_a: 5153.6,
+        e: 0.012,
+        i0: 0.96,
+        idot: 1e-10,
+        omega0: -2.1,
+        omega_dot: -8e-9,
+        omega: 0.7,
+        m0: 1.3,
+        delta_n: 4.5e-9,
+        cuc: 1e-6,
+        cus: 8e-6,
+        crc: 230.5,
+        crs: -20.25,
+        cic: 1e-7,
+        cis: -5e-8,
+        af0: 1.2e-4,
+        af1: -3e-12,
+        af2: 0.0,
+        tgd: [-1.1e-8, 0.0],
+        l2_codes: 0,
+        l2p_flag: false,
+        fit_interval: false,
+    };
+    let glonass = GlonassEphemeris {
+        sv_id: 3,
+        frequency: -2,
+        tk: 0,
+        tb: 900 * 40,
+        nt: 0,
+        position: [12_345_678.0, -9_876_543.0, 19_000_000.0],
+        velocity: [1234.5, -2345.6, 345.7],
+        acceleration: [9.3e-7, 0.0, -1.86e-6],
+        tau_n: -2.5e-5,
+        gamma_n: 9.1e-13,
+        delta_tau_n: 2.8e-9,
+        health: 0,
+        age: 1,
+        accuracy: 3,
+        m: 1,
+    };
+
+    let mut bytes = NavMessage::Ephemeris(gps.clone()).to_mga().unwrap();
+    bytes.extend(
+        NavMessage::GlonassEphemeris(glonass.clone())
+            .to_mga()
+            .unwrap(),
+    );
+    let mut parser = Parser::default();
+    let mut it = parser.consume(&bytes);
+    match it.next() {
+        Some(Ok(PacketRef::MgaGpsEph(pack))) => {
+            let decoded = Ephemeris::from(&pack);
+            assert_eq!((decoded.sv_id, decoded.iodc, decoded.toe), (7, 45, 7200.0));
+            assert!((decoded.sqrt_a - gps.sqrt_a).abs() < 2f64.powi(-19));
+            assert!((decoded.m0 - gps.m0).abs() < 1e-9);
+            assert!((decoded.omega_dot - gps.omega_dot).abs() < 1e-12);
+            assert!((decoded.crc - gps.crc).abs() < 1e-9);
+            assert!((decoded.af0 - gps.af0).abs() < 2f64.powi(-31));
+        },
+        _ => panic!(),
+    }
+    match it.next() {
+        Some(Ok(PacketRef::MgaGloEph(pack))) => {
+            let decoded = GlonassEphemeris::from(&pack);
+            assert_eq!(
+                (decoded.sv_id, decoded.frequency, decoded.tb),
+                (3, -2, 36000)
+            );
+            for i in 0..3 {
+                assert!((decoded.position[i] - glonass.position[i]).abs() < 0.5);
+                assert!((decoded.velocity[i] - glonass.velocity[i]).abs() < 1e-3);
+            }
+            assert!((decoded.tau_n - glonass.tau_n).abs() < 1e-9);
+            assert!((decoded.gamma_n - glonass.gamma_n).abs() < 1e-12);
+        },
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn test_mga_ack_matches_message() {
+    let message = NavMessage::Klobuchar(
+        GnssId::GPS,
+        ublox::navmsg::KlobucharModel {
+            alpha: [1.1e-8, 0.0, -6e-8, 0.0],
+            beta: [90112.0, 0.0, -196608.0, 0.0],
+        },
+    )
+    .to_mga()
+    .unwrap();
+    let ack = [0x01, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00];
+    let bytes = UbxUnknownPacketRef {
+        payload: &ack,
+        class: 0x13,
+        msg_id: 0x60,
+    }
+    .into_packet_vec();
+
+    let mut parser = Parser::default();
+    let mut it = parser.consume(&bytes);
+    match it.next() {
+        Some(Ok(PacketRef::MgaAck(ack))) => {
+            assert!(ack.is_accepted());
+            assert!(ack.is_ack_for(&message));
+            assert!(!ack.is_ack_for(&message[..8]));
+        },
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn test_mga_ini_not_received() {
+    // MGA-INI-TIME_GNSS has the same length as MGA-INI-TIME_UTC
+    let mut payload = [0u8; 24];
+    payload[0] = 0x11;
+    let bytes = UbxUnknownPacketRef {
+        payload: &payload,
+        class: 0x13,
+        msg_id: 0x40,
+    }
+    .into_packet_vec();
+
+    let mut parser = Parser::default();
+    let mut it = parser.consume(&bytes);
+    assert!(matches!(it.next(), Some(Ok(PacketRef::Unknown(_)))));
+}
+
+#[test]
+fn test_mga_ano_date() {
+    let mut payload = [0u8; 76];
+    payload[2] = 21;
+    payload[3] = 2;
+    payload[4..7].copy_from_slice(&[26, 10, 18]);
+    payload[8] = 0xaa;
+    let bytes = UbxUnknownPacketRef {
+        payload: &payload,
+        class: 0x13,
+        msg_id: 0x20,
+    }
+    .into_packet_vec();
+
+    let mut parser = Parser::default();
+    let mut it = parser.consume(&bytes);
+    match it.next() {
+        Some(Ok(PacketRef::MgaAno(pack))) => {
+            assert_eq!((pack.gnss_id(), pack.sv_id()), (2, 21));
+            assert_eq!(pack.date(), chrono::NaiveDate::from_ymd_opt(2026, 10, 18));
+            assert_eq!(pack.data()[0], 0xaa);
+        },
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn test_mga_dbd_dump() {
+    let payload: Vec<u8> = (0..40).collect();
+    let mut bytes = UbxUnknownPacketRef {
+        payload: &payload,
+        class: 0x13,
+        msg_id: 0x80,
+    }
+    .into_packet_vec();
+    let entry = bytes.clone();
+    bytes.extend(
+        UbxUnknownPacketRef {
+            payload: &[0x01, 0x00, 0x00, 0x80, 0x01, 0x00, 0x00, 0x00],
+            class: 0x13,
+            msg_id: 0x60,
+        }
+        .into_packet_vec(),
+    );
+
+    let mut parser = Parser::default();
+    let mut it = parser.consume(&bytes);
+    match it.next() {
+        Some(Ok(PacketRef::MgaDbd(pack))) => {
+            assert_eq!(pack.data().as_slice(), &payload[12..]);
+            assert_eq!(pack.into_packet_vec(), entry);
+        },
+        _ => panic!(),
+    }
+    match it.next() {
+        Some(Ok(PacketRef::MgaAck(ack))) => assert_eq!(ack.dbd_count(), Some(1)),
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn test_nav_svin() {
+    let mut payload = [0u8; 40];
+    payload[8..12].copy_from_slice(&300u32.to_le_bytes());
+    payload[12..16].copy_from_slice(&402788133i32.to_le_bytes());
+    payload[16..20].copy_from_slice(&30704560i32.to_le_bytes());
+    payload[20..24].copy_from_slice(&(-491947491i32).to_le_bytes());
+    payload[24..27].copy_from_slice(&[88, 1, -14i8 as u8]);
+    payload[28..32].copy_from_slice(&15000u32.to_le_bytes());
+    payload[32..36].copy_from_slice(&300u32.to_le_bytes());
+    payload[36] = 1;
+    let bytes = UbxUnknownPacketRef {
+        payload: &payload,
+        class: 0x01,
+        msg_id: 0x3b,
+    }
+    .into_packet_vec();
+
+    let mut parser = Parser::default();
+    let mut it = parser.consume(&bytes);
+    match it.next() {
+        Some(Ok(PacketRef::NavSvin(svin))) => {
+            let status = SurveyInStatus::from(&svin);
+            assert!(status.is_complete());
+            assert_eq!(status.duration, 300);
+            assert_eq!(status.observations, 300);
+            assert_eq!(status.accuracy, 1.5);
+            assert!((status.mean.x - 4027881.3388).abs() < 1e-6);
+            assert!((status.mean.z + 4919474.9114).abs() < 1e-6);
+            assert_eq!(status.fixed_base().cfg_vals()[5], CfgVal::TmodeEcefXHp(88));
+        },
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn test_nav_svin_getters() {
+    let mut payload = [0u8; 40];
+    payload[12..16].copy_from_slice(&(-12345i32).to_le_bytes());
+    payload[24] = -67i8 as u8;
+    payload[28..32].copy_from_slice(&25u32.to_le_bytes());
+    payload[37] = 1;
+    let bytes = UbxUnknownPacketRef {
+        payload: &payload,
+        class: 0x01,
+        msg_id: 0x3b,
+    }
+    .into_packet_vec();
+
+    let mut parser = Parser::default();
+    let mut it = parser.consume(&bytes);
+    match it.next() {
+        Some(Ok(PacketRef::NavSvin(svin))) => {
+            assert_eq!(svin.mean_x_m(), -123.45);
+            assert!((svin.mean_x_hp_m() + 0.0067).abs() < 1e-12);
+            assert_eq!(svin.mean_acc(), 0.0025);
+            assert!(!svin.valid());
+            assert!(svin.active());
+            let pos = PositionECEF::from(&svin);
+            assert!((pos.x + 123.4567).abs() < 1e-9);
+            assert_eq!(pos.y, 0.0);
+        },
+        _ => panic!(),
+    }
+}
+
+#[cfg(feature = "ubx_proto23")]
+#[test]
+fn test_nav_relposned_relative_position() {
+    let mut payload = [0u8; 40];
+    payload[2..4].copy_from_slice(&7u16.to_le_bytes());
+    payload[8..12].copy_from_slice(&100i32.to_le_bytes());
+    payload[12..16].copy_from_slice(&100i32.to_le_bytes());
+    payload[16..20].copy_from_slice(&(-50i32).to_le_bytes());
+    payload[20..23].copy_from_slice(&[5, -5i8 as u8, 0]);
+    payload[24..28].copy_from_slice(&100u32.to_le_bytes());
+    // gnssFixOK, diffSoln, relPosValid, fixed carrier solution, isMoving
+    payload[36..40].copy_from_slice(&0x37u32.to_le_bytes());
+    let bytes = UbxUnknownPacketRef {
+        payload: &payload,
+        class: 0x01,
+        msg_id: 0x3c,
+    }
+    .into_packet_vec();
+
+    let mut parser = Parser::default();
+    let mut it = parser.consume(&bytes);
+    match it.next() {
+        Some(Ok(PacketRef::NavRelPosNed(pack))) => {
+            let rel = RelativePosition::from(&pack);
+            assert_eq!(rel.ref_station_id, 7);
+            assert!((rel.ned.north - 1.0005).abs() < 1e-9);
+            assert!((rel.ned.east - 0.9995).abs() < 1e-9);
+            assert_eq!(rel.ned.down, -0.5);
+            assert_eq!(rel.accuracy.north, 0.01);
+            assert_eq!(rel.quality, RelativePositionQuality::Fixed);
+            assert!(rel.moving_base);
+            assert!((rel.length - 1.5).abs() < 1e-6);
+            assert!((rel.heading.unwrap() - 44.971).abs() < 1e-3);
+            assert_eq!(rel.heading_accuracy, None);
+            assert!((rel.pitch - 19.47).abs() < 1e-2);
+        },
+        _ => panic!(),
+    }
+    drop(it);
+
+    // relPosValid with the reserved carrier solution value 3
+    payload[36..40].copy_from_slice(&0x1cu32.to_le_bytes());
+    let bytes = UbxUnknownPacketRef {
+        payload: &payload,
+        class: 0x01,
+        msg_id: 0x3c,
+    }
+    .into_packet_vec();
+    let mut it = parser.consume(&bytes);
+    match it.next() {
+        Some(Ok(PacketRef::NavRelPosNed(pack))) => {
+            let rel = RelativePosition::from(&pack);
+            assert_eq!(rel.quality, RelativePositionQuality::Invalid);
+        },
+        _ => panic!(),
+    }
+}
+
+#[cfg(any(feature = "ubx_proto27", feature = "ubx_proto31"))]
+#[test]
+fn test_nav_relposned_relative_position() {
+    let mut payload = [0u8; 64];
+    payload[0] = 1;
+    payload[2..4].copy_from_slice(&7u16.to_le_bytes());
+    payload[8..12].copy_from_slice(&100i32.to_le_bytes());
+    payload[12..16].copy_from_slice(&100i32.to_le_bytes());
+    payload[16..20].copy_from_slice(&(-50i32).to_le_bytes());
+    payload[20..24].copy_from_slice(&150i32.to_le_bytes());
+    payload[24..28].copy_from_slice(&4_500_000i32.to_le_bytes());
+    payload[32..36].copy_from_slice(&[5, -5i8 as u8, 0, 3]);
+    payload[36..40].copy_from_slice(&100u32.to_le_bytes());
+    payload[48..52].copy_from_slice(&20u32.to_le_bytes());
+    payload[52..56].copy_from_slice(&100_000u32.to_le_bytes());
+    // gnssFixOK, diffSoln, relPosValid, float carrier solution, isMoving, relPosHeadingValid
+    payload[60..64].copy_from_slice(&0x12fu32.to_le_bytes());
+    let bytes = UbxUnknownPacketRef {
+        payload: &payload,
+        class: 0x01,
+        msg_id: 0x3c,
+    }
+    .into_packet_vec();
+
+    let mut parser = Parser::default();
+    let mut it = parser.consume(&bytes);
+    match it.next() {
+        Some(Ok(PacketRef::NavRelPosNed(pack))) => {
+            let rel = RelativePosition::from(&pack);
+            assert_eq!(rel.ref_station_id, 7);
+            assert!((rel.ned.north - 1.0005).abs() < 1e-9);
+            assert_eq!(rel.accuracy.north, 0.01);
+            assert_eq!(rel.quality, RelativePositionQuality::Float);
+            assert!(rel.moving_base);
+            assert!((rel.length - 1.5003).abs() < 1e-9);
+            assert!((rel.length_accuracy.unwrap() - 0.002).abs() < 1e-9);
+            assert!((rel.heading.unwrap() - 45.0).abs() < 1e-9);
+            assert!((rel.heading_accuracy.unwrap() - 1.0).abs() < 1e-9);
+        },
+        _ => panic!(),
+    }
+    drop(it);
+
+    // relPosValid with the reserved carrier solution value 3
+    payload[60..64].copy_from_slice(&0x1cu32.to_le_bytes());
+    let bytes = UbxUnknownPacketRef {
+        payload: &payload,
+        class: 0x01,
+        msg_id: 0x3c,
+    }
+    .into_packet_vec();
+    let mut it = parser.consume(&bytes);
+    match it.next() {
+        Some(Ok(PacketRef::NavRelPosNed(pack))) => {
+            let rel = RelativePosition::from(&pack);
+            assert_eq!(rel.quality, RelativePositionQuality::Invalid);
+        },
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn test_esf_meas_input() {
+    let data = [
+        EsfMeasInputData {
+            data_type: EsfSensorType::Speed,
+            value: SensorData::Value(-1.5),
+        },
+        EsfMeasInputData {
+            data_type: EsfSensorType::RearLeftWheelTicks,
+            value: SensorData::Tick(1234),
+        },
+        EsfMeasInputData {
+            data_type: EsfSensorType::RearRightWheelTicks,
+            value: SensorData::Tick(-1234),
+        },
+    ];
+    let bytes = EsfMeasInputBuilder::new(5000, &data).into_packet_vec();
+    assert_eq!(bytes.len(), 8 + 8 + 12);
+    // Backward ticks have the direction bit set
+    assert_eq!(&bytes[22..26], &[0xd2, 0x04, 0x80, 0x09]);
+
+    let mut parser = Parser::default();
+    let mut it = parser.consume(&bytes);
+    match it.next() {
+        Some(Ok(PacketRef::EsfMeas(pack))) => {
+            assert_eq!(pack.itow(), 5000);
+            assert_eq!(pack.flags().num_meas(), 3);
+            let mut meas = pack.data();
+            let speed = meas.next().unwrap();
+            assert_eq!(speed.data_type, EsfSensorType::Speed);
+            match speed.value() {
+                SensorData::Value(value) => assert!((value + 1.5).abs() < 1e-6),
+                other => panic!("{other:?}"),
+            }
+            let ticks = meas.next().unwrap();
+            assert_eq!(ticks.value(), SensorData::Tick(1234));
+        },
+        _ => panic!(),
+    }
+
+    // A full message fits in the payload
+    let full = [data[1]; EsfMeasInputBuilder::MAX_MEAS];
+    let full_len = EsfMeasInputBuilder::new(5000, &full)
+        .into_packet_vec()
+        .len()
+        - 8;
+    assert_eq!(full_len, usize::from(EsfMeasInput::MAX_PAYLOAD_LEN));
+}
+
+#[test]
+fn test_esf_timeline() {
+    let mut meas = Vec::new();
+    meas.extend_from_slice(&1000u32.to_le_bytes());
+    meas.extend_from_slice(&((1u16 << 11) | (1 << 3)).to_le_bytes());
+    meas.extend_from_slice(&0u16.to_le_bytes());
+    meas.extend_from_slice(&((-2000i32 as u32 & 0xFFFFFF) | (11 << 24)).to_le_bytes());
+    meas.extend_from_slice(&5000u32.to_le_bytes());
+
+    let mut raw = vec![0u8; 4];
+    for (data, tag) in [(0x05FFF000u32, 5100u32), (0x10002800, 5100)] {
+        raw.extend_from_slice(&data.to_le_bytes());
+        raw.extend_from_slice(&tag.to_le_bytes());
+    }
+
+    let mut bytes = Vec::new();
+    for (msg_id, payload) in [(0x03, &raw), (0x02, &meas), (0x03, &raw)] {
+        bytes.extend(
+            UbxUnknownPacketRef {
+                payload,
+                class: 0x10,
+                msg_id,
+            }
+            .into_packet_vec(),
+        );
+    }
+
+    let mut timeline = EsfTimeline::new(1e-3, 24);
+    let mut samples = Vec::new();
+    let mut parser = Parser::default();
+    let mut it = parser.consume(&bytes);
+    while let Some(pack) = it.next() {
+        match pack {
+            Ok(PacketRef::EsfRaw(raw)) => samples.extend(timeline.raw(&raw)),
+            Ok(PacketRef::EsfMeas(meas)) => samples.extend(timeline.meas(&meas)),
+            _ => panic!(),
+        }
+    }
+    assert!(timeline.is_aligned());
+    assert_eq!(samples.len(), 5);
+
+    assert_eq!(samples[0].sensor, EsfSensorType::GyroZ);
+    assert_eq!(samples[0].gnss_time, None);
+    assert!((samples[0].sensor_time.unwrap() - 5.1).abs() < 1e-9);
+    match samples[0].value {
+        SensorValue::AngularRate(rate) => assert!((rate + 1f64.to_radians()).abs() < 1e-12),
+        other => panic!("{other:?}"),
+    }
+    assert_eq!(samples[1].value, SensorValue::Acceleration(10.0));
+
+    assert_eq!(samples[2].sensor, EsfSensorType::Speed);
+    assert_eq!(samples[2].value, SensorValue::Speed(-2.0));
+    assert_eq!(samples[2].gnss_time, Some(1.0));
+    assert!((samples[2].sensor_time.unwrap() - 5.0).abs() < 1e-9);
+
+    assert!((samples[4].gnss_time.unwrap() - 1.1).abs() < 1e-9);
+}
+
+fn esf_status(itow: u32, init: [u8; 2], mode: u8, calibration: u8, faults: u8) -> Vec<u8> {
+    let mut payload = vec![0u8; 16];
+    payload[0..4].copy_from_slice(&itow.to_le_bytes());
+    payload[4] = 2;
+    payload[5..7].copy_from_slice(&init);
+    payload[12] = mode;
+    payload[15] = 1;
+    // Used and ready gyroscope at 100 Hz
+    payload.extend_from_slice(&[0xc5, calibration, 100, faults]);
+    UbxUnknownPacketRef {
+        payload: &payload,
+        class: 0x10,
+        msg_id: 0x10,
+    }
+    .into_packet_vec()
+}
+
+#[test]
+fn test_fusion_monitor() {
+    let mut alg = [0u8; 16];
+    alg[0..4].copy_from_slice(&1500u32.to_le_bytes());
+    alg[4] = 1;
+    // Automatic coarse alignment
+    alg[5] = 0x07;
+    let alg = UbxUnknownPacketRef {
+        payload: &alg,
+        class: 0x10,
+        msg_id: 0x14,
+    }
+    .into_packet_vec();
+
+    let mut monitor = FusionMonitor::default();
+    let mut events = Vec::new();
+    let mut parser = Parser::default();
+    for bytes in [
+        esf_status(1000, [0x25, 0x01], 0, 1, 0),
+        alg,
+        esf_status(61000, [0x4a, 0x02], 1, 2, 0),
+        esf_status(62000, [0x4a, 0x02], 2, 2, 0x08),
+    ] {
+        let mut it = parser.consume(&bytes);
+        let packet = it.next().unwrap().unwrap();
+        monitor.handle(&packet, |event| events.push(event));
+    }
+
+    let gyro = EsfSensorType::GyroZ;
+    assert_eq!(
+        events,
+        [
+            FusionEvent::FusionMode(EsfStatusFusionMode::Initializing),
+            FusionEvent::ImuInit(EsfStatusImuInit::Initializing),
+            FusionEvent::InsInit(EsfStatusInsInit::Initializing),
+            FusionEvent::MountAlignment(EsfStatusMountAngle::Initializing),
+            FusionEvent::WheelTickCalibration(EsfStatusWheelTickInit::Initializing),
+            FusionEvent::SensorCalibration {
+                sensor: gyro,
+                status: EsfSensorStatusCalibration::Calibrating
+            },
+            FusionEvent::AlignmentStatus(EsfAlgStatus::CoarseAlignment),
+            FusionEvent::FusionMode(EsfStatusFusionMode::Fusion),
+            FusionEvent::ImuInit(EsfStatusImuInit::Initialized),
+            FusionEvent::InsInit(EsfStatusInsInit::Initialized),
+            FusionEvent::MountAlignment(EsfStatusMountAngle::Initialized),
+            FusionEvent::WheelTickCalibration(EsfStatusWheelTickInit::Initialized),
+            FusionEvent::SensorCalibration {
+                sensor: gyro,
+                status: EsfSensorStatusCalibration::Calibrated
+            },
+            FusionEvent::Calibrated { after: 60.0 },
+            FusionEvent::FusionMode(EsfStatusFusionMode::Suspended),
+            FusionEvent::SensorFault {
+                sensor: gyro,
+                faults: EsfSensorFaults::NOISY_MEASUREMENT
+            },
+            FusionEvent::CalibrationLost,
+        ]
+    );
+    assert_eq!(monitor.time_to_calibrated(), Some(60.0));
+    assert_eq!(
+        monitor.sensor(gyro),
+        Some((
+            EsfSensorStatusCalibration::Calibrated,
+            EsfSensorFaults::NOISY_MEASUREMENT
+        ))
+    );
+}
+
+#[test]
+fn test_fusion_monitor_reserved_init_status() {
+    let mut monitor = FusionMonitor::default();
+    let mut events = Vec::new();
+    let mut parser = Parser::default();
+    let bytes = esf_status(1000, [0x77, 0x03], 0, 1, 0);
+    let mut it = parser.consume(&bytes);
+    let packet = it.next().unwrap().unwrap();
+    monitor.handle(&packet, |event| events.push(event));
+
+    assert_eq!(
+        events[1..5],
+        [
+            FusionEvent::ImuInit(EsfStatusImuInit::Reserved3),
+            FusionEvent::InsInit(EsfStatusInsInit::Reserved3),
+            FusionEvent::MountAlignment(EsfStatusMountAngle::Reserved5),
+            FusionEvent::WheelTickCalibration(EsfStatusWheelTickInit::Reserved3),
+        ]
+    );
+}
+
+fn ubx_packet(class: u8, msg_id: u8, payload: &[u8]) -> Vec<u8> {
+    UbxUnknownPacketRef {
+        payload,
+        class,
+        msg_id,
+    }
+    .into_packet_vec()
+}
+
+fn mon_hw(noise: u16, agc: u16, jamming: u8, jam_ind: u8) -> Vec<u8> {
+    let mut payload = [0u8; 60];
+    payload[16..18].copy_from_slice(&noise.to_le_bytes());
+    payload[18..20].copy_from_slice(&agc.to_le_bytes());
+    payload[20] = 2;
+    payload[22] = jamming << 2;
+    payload[45] = jam_ind;
+    ubx_packet(0x0a, 0x09, &payload)
+}
+
+fn nav_sat(cno: &[u8]) -> Vec<u8> {
+    let mut payload = vec![0, 0, 0, 0, 1, cno.len() as u8, 0, 0];
+    for (i, &cno) in cno.iter().enumerate() {
+        payload.extend_from_slice(&[0, i as u8 + 1, cno, 30, 0, 0, 0, 0, 0x0f, 0, 0, 0]);
+    }
+    ubx_packet(0x01, 0x35, &payload)
+}
+
+#[test]
+fn test_interference_monitor() {
+    let itfm = (1u32 << 31) | 3 | (15 << 4) | (0x16B156 << 9);
+    let mut itfm_payload = itfm.to_le_bytes().to_vec();
+    itfm_payload.extend_from_slice(&0x31Eu32.to_le_bytes());
+    let mut nav_status = [0u8; 16];
+    let mut epochs = vec![ubx_packet(0x06, 0x39, &itfm_payload)];
+    nav_status[7] = 1 << 3;
+    epochs.extend([
+        mon_hw(80, 5000, 1, 10),
+        nav_sat(&[30, 35, 38, 40, 42, 45, 44, 33]),
+        ubx_packet(0x01, 0x03, &nav_status),
+        ubx_packet(0x01, 0x61, &1000u32.to_le_bytes()),
+    ]);
+    nav_status[7] = 2 << 3;
+    epochs.extend([
+        mon_hw(80, 500, 2, 120),
+        nav_sat(&[45; 8]),
+        ubx_packet(0x01, 0x03, &nav_status),
+        ubx_packet(0x01, 0x61, &2000u32.to_le_bytes()),
+    ]);
+
+    let mut monitor = InterferenceMonitor::default();
+    let mut events = Vec::new();
+    let mut parser = Parser::default();
+    for bytes in epochs {
+        let mut it = parser.consume(&bytes);
+        let packet = it.next().unwrap().unwrap();
+        if let PacketRef::CfgItfm(cfg) = &packet {
+            assert!(cfg.config().enabled());
+            assert_eq!(cfg.config().bb_threshold(), 3);
+            assert_eq!(cfg.config().cw_threshold(), 15);
+        }
+        monitor.handle(&packet, |event| events.push(event));
+    }
+
+    assert_eq!(events[0], InterferenceEvent::Antenna(AntennaStatus::Ok));
+    assert_eq!(events[1], InterferenceEvent::Jamming(ThreatLevel::None));
+    assert_eq!(events[2], InterferenceEvent::Spoofing(ThreatLevel::None));
+    match events[3] {
+        InterferenceEvent::Epoch(epoch) => {
+            assert_eq!(epoch.itow, 1000);
+            assert!(epoch.indications.is_empty());
+            let cno = epoch.cno.unwrap();
+            assert_eq!(cno.satellites, 8);
+            assert!((cno.mean - 38.375).abs() < 1e-9);
+            assert_eq!(cno.max, 45);
+        },
+        other => panic!("{other:?}"),
+    }
+
+    assert_eq!(events[4], InterferenceEvent::Jamming(ThreatLevel::Critical));
+    assert_eq!(
+        events[5],
+        InterferenceEvent::Spoofing(ThreatLevel::Critical)
+    );
+    let latest = monitor.latest().unwrap();
+    assert_eq!(events[6], InterferenceEvent::Epoch(*latest));
+    assert_eq!(events.len(), 7);
+    assert_eq!(
+        latest.indications,
+        InterferenceIndications::RECEIVER_JAMMING
+            | InterferenceIndications::JAM_INDICATOR
+            | InterferenceIndications::AGC
+            | InterferenceIndications::RECEIVER_SPOOFING
+            | InterferenceIndications::UNIFORM_CNO
+    );
+}
+
+#[test]
+#[cfg(any(feature = "ubx_proto27", feature = "ubx_proto31"))]
+fn test_mon_rf_sec_sig() {
+    use ublox::{JammingState, SpoofingState};
+
+    let mut rf = vec![0, 2, 0, 0];
+    for (block_id, jamming, agc, jam_ind) in [(0u8, 1u8, 5000u16, 5u8), (1, 2, 3000, 90)] {
+        let mut block = [0u8; 24];
+        block[0] = block_id;
+        block[1] = jamming;
+        block[2] = 2;
+        block[3] = 1;
+        block[14..16].copy_from_slice(&agc.to_le_bytes());
+        block[16] = jam_ind;
+        rf.extend_from_slice(&block);
+    }
+    let sec_sig_v1 = [1, 0, 0, 0, 0x05, 0, 0, 0, 0x07, 0, 0, 0];
+    let mut sec_sig_v2 = vec![2, 0x2f, 0, 1];
+    sec_sig_v2.extend_from_slice(&(1575420u32 | (1 << 24)).to_le_bytes());
+
+    let mut bytes = ubx_packet(0x0a, 0x38, &rf);
+    bytes.extend(ubx_packet(0x27, 0x09, &sec_sig_v1));
+    bytes.extend(ubx_packet(0x27, 0x09, &sec_sig_v2));
+    let mut parser = Parser::default();
+    let mut it = parser.consume(&bytes);
+    match it.next() {
+        Some(Ok(PacketRef::MonRf(rf))) => {
+            let blocks: Vec<_> = rf.blocks().collect();
+            assert_eq!(blocks.len(), 2);
+            assert_eq!(blocks[0].jamming_state(), JammingState::Ok);
+            assert_eq!(blocks[1].block_id(), 1);
+            assert_eq!(blocks[1].jamming_state(), JammingState::Warning);
+            assert_eq!(blocks[1].agc_cnt(), 3000);
+            assert_eq!(blocks[1].jam_ind(), 90);
+            assert_eq!(blocks[1].ant_status(), AntennaStatus::Ok);
+        },
+        other => panic!("{other:?}"),
+    }
+    match it.next() {
+        Some(Ok(PacketRef::SecSig(sig))) => {
+            assert!(sig.jamming_detection_enabled());
+            assert_eq!(sig.jamming_state(), JammingState::Warning);
+            assert!(sig.spoofing_detection_enabled());
+            assert_eq!(sig.spoofing_state(), SpoofingState::Affirmed);
+            assert_eq!(sig.jamming_frequencies().count(), 0);
+        },
+        other => panic!("{other:?}"),
+    }
+    match it.next() {
+        Some(Ok(PacketRef::SecSig(sig))) => {
+            assert!(sig.jamming_detection_enabled());
+            assert_eq!(sig.jamming_state(), JammingState::Critical);
+            assert!(sig.spoofing_detection_enabled());
+            assert_eq!(sig.spoofing_state(), SpoofingState::Indicated);
+            assert_eq!(
+                sig.jamming_frequencies().collect::<Vec<_>>(),
+                [(1575420, true)]
+            );
+        },
+        other => panic!("{other:?}"),
+    }
+}
+
+#[test]
+#[cfg(any(feature = "ubx_proto27", feature = "ubx_proto31"))]
+fn test_interference_monitor_front_ends() {
+    // One L1 block within the thresholds, antenna status unknown
+    let mut rf = vec![0, 1, 0, 0];
+    let mut block = [0u8; 24];
+    block[1] = 1;
+    block[2] = 1;
+    block[12..14].copy_from_slice(&80u16.to_le_bytes());
+    block[14..16].copy_from_slice(&5000u16.to_le_bytes());
+    block[16] = 5;
+    rf.extend_from_slice(&block);
+    let rf = ubx_packet(0x0a, 0x38, &rf);
+    let hw = mon_hw(80, 5000, 1, 120);
+
+    let mut monitor = InterferenceMonitor::default();
+    let mut events = Vec::new();
+    let mut parser = Parser::default();
+    for bytes in [&hw, &rf, &hw, &rf, &hw] {
+        let mut it = parser.consume(bytes);
+        let packet = it.next().unwrap().unwrap();
+        monitor.handle(&packet, |event| events.push(event));
+    }
+    assert_eq!(
+        events,
+        [
+            InterferenceEvent::Antenna(AntennaStatus::Ok),
+            InterferenceEvent::Antenna(AntennaStatus::DontKnow),
+        ]
+    );
+
+    // MON-HW exceeds the jamming indicator threshold, MON-RF does not
+    let epoch = monitor.end_epoch(1000, |_| {});
+    assert_eq!(epoch.indications, InterferenceIndications::JAM_INDICATOR);
+    assert_eq!(epoch.jamming, ThreatLevel::Warning);
+}
@@ -712,6 +712,7 @@ pub fn generate_code_for_parse(recv_packs: &RecvPackets) -> TokenStream {
     let mut pack_enum_variants = Vec::with_capacity(recv_packs.all_packets.len());
     let mut matches = Vec::with_capacity(recv_packs.all_packets.len());
     let mut class_id_matches = Vec::with_capacity(recv_packs.all_packets.len());
+    let mut payload_matches = Vec::with_capacity(recv_packs.all_packets.len());
     let mut serializers = Vec::with_capacity(recv_packs.all_packets.len());
 
     for name in &recv_packs.all_packets {
@@ -730,6 +731,10 @@ pub fn generate_code_for_parse(recv_packs: &RecvPackets) -> TokenStream {
             #union_enum_name::#name(_) => (#name::CLASS, #name::ID)
         });
 
+        payload_matches.push(quote! {
+            #union_enum_name::#name(ref pack) => pack.0
+        });
+
         serializers.push(quote! {
             #union_enum_name::#name(ref msg) => PacketSerializer {
                 class: #name::CLASS,
@@ -764,6 +769,13 @@ pub fn generate_code_for_parse(recv_packs: &RecvPackets) -> TokenStream {
                     #union_enum_name::Unknown(ref pack) => (pack.class, pack.msg_id),
                 }
             }
+
+            pub fn payload(&self) -> &'a [u8] {
+                match *self {
+                    #(#payload_matches),*,
+                    #union_enum_name::Unknown(ref pack) => pack.payload,
+                }
+            }
         }
 
         pub(crate) fn match_packet(class: u8, msg_id: u8, payload: &[u8]) -> Result<#union_enum_name, ParserError> {
@@ -545,6 +545,14 @@ fn test_define_recv_packets() {
                         PacketRef::Unknown(ref pack) => (pack.class, pack.msg_id),
                     }
                 }
+
+                pub fn payload(&self) -> &'a [u8] {
+                    match *self {
+                        PacketRef::Pack1(ref pack) => pack.0,
+                        PacketRef::Pack2(ref pack) => pack.0,
+                        PacketRef::Unknown(ref pack) => pack.payload,
+                    }
+                }
             }
 
             pub(crate) fn match_packet(